@@ -0,0 +1,4 @@
+-- Add down migration script here
+Drop Table If Exists cash_entries;
+Drop Type If Exists cash_entry_kind;
+Drop Table If Exists accounts;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+Create Table If Not Exists accounts (
+  id Integer Primary Key Generated Always As Identity,
+  uuid Uuid Not Null Unique,
+  user_id Integer Not Null References users (id) On Delete Cascade,
+  name Text Not Null,
+  starting_cash Numeric Not Null,
+  cash Numeric Not Null,
+  created_at Timestamptz Not Null Default now(),
+  Unique (user_id, name)
+);
+
+Create Type cash_entry_kind As Enum ('deposit', 'reset');
+
+Create Table If Not Exists cash_entries (
+  id BigInt Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  kind cash_entry_kind Not Null,
+  amount Numeric Not Null,
+  balance Numeric Not Null,
+  created_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists cash_entries_account_id On cash_entries (account_id);
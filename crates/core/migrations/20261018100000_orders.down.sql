@@ -0,0 +1,5 @@
+-- Add down migration script here
+Drop Table If Exists orders;
+Drop Type If Exists order_status;
+Drop Type If Exists order_type;
+Drop Type If Exists order_side;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+Create Type order_side As Enum ('buy', 'sell');
+
+Create Type order_type As Enum ('market', 'limit');
+
+Create Type order_status As Enum ('open', 'filled', 'cancelled', 'rejected');
+
+Create Table If Not Exists orders (
+  id Integer Primary Key Generated Always As Identity,
+  uuid Uuid Not Null Unique,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  side order_side Not Null,
+  order_type order_type Not Null,
+  quantity Numeric Not Null Check (quantity > 0),
+  limit_price Numeric Check (limit_price > 0),
+  status order_status Not Null Default 'open',
+  filled_quantity Numeric Not Null Default 0,
+  created_at Timestamptz Not Null Default now(),
+  updated_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists orders_account_id On orders (account_id);
+
+Create Index If Not Exists orders_open On orders (status)
+Where
+  status = 'open';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+Drop Table If Exists positions;
+Drop Table If Exists fills;
+-- enum values can't be dropped, so `trade` entries are left as is
@@ -0,0 +1,26 @@
+-- Add up migration script here
+Alter Type cash_entry_kind Add Value If Not Exists 'trade';
+
+Create Table If Not Exists fills (
+  id Integer Primary Key Generated Always As Identity,
+  uuid Uuid Not Null Unique,
+  order_id Integer Not Null References orders (id) On Delete Cascade,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  side order_side Not Null,
+  quantity Numeric Not Null Check (quantity > 0),
+  price Numeric Not Null Check (price > 0),
+  created_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists fills_account_id On fills (account_id);
+
+Create Table If Not Exists positions (
+  id Integer Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  quantity Numeric Not Null,
+  cost_basis Numeric Not Null,
+  updated_at Timestamptz Not Null Default now(),
+  Unique (account_id, symbol)
+);
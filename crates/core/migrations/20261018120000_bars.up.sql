@@ -0,0 +1,11 @@
+-- Add up migration script here
+Create Table If Not Exists bars (
+  symbol Text Not Null,
+  time Timestamptz Not Null,
+  open Numeric Not Null,
+  high Numeric Not Null,
+  low Numeric Not Null,
+  close Numeric Not Null,
+  volume Numeric Not Null,
+  Primary Key (symbol, time)
+);
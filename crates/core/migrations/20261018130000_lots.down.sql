@@ -0,0 +1,5 @@
+-- Add down migration script here
+Drop Table If Exists lots;
+Alter Table positions Drop Column If Exists realized_pnl;
+Alter Table accounts Drop Column If Exists lot_method;
+Drop Type If Exists lot_method;
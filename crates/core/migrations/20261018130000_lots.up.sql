@@ -0,0 +1,47 @@
+-- Add up migration script here
+Create Type lot_method As Enum ('fifo', 'lifo', 'highest_cost');
+
+Alter Table accounts
+Add Column If Not Exists lot_method lot_method Not Null Default 'fifo';
+
+Alter Table positions
+Add Column If Not Exists realized_pnl Numeric Not Null Default 0;
+
+Create Table If Not Exists lots (
+  id Integer Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  fill_id Integer Not Null References fills (id) On Delete Cascade,
+  quantity Numeric Not Null Check (quantity >= 0),
+  price Numeric Not Null,
+  opened_at Timestamptz Not Null
+);
+
+Create Index If Not Exists lots_open On lots (account_id, symbol)
+Where
+  quantity > 0;
+
+-- positions bought before lots existed become a single lot at their average
+-- cost, so they can be relieved like any other
+Insert Into
+  lots (account_id, symbol, fill_id, quantity, price, opened_at)
+Select
+  positions.account_id,
+  positions.symbol,
+  (
+    Select
+      Max(fills.id)
+    From
+      fills
+    Where
+      fills.account_id = positions.account_id
+      And fills.symbol = positions.symbol
+      And fills.side = 'buy'
+  ),
+  positions.quantity,
+  positions.cost_basis / positions.quantity,
+  positions.updated_at
+From
+  positions
+Where
+  positions.quantity > 0;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+Alter Table orders
+Drop Column If Exists triggered_at,
+Drop Column If Exists trail_reference,
+Drop Column If Exists trail_percent,
+Drop Column If Exists trail_amount,
+Drop Column If Exists stop_price;
+-- enum values can't be dropped, so stop order types are left as is
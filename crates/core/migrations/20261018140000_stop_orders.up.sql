@@ -0,0 +1,16 @@
+-- Add up migration script here
+Alter Type order_type Add Value If Not Exists 'stop';
+
+Alter Type order_type Add Value If Not Exists 'stop_limit';
+
+Alter Type order_type Add Value If Not Exists 'trailing_stop';
+
+Alter Table orders
+Add Column If Not Exists stop_price Numeric Check (stop_price > 0),
+Add Column If Not Exists trail_amount Numeric Check (trail_amount > 0),
+Add Column If Not Exists trail_percent Numeric Check (
+  trail_percent > 0
+  And trail_percent < 100
+),
+Add Column If Not Exists trail_reference Numeric,
+Add Column If Not Exists triggered_at Timestamptz;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+Alter Table orders
+Drop Column If Exists expires_at,
+Drop Column If Exists time_in_force;
+Drop Type If Exists time_in_force;
+-- enum values can't be dropped, so `expired` orders are left as is
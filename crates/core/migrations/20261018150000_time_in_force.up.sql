@@ -0,0 +1,13 @@
+-- Add up migration script here
+Create Type time_in_force As Enum ('day', 'gtc', 'ioc', 'fok', 'gtd');
+
+Alter Type order_status Add Value If Not Exists 'expired';
+
+-- orders placed before time in force existed never expired
+Alter Table orders
+Add Column If Not Exists time_in_force time_in_force Not Null Default 'gtc',
+Add Column If Not Exists expires_at Timestamptz;
+
+Alter Table orders
+Alter Column time_in_force
+Drop Default;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+Alter Table orders
+Drop Column If Exists parent_id,
+Drop Column If Exists group_id;
+Drop Table If Exists order_groups;
+Drop Type If Exists order_group_kind;
+-- enum values can't be dropped, so `pending` orders are left as is
@@ -0,0 +1,27 @@
+-- Add up migration script here
+Create Type order_group_kind As Enum ('bracket', 'oco');
+
+-- children of a bracket wait, pending, for their parent to fill
+Alter Type order_status Add Value If Not Exists 'pending';
+
+Create Table If Not Exists order_groups (
+  id Integer Primary Key Generated Always As Identity,
+  uuid Uuid Not Null Unique,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  kind order_group_kind Not Null,
+  created_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists order_groups_account_id On order_groups (account_id);
+
+Alter Table orders
+Add Column If Not Exists group_id Integer References order_groups (id) On Delete Cascade,
+Add Column If Not Exists parent_id Integer References orders (id) On Delete Cascade;
+
+Create Index If Not Exists orders_group_id On orders (group_id)
+Where
+  group_id Is Not Null;
+
+Create Index If Not Exists orders_parent_id On orders (parent_id)
+Where
+  parent_id Is Not Null;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+Alter Table orders
+Add Column If Not Exists extended_hours Boolean Not Null Default False;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+Alter Table positions
+Drop Column If Exists fees;
+Alter Table fills
+Drop Column If Exists fee;
+Alter Table accounts
+Drop Column If Exists fee_schedule_id;
+Drop Table If Exists fee_schedules;
+-- enum values can't be dropped, so `fee` cash entries are left as is
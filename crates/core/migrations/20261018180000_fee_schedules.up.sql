@@ -0,0 +1,61 @@
+-- Add up migration script here
+Create Table If Not Exists fee_schedules (
+  id Integer Primary Key Generated Always As Identity,
+  name Text Not Null Unique,
+  per_share Numeric Not Null Default 0 Check (per_share >= 0),
+  per_trade Numeric Not Null Default 0 Check (per_trade >= 0),
+  percent Numeric Not Null Default 0 Check (percent >= 0),
+  minimum Numeric Not Null Default 0 Check (minimum >= 0),
+  sec_fee_rate Numeric Not Null Default 0 Check (sec_fee_rate >= 0),
+  taf_per_share Numeric Not Null Default 0 Check (taf_per_share >= 0),
+  taf_maximum Numeric Check (taf_maximum >= 0)
+);
+
+-- the regulatory fees are the SEC's section 31 fee & FINRA's trading activity
+-- fee, both charged on sells only
+Insert Into
+  fee_schedules (
+    name,
+    per_share,
+    per_trade,
+    percent,
+    minimum,
+    sec_fee_rate,
+    taf_per_share,
+    taf_maximum
+  )
+Values
+  ('free', 0, 0, 0, 0, 0, 0, Null),
+  ('retail', 0, 0, 0, 0, 0.0000278, 0.000166, 8.30),
+  ('per_share', 0.005, 0, 0, 1.00, 0.0000278, 0.000166, 8.30),
+  ('flat', 0, 4.95, 0, 0, 0.0000278, 0.000166, 8.30),
+  ('percent', 0, 0, 0.25, 5.00, 0.0000278, 0.000166, 8.30)
+On Conflict (name) Do Nothing;
+
+Alter Type cash_entry_kind Add Value If Not Exists 'fee';
+
+Alter Table accounts
+Add Column If Not Exists fee_schedule_id Integer References fee_schedules (id);
+
+Update accounts
+Set
+  fee_schedule_id = (
+    Select
+      id
+    From
+      fee_schedules
+    Where
+      name = 'retail'
+  )
+Where
+  fee_schedule_id Is Null;
+
+Alter Table accounts
+Alter Column fee_schedule_id
+Set Not Null;
+
+Alter Table fills
+Add Column If Not Exists fee Numeric Not Null Default 0 Check (fee >= 0);
+
+Alter Table positions
+Add Column If Not Exists fees Numeric Not Null Default 0;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+Alter Table accounts
+Drop Column If Exists slippage_bps,
+Drop Column If Exists slippage_model;
+Drop Type If Exists slippage_model;
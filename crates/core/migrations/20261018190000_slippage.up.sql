@@ -0,0 +1,6 @@
+-- Add up migration script here
+Create Type slippage_model As Enum ('router', 'last', 'fixed', 'spread', 'volume');
+
+Alter Table accounts
+Add Column If Not Exists slippage_model slippage_model Not Null Default 'router',
+Add Column If Not Exists slippage_bps Numeric Check (slippage_bps >= 0);
@@ -0,0 +1,21 @@
+-- Add down migration script here
+Drop Index If Exists lots_open;
+
+Delete From lots
+Where
+  quantity < 0;
+
+Alter Table lots
+Add Constraint lots_quantity_check Check (quantity >= 0);
+
+Create Index If Not Exists lots_open On lots (account_id, symbol)
+Where
+  quantity > 0;
+
+Alter Table positions
+Drop Column If Exists borrow_fees;
+
+Drop Table If Exists borrow_fees;
+
+Drop Table If Exists borrow_rates;
+-- enum values can't be dropped, so `borrow_fee` cash entries are left as is
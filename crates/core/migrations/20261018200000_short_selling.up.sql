@@ -0,0 +1,53 @@
+-- Add up migration script here
+Create Table If Not Exists borrow_rates (
+  symbol Text Primary Key,
+  easy_to_borrow Boolean Not Null Default True,
+  annual_rate Numeric Not Null Check (annual_rate >= 0),
+  updated_at Timestamptz Not Null Default now()
+);
+
+-- hard to borrow symbols are listed with their rates, but can't be shorted
+-- without a locate, which isn't supported
+Insert Into
+  borrow_rates (symbol, easy_to_borrow, annual_rate)
+Values
+  ('AAPL', True, 0.25),
+  ('AMZN', True, 0.25),
+  ('GOOGL', True, 0.25),
+  ('META', True, 0.25),
+  ('MSFT', True, 0.25),
+  ('NVDA', True, 0.30),
+  ('TSLA', True, 0.50),
+  ('SPY', True, 0.25),
+  ('QQQ', True, 0.25),
+  ('IWM', True, 0.35),
+  ('AMC', False, 35.00),
+  ('GME', False, 25.00)
+On Conflict (symbol) Do Nothing;
+
+Create Table If Not Exists borrow_fees (
+  id Integer Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  date Date Not Null,
+  quantity Numeric Not Null Check (quantity > 0),
+  price Numeric Not Null,
+  annual_rate Numeric Not Null,
+  amount Numeric Not Null Check (amount >= 0),
+  Unique (account_id, symbol, date)
+);
+
+Alter Type cash_entry_kind Add Value If Not Exists 'borrow_fee';
+
+Alter Table positions
+Add Column If Not Exists borrow_fees Numeric Not Null Default 0;
+
+-- short lots hold negative quantities
+Alter Table lots
+Drop Constraint If Exists lots_quantity_check;
+
+Drop Index If Exists lots_open;
+
+Create Index If Not Exists lots_open On lots (account_id, symbol)
+Where
+  quantity <> 0;
@@ -0,0 +1,14 @@
+-- Add down migration script here
+Alter Table orders
+Drop Column If Exists origin;
+
+Alter Table accounts
+Drop Column If Exists margin_call_at,
+Drop Column If Exists auto_liquidate,
+Drop Column If Exists maintenance_margin,
+Drop Column If Exists initial_margin,
+Drop Column If Exists account_type;
+
+Drop Type If Exists order_origin;
+
+Drop Type If Exists account_type;
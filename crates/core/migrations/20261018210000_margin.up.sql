@@ -0,0 +1,36 @@
+-- Add up migration script here
+Create Type account_type As Enum ('cash', 'margin');
+
+Create Type order_origin As Enum ('user', 'liquidation');
+
+-- margins are percentages of the value held, Reg-T's 50% to open & 25% to
+-- keep positions open by default
+Alter Table accounts
+Add Column If Not Exists account_type account_type Not Null Default 'cash',
+Add Column If Not Exists initial_margin Numeric Not Null Default 50 Check (
+  initial_margin > 0
+  And initial_margin <= 100
+),
+Add Column If Not Exists maintenance_margin Numeric Not Null Default 25 Check (
+  maintenance_margin > 0
+  And maintenance_margin <= initial_margin
+),
+Add Column If Not Exists auto_liquidate Boolean Not Null Default False,
+Add Column If Not Exists margin_call_at Timestamptz;
+
+-- only margin accounts may be short
+Update accounts
+Set
+  account_type = 'margin'
+Where
+  Exists (
+    Select
+    From
+      positions
+    Where
+      positions.account_id = accounts.id
+      And positions.quantity < 0
+  );
+
+Alter Table orders
+Add Column If Not Exists origin order_origin Not Null Default 'user';
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- added apart from the journal, since new enum values can't be used in the
+-- transaction adding them
+Alter Type cash_entry_kind Add Value If Not Exists 'opening';
@@ -0,0 +1,10 @@
+-- Add down migration script here
+Drop Table If Exists journal_lines;
+
+Drop Table If Exists journal_entries;
+
+Drop Function If Exists journal_balanced;
+
+Drop Function If Exists journal_append_only;
+
+Drop Type If Exists ledger_account;
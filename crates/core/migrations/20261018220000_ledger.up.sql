@@ -0,0 +1,120 @@
+-- Add up migration script here
+Create Type ledger_account As Enum (
+  'cash',
+  'securities',
+  'capital',
+  'realized_pnl',
+  'fees',
+  'borrow_fees'
+);
+
+Create Table If Not Exists journal_entries (
+  id BigInt Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  kind cash_entry_kind Not Null,
+  fill_id Integer References fills (id) On Delete Cascade,
+  created_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists journal_entries_account_id On journal_entries (account_id);
+
+-- debits are positive & credits negative, so every entry's lines sum to zero
+Create Table If Not Exists journal_lines (
+  id BigInt Primary Key Generated Always As Identity,
+  entry_id BigInt Not Null References journal_entries (id) On Delete Cascade,
+  ledger ledger_account Not Null,
+  symbol Text,
+  amount Numeric Not Null,
+  quantity Numeric Not Null Default 0
+);
+
+Create Index If Not Exists journal_lines_entry_id On journal_lines (entry_id);
+
+-- open the journal with every account's balances so far
+Insert Into
+  journal_entries (account_id, kind)
+Select
+  id,
+  'opening'
+From
+  accounts;
+
+Insert Into
+  journal_lines (entry_id, ledger, amount)
+Select
+  journal_entries.id,
+  'cash',
+  accounts.cash
+From
+  journal_entries
+  Join accounts On accounts.id = journal_entries.account_id;
+
+Insert Into
+  journal_lines (entry_id, ledger, symbol, amount, quantity)
+Select
+  journal_entries.id,
+  balances.ledger::ledger_account,
+  positions.symbol,
+  balances.amount,
+  balances.quantity
+From
+  journal_entries
+  Join positions On positions.account_id = journal_entries.account_id
+  Cross Join Lateral (
+    Values
+      ('securities', positions.cost_basis, positions.quantity),
+      ('realized_pnl', -positions.realized_pnl, 0),
+      ('fees', positions.fees, 0),
+      ('borrow_fees', positions.borrow_fees, 0)
+  ) As balances (ledger, amount, quantity)
+Where
+  balances.amount <> 0
+  Or balances.quantity <> 0;
+
+Insert Into
+  journal_lines (entry_id, ledger, amount)
+Select
+  entry_id,
+  'capital',
+  -Sum(amount)
+From
+  journal_lines
+Group By
+  entry_id;
+
+Create Function journal_append_only () Returns Trigger As $$
+Begin
+  Raise Exception 'the journal is append only';
+End;
+$$ Language plpgsql;
+
+Create Trigger journal_entries_append_only Before
+Update
+Or Delete On journal_entries For Each Statement
+Execute Function journal_append_only ();
+
+Create Trigger journal_lines_append_only Before
+Update
+Or Delete On journal_lines For Each Statement
+Execute Function journal_append_only ();
+
+Create Function journal_balanced () Returns Trigger As $$
+Begin
+  If (
+    Select
+      Sum(amount)
+    From
+      journal_lines
+    Where
+      entry_id = new.entry_id
+  ) <> 0 Then
+    Raise Exception 'journal entry % does not balance', new.entry_id;
+  End If;
+  Return Null;
+End;
+$$ Language plpgsql;
+
+-- checked at commit, once all of an entry's lines are in
+Create Constraint Trigger journal_lines_balanced
+After Insert On journal_lines Deferrable Initially Deferred For Each Row
+Execute Function journal_balanced ();
@@ -0,0 +1,14 @@
+-- Add down migration script here
+Alter Table positions
+Drop Column If Exists dividends;
+
+Alter Table journal_entries
+Drop Column If Exists effective_at;
+
+Drop Table If Exists dividends;
+
+Drop Table If Exists corporate_actions;
+
+Drop Type If Exists corporate_action_kind;
+-- enum values can't be dropped, so `split` & `dividend` journal entries, and
+-- the `dividends` ledger, are left as is
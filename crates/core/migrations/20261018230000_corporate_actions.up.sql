@@ -0,0 +1,64 @@
+-- Add up migration script here
+Create Type corporate_action_kind As Enum ('split', 'dividend');
+
+-- splits give `ratio` new shares for each held, so reverse splits have ratios
+-- below one, & dividends pay `amount` a share
+Create Table If Not Exists corporate_actions (
+  id Integer Primary Key Generated Always As Identity,
+  symbol Text Not Null,
+  kind corporate_action_kind Not Null,
+  ex_date Date Not Null,
+  pay_date Date,
+  ratio Numeric Check (ratio > 0),
+  amount Numeric Check (amount >= 0),
+  applied_at Timestamptz,
+  created_at Timestamptz Not Null Default now(),
+  Unique (symbol, kind, ex_date),
+  Check (
+    (
+      kind = 'split'
+      And ratio Is Not Null
+    )
+    Or (
+      kind = 'dividend'
+      And amount Is Not Null
+      And pay_date >= ex_date
+    )
+  )
+);
+
+Create Index If Not Exists corporate_actions_pending On corporate_actions (ex_date)
+Where
+  applied_at Is Null;
+
+-- what each account is owed by a dividend, from its holding as the symbol
+-- went ex-dividend, negative when short
+Create Table If Not Exists dividends (
+  id Integer Primary Key Generated Always As Identity,
+  action_id Integer Not Null References corporate_actions (id) On Delete Cascade,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  symbol Text Not Null,
+  quantity Numeric Not Null,
+  amount Numeric Not Null,
+  pay_date Date Not Null,
+  paid_at Timestamptz,
+  Unique (action_id, account_id)
+);
+
+Create Index If Not Exists dividends_unpaid On dividends (pay_date)
+Where
+  paid_at Is Null;
+
+-- entries that take effect before they're posted, like splits applied after
+-- their ex-date, record when they did
+Alter Table journal_entries
+Add Column If Not Exists effective_at Timestamptz;
+
+Alter Type cash_entry_kind Add Value If Not Exists 'split';
+
+Alter Type cash_entry_kind Add Value If Not Exists 'dividend';
+
+Alter Type ledger_account Add Value If Not Exists 'dividends';
+
+Alter Table positions
+Add Column If Not Exists dividends Numeric Not Null Default 0;
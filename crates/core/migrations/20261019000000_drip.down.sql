@@ -0,0 +1,10 @@
+-- Add down migration script here
+Alter Table dividends
+Drop Column If Exists order_id;
+
+Alter Table positions
+Drop Column If Exists drip;
+
+Alter Table accounts
+Drop Column If Exists drip;
+-- enum values can't be dropped, so `reinvestment` orders are left as is
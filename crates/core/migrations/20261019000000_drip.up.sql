@@ -0,0 +1,12 @@
+-- Add up migration script here
+Alter Type order_origin Add Value If Not Exists 'reinvestment';
+
+Alter Table accounts
+Add Column If Not Exists drip Boolean Not Null Default False;
+
+-- overrides the account's setting for a symbol, unless null
+Alter Table positions
+Add Column If Not Exists drip Boolean;
+
+Alter Table dividends
+Add Column If Not Exists order_id Integer References orders (id) On Delete Set Null;
@@ -0,0 +1,28 @@
+-- Add up migration script here
+Create Table If Not Exists instruments (
+  symbol Text Primary Key,
+  tick_size Numeric Not Null Check (tick_size > 0),
+  lot_size Numeric Not Null Check (lot_size > 0),
+  fractional Boolean Not Null Default True,
+  updated_at Timestamptz Not Null Default now()
+);
+
+-- symbols that aren't listed trade with the defaults, in cents & whole or
+-- fractional shares
+Insert Into
+  instruments (symbol, tick_size, lot_size, fractional)
+Values
+  ('AAPL', 0.01, 1, True),
+  ('AMZN', 0.01, 1, True),
+  ('GOOGL', 0.01, 1, True),
+  ('META', 0.01, 1, True),
+  ('MSFT', 0.01, 1, True),
+  ('NVDA', 0.01, 1, True),
+  ('TSLA', 0.01, 1, True),
+  ('SPY', 0.01, 1, True),
+  ('QQQ', 0.01, 1, True),
+  ('IWM', 0.01, 1, True),
+  ('AMC', 0.01, 1, False),
+  ('GME', 0.01, 1, False),
+  ('BRK.A', 1, 1, False)
+On Conflict (symbol) Do Nothing;
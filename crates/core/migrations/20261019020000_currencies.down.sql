@@ -0,0 +1,23 @@
+-- Add down migration script here
+Drop Table If Exists conversions;
+
+Alter Table cash_entries
+Drop Column If Exists currency;
+
+Alter Table journal_entries
+Drop Column If Exists currency;
+
+Drop Table If Exists cash_balances;
+
+Alter Table accounts
+Drop Column If Exists fx_spread_bps,
+Drop Column If Exists auto_convert,
+Drop Column If Exists base_currency;
+
+Delete From instruments
+Where
+  currency <> 'USD';
+
+Alter Table instruments
+Drop Column If Exists currency;
+-- enum values can't be dropped, so `conversion` & `exchange` are left as is
@@ -0,0 +1,57 @@
+-- Add up migration script here
+Alter Table instruments
+Add Column If Not Exists currency Text Not Null Default 'USD';
+
+Insert Into
+  instruments (symbol, tick_size, lot_size, fractional, currency)
+Values
+  ('SAP.DE', 0.01, 1, False, 'EUR'),
+  ('SIE.DE', 0.01, 1, False, 'EUR'),
+  ('MC.PA', 0.1, 1, False, 'EUR'),
+  ('ASML.AS', 0.1, 1, False, 'EUR'),
+  ('SHOP.TO', 0.01, 1, False, 'CAD'),
+  ('RY.TO', 0.01, 1, False, 'CAD'),
+  ('TD.TO', 0.01, 1, False, 'CAD'),
+  ('ENB.TO', 0.01, 1, False, 'CAD')
+On Conflict (symbol) Do Nothing;
+
+-- an account's cash is held in its base currency, and whatever it holds in
+-- others is kept apart
+Alter Table accounts
+Add Column If Not Exists base_currency Text Not Null Default 'USD',
+Add Column If Not Exists auto_convert Boolean Not Null Default True,
+Add Column If Not Exists fx_spread_bps Numeric Not Null Default 25 Check (fx_spread_bps >= 0);
+
+Create Table If Not Exists cash_balances (
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  currency Text Not Null,
+  amount Numeric Not Null,
+  updated_at Timestamptz Not Null Default now(),
+  Primary Key (account_id, currency)
+);
+
+-- every line of an entry is in its currency, so entries still balance
+Alter Table journal_entries
+Add Column If Not Exists currency Text Not Null Default 'USD';
+
+Alter Table cash_entries
+Add Column If Not Exists currency Text Not Null Default 'USD';
+
+Alter Type cash_entry_kind Add Value If Not Exists 'conversion';
+
+Alter Type ledger_account Add Value If Not Exists 'exchange';
+
+Create Table If Not Exists conversions (
+  id Integer Primary Key Generated Always As Identity,
+  uuid Uuid Not Null Unique,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  fill_id Integer References fills (id) On Delete Set Null,
+  from_currency Text Not Null,
+  to_currency Text Not Null Check (to_currency <> from_currency),
+  amount Numeric Not Null Check (amount > 0),
+  rate Numeric Not Null Check (rate > 0),
+  converted Numeric Not Null Check (converted >= 0),
+  created_at Timestamptz Not Null Default now()
+);
+
+Create Index If Not Exists conversions_account On conversions (account_id, id);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+Alter Table instruments
+Drop Column If Exists active,
+Drop Column If Exists shortable,
+Drop Column If Exists asset_class,
+Drop Column If Exists exchange,
+Drop Column If Exists name;
+
+Drop Type If Exists asset_class;
@@ -0,0 +1,10 @@
+-- Add up migration script here
+Create Type asset_class As Enum ('equity', 'etf');
+
+-- the registry itself is seeded from the bundled `data/instruments.csv`
+Alter Table instruments
+Add Column If Not Exists name Text Not Null Default '',
+Add Column If Not Exists exchange Text Not Null Default 'XNYS',
+Add Column If Not Exists asset_class asset_class Not Null Default 'equity',
+Add Column If Not Exists shortable Boolean Not Null Default True,
+Add Column If Not Exists active Boolean Not Null Default True;
@@ -0,0 +1,19 @@
+-- Add down migration script here
+Alter Table fills
+Drop Constraint If Exists fills_price_check;
+
+Alter Table fills
+Add Constraint fills_price_check Check (price > 0) Not Valid;
+
+Drop Index If Exists instruments_expires_on;
+
+Alter Table instruments
+Drop Column If Exists expires_on,
+Drop Column If Exists strike,
+Drop Column If Exists option_type,
+Drop Column If Exists underlying,
+Drop Column If Exists multiplier;
+
+Drop Type If Exists option_type;
+-- enum values can't be dropped, so `option` contracts & `exercise`,
+-- `assignment` & `expiration` orders are left as is
@@ -0,0 +1,31 @@
+-- Add up migration script here
+Alter Type asset_class Add Value If Not Exists 'option';
+
+Alter Type order_origin Add Value If Not Exists 'exercise';
+
+Alter Type order_origin Add Value If Not Exists 'assignment';
+
+Alter Type order_origin Add Value If Not Exists 'expiration';
+
+Create Type option_type As Enum ('call', 'put');
+
+-- contracts are listed as their chains are first asked for, by their OCC
+-- symbols
+Alter Table instruments
+Add Column If Not Exists multiplier Numeric Not Null Default 1 Check (multiplier > 0),
+Add Column If Not Exists underlying Text References instruments (symbol),
+Add Column If Not Exists option_type option_type,
+Add Column If Not Exists strike Numeric Check (strike > 0),
+Add Column If Not Exists expires_on Date;
+
+Create Index If Not Exists instruments_expires_on On instruments (expires_on)
+Where
+  active
+  And expires_on Is Not Null;
+
+-- contracts that expire worthless are closed at no price
+Alter Table fills
+Drop Constraint If Exists fills_price_check;
+
+Alter Table fills
+Add Constraint fills_price_check Check (price >= 0);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+Alter Table order_groups
+Drop Column If Exists quantity,
+Drop Column If Exists limit_price;
+
+-- enum values can't be dropped, so `multi_leg` groups are left as is
@@ -0,0 +1,9 @@
+-- Add up migration script here
+Alter Type order_group_kind Add Value If Not Exists 'multi_leg';
+
+-- a multi-leg group trades its legs as a unit, `quantity` of them at a net
+-- price no worse than `limit_price`, a debit when positive & a credit when
+-- negative
+Alter Table order_groups
+Add Column If Not Exists quantity Numeric Check (quantity > 0),
+Add Column If Not Exists limit_price Numeric;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+Alter Table instruments
+Drop Column If Exists settled_on,
+Drop Column If Exists roll_on,
+Drop Column If Exists maintenance_margin,
+Drop Column If Exists initial_margin;
+
+-- enum values can't be dropped, so `future` contracts, `settlement` orders,
+-- `variation_margin` entries & `notional` ledgers are left as is
@@ -0,0 +1,20 @@
+-- Add up migration script here
+Alter Type asset_class Add Value If Not Exists 'future';
+
+Alter Type order_origin Add Value If Not Exists 'settlement';
+
+Alter Type cash_entry_kind Add Value If Not Exists 'variation_margin';
+
+-- futures are bought & sold without paying for what they're worth, which
+-- this balances in the journal
+Alter Type ledger_account Add Value If Not Exists 'notional';
+
+-- margins are per contract, in the contract's currency
+Alter Table instruments
+Add Column If Not Exists initial_margin Numeric Check (initial_margin > 0),
+Add Column If Not Exists maintenance_margin Numeric Check (
+  maintenance_margin > 0
+  And maintenance_margin <= initial_margin
+),
+Add Column If Not Exists roll_on Date,
+Add Column If Not Exists settled_on Date;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+Alter Type asset_class Add Value If Not Exists 'crypto';
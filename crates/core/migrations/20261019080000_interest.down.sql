@@ -0,0 +1,8 @@
+-- Add down migration script here
+Drop Table If Exists interest_accruals;
+
+Drop Table If Exists interest_rates;
+
+Drop Type If Exists interest_kind;
+
+-- enum values can't be dropped, so `interest` entries & ledgers are left as is
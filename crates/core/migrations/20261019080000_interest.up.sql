@@ -0,0 +1,54 @@
+-- Add up migration script here
+Create Type interest_kind As Enum ('credit', 'debit');
+
+-- a rate applies from its effective date until the next one for its currency
+-- & kind takes effect
+Create Table If Not Exists interest_rates (
+  id Integer Primary Key Generated Always As Identity,
+  currency Text Not Null,
+  kind interest_kind Not Null,
+  effective_on Date Not Null,
+  annual_rate Numeric Not Null Check (annual_rate >= 0),
+  Unique (currency, kind, effective_on)
+);
+
+Insert Into
+  interest_rates (currency, kind, effective_on, annual_rate)
+Values
+  ('USD', 'credit', '2024-01-01', 4.50),
+  ('USD', 'credit', '2024-12-19', 3.75),
+  ('USD', 'credit', '2025-09-18', 3.50),
+  ('USD', 'debit', '2024-01-01', 8.50),
+  ('USD', 'debit', '2024-12-19', 7.75),
+  ('USD', 'debit', '2025-09-18', 7.50),
+  ('EUR', 'credit', '2024-01-01', 3.00),
+  ('EUR', 'credit', '2025-06-11', 1.50),
+  ('EUR', 'debit', '2024-01-01', 6.00),
+  ('EUR', 'debit', '2025-06-11', 4.50),
+  ('GBP', 'credit', '2024-01-01', 4.25),
+  ('GBP', 'credit', '2025-08-07', 3.25),
+  ('GBP', 'debit', '2024-01-01', 8.25),
+  ('GBP', 'debit', '2025-08-07', 7.25)
+On Conflict (currency, kind, effective_on) Do Nothing;
+
+-- accruals are kept to fractions of a cent, and posted to the journal a month at a time
+Create Table If Not Exists interest_accruals (
+  id Integer Primary Key Generated Always As Identity,
+  account_id Integer Not Null References accounts (id) On Delete Cascade,
+  currency Text Not Null,
+  date Date Not Null,
+  kind interest_kind Not Null,
+  balance Numeric Not Null,
+  annual_rate Numeric Not Null,
+  amount Numeric Not Null,
+  posted_on Date,
+  Unique (account_id, currency, date)
+);
+
+Create Index If Not Exists interest_accruals_unposted On interest_accruals (account_id, currency)
+Where
+  posted_on Is Null;
+
+Alter Type cash_entry_kind Add Value If Not Exists 'interest';
+
+Alter Type ledger_account Add Value If Not Exists 'interest';
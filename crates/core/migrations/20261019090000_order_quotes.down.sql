@@ -0,0 +1,3 @@
+-- Add down migration script here
+Alter Table orders
+Drop Column If Exists quoted_price;
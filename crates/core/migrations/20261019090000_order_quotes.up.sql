@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- what a market order was quoted at as it was placed, reserved while it's open
+Alter Table orders
+Add Column If Not Exists quoted_price Numeric Check (quoted_price > 0);
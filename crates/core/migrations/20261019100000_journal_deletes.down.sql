@@ -0,0 +1,10 @@
+-- Add down migration script here
+Alter Table journal_entries
+Drop Constraint If Exists journal_entries_fill_id_fkey,
+Add Constraint journal_entries_fill_id_fkey Foreign Key (fill_id) References fills (id) On Delete Cascade;
+
+Create Or Replace Function journal_append_only () Returns Trigger As $$
+Begin
+  Raise Exception 'the journal is append only';
+End;
+$$ Language plpgsql;
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- the journal can't be edited, but goes with the account it belongs to when
+-- that's deleted, cascading from its user
+Create Or Replace Function journal_append_only () Returns Trigger As $$
+Begin
+  If tg_op = 'DELETE' And pg_trigger_depth() > 1 Then
+    Return Null;
+  End If;
+  Raise Exception 'the journal is append only';
+End;
+$$ Language plpgsql;
+
+-- fills can't be deleted out from under the entries that posted them, only
+-- along with their account
+Alter Table journal_entries
+Drop Constraint If Exists journal_entries_fill_id_fkey,
+Add Constraint journal_entries_fill_id_fkey Foreign Key (fill_id) References fills (id);
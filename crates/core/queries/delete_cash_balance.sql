@@ -0,0 +1,6 @@
+Delete From cash_balances
+Where
+  account_id = $1
+  And currency = $2
+Returning
+  amount
@@ -0,0 +1,5 @@
+Delete From
+  dividends
+Where
+  account_id = $1
+  And paid_at Is Null
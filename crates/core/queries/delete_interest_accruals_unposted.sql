@@ -0,0 +1,5 @@
+Delete From
+  interest_accruals
+Where
+  account_id = $1
+  And posted_on Is Null
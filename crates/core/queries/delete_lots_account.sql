@@ -0,0 +1,4 @@
+Delete From
+  lots
+Where
+  account_id = $1
@@ -0,0 +1,4 @@
+Delete From
+  positions
+Where
+  account_id = $1
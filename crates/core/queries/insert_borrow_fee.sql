@@ -0,0 +1,15 @@
+Insert Into
+  borrow_fees (
+    account_id,
+    symbol,
+    date,
+    quantity,
+    price,
+    annual_rate,
+    amount
+  )
+Values
+  ($1, $2, $3, $4, $5, $6, $7)
+On Conflict (account_id, symbol, date) Do Nothing
+Returning
+  id
@@ -0,0 +1,12 @@
+Insert Into
+  cash_entries (account_id, kind, currency, amount, balance)
+Values
+  ($1, $2, $3, $4, $5)
+Returning
+  id,
+  account_id,
+  kind As "kind: CashEntryKind",
+  currency,
+  amount As "amount: Money",
+  balance As "balance: Money",
+  created_at
@@ -0,0 +1,24 @@
+Insert Into
+  conversions (
+    uuid,
+    account_id,
+    fill_id,
+    from_currency,
+    to_currency,
+    amount,
+    rate,
+    converted
+  )
+Values
+  ($1, $2, $3, $4, $5, $6, $7, $8)
+Returning
+  id,
+  uuid,
+  account_id,
+  fill_id,
+  from_currency,
+  to_currency,
+  amount As "amount: Money",
+  rate,
+  converted As "converted: Money",
+  created_at
@@ -0,0 +1,28 @@
+Insert Into
+  dividends (
+    action_id,
+    account_id,
+    symbol,
+    quantity,
+    amount,
+    pay_date
+  )
+Select
+  $1,
+  journal_entries.account_id,
+  $2,
+  Sum(journal_lines.quantity),
+  Round(Sum(journal_lines.quantity) * $3, 2),
+  $4
+From
+  journal_lines
+  Join journal_entries On journal_entries.id = journal_lines.entry_id
+Where
+  journal_lines.ledger = 'securities'
+  And journal_lines.symbol = $2
+  And Coalesce(journal_entries.effective_at, journal_entries.created_at) < $5
+Group By
+  journal_entries.account_id
+Having
+  Sum(journal_lines.quantity) <> 0
+On Conflict (action_id, account_id) Do Nothing
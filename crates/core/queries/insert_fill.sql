@@ -0,0 +1,24 @@
+Insert Into
+  fills (
+    uuid,
+    order_id,
+    account_id,
+    symbol,
+    side,
+    quantity,
+    price,
+    fee
+  )
+Values
+  ($1, $2, $3, $4, $5, $6, $7, $8)
+Returning
+  id,
+  uuid,
+  order_id,
+  account_id,
+  symbol,
+  side As "side: OrderSide",
+  quantity As "quantity: Quantity",
+  price As "price: Money",
+  fee As "fee: Money",
+  created_at
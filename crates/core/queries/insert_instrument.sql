@@ -0,0 +1,43 @@
+Insert Into
+  instruments (
+    symbol,
+    name,
+    exchange,
+    asset_class,
+    currency,
+    tick_size,
+    lot_size,
+    fractional,
+    shortable,
+    active,
+    multiplier,
+    underlying,
+    option_type,
+    strike,
+    expires_on,
+    initial_margin,
+    maintenance_margin,
+    roll_on
+  )
+Values
+  (
+    $1,
+    $2,
+    $3,
+    $4,
+    $5,
+    $6,
+    $7,
+    $8,
+    $9,
+    $10,
+    $11,
+    $12,
+    $13,
+    $14,
+    $15,
+    $16,
+    $17,
+    $18
+  )
+On Conflict (symbol) Do Nothing
@@ -0,0 +1,15 @@
+Insert Into
+  interest_accruals (
+    account_id,
+    currency,
+    date,
+    kind,
+    balance,
+    annual_rate,
+    amount
+  )
+Values
+  ($1, $2, $3, $4, $5, $6, $7)
+On Conflict (account_id, currency, date) Do Nothing
+Returning
+  id
@@ -0,0 +1,6 @@
+Insert Into
+  journal_entries (account_id, kind, fill_id, effective_at, currency)
+Values
+  ($1, $2, $3, $4, $5)
+Returning
+  id
@@ -0,0 +1,4 @@
+Insert Into
+  journal_lines (entry_id, ledger, symbol, amount, quantity)
+Values
+  ($1, $2, $3, $4, $5)
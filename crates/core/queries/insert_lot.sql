@@ -0,0 +1,12 @@
+Insert Into
+  lots (account_id, symbol, fill_id, quantity, price, opened_at)
+Values
+  ($1, $2, $3, $4, $5, $6)
+Returning
+  id,
+  account_id,
+  symbol,
+  fill_id,
+  quantity As "quantity: Quantity",
+  price As "price: Money",
+  opened_at
@@ -0,0 +1,68 @@
+Insert Into
+  orders (
+    uuid,
+    account_id,
+    symbol,
+    side,
+    order_type,
+    quantity,
+    limit_price,
+    stop_price,
+    trail_amount,
+    trail_percent,
+    trail_reference,
+    time_in_force,
+    expires_at,
+    group_id,
+    parent_id,
+    status,
+    extended_hours,
+    origin,
+    quoted_price
+  )
+Values
+  (
+    $1,
+    $2,
+    $3,
+    $4,
+    $5,
+    $6,
+    $7,
+    $8,
+    $9,
+    $10,
+    $11,
+    $12,
+    $13,
+    $14,
+    $15,
+    $16,
+    $17,
+    $18,
+    $19
+  )
+Returning
+  id,
+  uuid,
+  account_id,
+  symbol,
+  side As "side: OrderSide",
+  order_type As "order_type: OrderType",
+  quantity As "quantity: Quantity",
+  limit_price As "limit_price: Money",
+  stop_price As "stop_price: Money",
+  trail_amount As "trail_amount: Money",
+  trail_percent,
+  trail_reference As "trail_reference: Money",
+  triggered_at,
+  time_in_force As "time_in_force: TimeInForce",
+  expires_at,
+  extended_hours,
+  group_id,
+  parent_id,
+  origin As "origin: OrderOrigin",
+  status As "status: OrderStatus",
+  filled_quantity As "filled_quantity: Quantity",
+  created_at,
+  updated_at
@@ -0,0 +1,12 @@
+Insert Into
+  order_groups (uuid, account_id, kind, quantity, limit_price)
+Values
+  ($1, $2, $3, $4, $5)
+Returning
+  id,
+  uuid,
+  account_id,
+  kind As "kind: OrderGroupKind",
+  quantity As "quantity: Quantity",
+  limit_price As "limit_price: Money",
+  created_at
@@ -0,0 +1,6 @@
+Select
+  id,
+  base_currency,
+  cash
+From
+  accounts
@@ -0,0 +1,34 @@
+Select
+  id,
+  uuid,
+  user_id,
+  name,
+  starting_cash As "starting_cash: Money",
+  cash As "cash: Money",
+  account_type As "account_type: AccountType",
+  initial_margin,
+  maintenance_margin,
+  auto_liquidate,
+  drip,
+  base_currency,
+  auto_convert,
+  fx_spread_bps,
+  margin_call_at,
+  lot_method As "lot_method: LotMethod",
+  slippage_model As "slippage_model: SlippageKind",
+  slippage_bps,
+  fee_schedule_id,
+  (
+    Select
+      name
+    From
+      fee_schedules
+    Where
+      fee_schedules.id = accounts.fee_schedule_id
+  ) As "fee_schedule!",
+  created_at
+From
+  accounts
+Where
+  id = $1
+For Update
@@ -0,0 +1,47 @@
+-- accounts still flagged are included, so their calls are cleared once met
+Select
+  id,
+  uuid,
+  user_id,
+  name,
+  starting_cash As "starting_cash: Money",
+  cash As "cash: Money",
+  account_type As "account_type: AccountType",
+  initial_margin,
+  maintenance_margin,
+  auto_liquidate,
+  drip,
+  base_currency,
+  auto_convert,
+  fx_spread_bps,
+  margin_call_at,
+  lot_method As "lot_method: LotMethod",
+  slippage_model As "slippage_model: SlippageKind",
+  slippage_bps,
+  fee_schedule_id,
+  (
+    Select
+      name
+    From
+      fee_schedules
+    Where
+      fee_schedules.id = accounts.fee_schedule_id
+  ) As "fee_schedule!",
+  created_at
+From
+  accounts
+Where
+  account_type = 'margin'
+  And (
+    margin_call_at Is Not Null
+    Or Exists (
+      Select
+      From
+        positions
+      Where
+        positions.account_id = accounts.id
+        And positions.quantity <> 0
+    )
+  )
+Order By
+  id
@@ -0,0 +1,16 @@
+Select
+  time,
+  open,
+  high,
+  low,
+  close,
+  volume
+From
+  bars
+Where
+  symbol = $1
+  And time <= $2
+Order By
+  time Desc
+Limit
+  1
@@ -0,0 +1,15 @@
+Select
+  time,
+  open,
+  high,
+  low,
+  close,
+  volume
+From
+  bars
+Where
+  symbol = $1
+  And time >= $2
+  And time < $3
+Order By
+  time
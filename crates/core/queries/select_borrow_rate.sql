@@ -0,0 +1,6 @@
+Select
+  *
+From
+  borrow_rates
+Where
+  symbol = $1
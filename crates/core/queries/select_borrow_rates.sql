@@ -0,0 +1,6 @@
+Select
+  *
+From
+  borrow_rates
+Order By
+  symbol
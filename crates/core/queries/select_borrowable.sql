@@ -0,0 +1,18 @@
+-- writing an option contract or selling a future borrows nothing, so they can
+-- always be sold short
+Select
+  Exists (
+    Select
+    From
+      instruments
+      Left Join borrow_rates Using (symbol)
+    Where
+      symbol = $1
+      And (
+        asset_class In ('option', 'future')
+        Or (
+          easy_to_borrow
+          And shortable
+        )
+      )
+  ) As "borrowable!"
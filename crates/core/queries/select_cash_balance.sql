@@ -0,0 +1,7 @@
+Select
+  amount
+From
+  cash_balances
+Where
+  account_id = $1
+  And currency = $2
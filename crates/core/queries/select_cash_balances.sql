@@ -0,0 +1,11 @@
+Select
+  account_id,
+  currency,
+  amount As "amount: Money"
+From
+  cash_balances
+Where
+  account_id = $1
+  And amount <> 0
+Order By
+  currency
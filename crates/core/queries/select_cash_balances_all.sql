@@ -0,0 +1,6 @@
+Select
+  account_id,
+  currency,
+  amount As "amount: Money"
+From
+  cash_balances
@@ -0,0 +1,78 @@
+-- the cash each account held in each currency at the close of every day, in
+-- UTC, since it last accrued interest there & through $1, as the journal has
+-- it, skipping days it held none
+With
+  changes As (
+    Select
+      journal_entries.account_id,
+      journal_entries.currency,
+      (
+        Coalesce(
+          journal_entries.effective_at,
+          journal_entries.created_at
+        ) At Time Zone 'UTC'
+      )::Date As date,
+      Sum(journal_lines.amount) As amount
+    From
+      journal_entries
+      Join journal_lines On journal_lines.entry_id = journal_entries.id
+    Where
+      journal_lines.ledger = 'cash'
+    Group By
+      1,
+      2,
+      3
+  ),
+  balances As (
+    Select
+      account_id,
+      currency,
+      date,
+      Lead(date) Over (
+        Partition By
+          account_id,
+          currency
+        Order By
+          date
+      ) As until,
+      Sum(amount) Over (
+        Partition By
+          account_id,
+          currency
+        Order By
+          date
+      ) As amount
+    From
+      changes
+  ),
+  accrued As (
+    Select
+      account_id,
+      currency,
+      Max(date) As date
+    From
+      interest_accruals
+    Group By
+      1,
+      2
+  )
+Select
+  balances.account_id As "account_id!",
+  balances.currency As "currency!",
+  days.date::Date As "date!",
+  balances.amount As "amount!"
+From
+  balances
+  Left Join accrued On accrued.account_id = balances.account_id
+  And accrued.currency = balances.currency
+  Cross Join Lateral generate_series(
+    Greatest(balances.date, accrued.date + 1)::Timestamp,
+    Least(balances.until - 1, $1::Date)::Timestamp,
+    '1 day'
+  ) As days (date)
+Where
+  balances.amount <> 0
+Order By
+  3,
+  1,
+  2
@@ -0,0 +1,14 @@
+Select
+  id,
+  account_id,
+  kind As "kind: CashEntryKind",
+  currency,
+  amount As "amount: Money",
+  balance As "balance: Money",
+  created_at
+From
+  cash_entries
+Where
+  account_id = $1
+Order By
+  id
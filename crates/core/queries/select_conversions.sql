@@ -0,0 +1,17 @@
+Select
+  id,
+  uuid,
+  account_id,
+  fill_id,
+  from_currency,
+  to_currency,
+  amount As "amount: Money",
+  rate,
+  converted As "converted: Money",
+  created_at
+From
+  conversions
+Where
+  account_id = $1
+Order By
+  id Desc
@@ -0,0 +1,15 @@
+Select
+  id,
+  symbol,
+  kind As "kind: CorporateActionKind",
+  ex_date,
+  pay_date,
+  ratio As "ratio: Money",
+  amount As "amount: Money",
+  applied_at
+From
+  corporate_actions
+Where
+  id = $1
+  And applied_at Is Null
+For Update
@@ -0,0 +1,17 @@
+Select
+  id,
+  symbol,
+  kind As "kind: CorporateActionKind",
+  ex_date,
+  pay_date,
+  ratio As "ratio: Money",
+  amount As "amount: Money",
+  applied_at
+From
+  corporate_actions
+Where
+  $1::Text Is Null
+  Or symbol = $1
+Order By
+  ex_date,
+  symbol
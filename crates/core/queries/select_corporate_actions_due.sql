@@ -0,0 +1,17 @@
+Select
+  id,
+  symbol,
+  kind As "kind: CorporateActionKind",
+  ex_date,
+  pay_date,
+  ratio As "ratio: Money",
+  amount As "amount: Money",
+  applied_at
+From
+  corporate_actions
+Where
+  applied_at Is Null
+  And ex_date <= $1
+Order By
+  ex_date,
+  id
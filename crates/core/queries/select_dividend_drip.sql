@@ -0,0 +1,8 @@
+Select
+  Coalesce(positions.drip, accounts.drip) As "drip!"
+From
+  accounts
+  Left Join positions On positions.account_id = accounts.id
+  And positions.symbol = $2
+Where
+  accounts.id = $1
@@ -0,0 +1,17 @@
+Select
+  id,
+  action_id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  amount As "amount: Money",
+  pay_date,
+  paid_at,
+  order_id
+From
+  dividends
+Where
+  paid_at Is Null
+  And pay_date <= $1
+Order By
+  id
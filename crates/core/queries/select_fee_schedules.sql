@@ -0,0 +1,14 @@
+Select
+  id,
+  name,
+  per_share,
+  per_trade,
+  percent,
+  minimum,
+  sec_fee_rate,
+  taf_per_share,
+  taf_maximum
+From
+  fee_schedules
+Order By
+  id
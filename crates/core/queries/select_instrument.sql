@@ -0,0 +1,24 @@
+Select
+  symbol,
+  name,
+  exchange,
+  asset_class As "asset_class: AssetClass",
+  currency,
+  tick_size As "tick_size: Money",
+  lot_size As "lot_size: Quantity",
+  fractional,
+  shortable,
+  active,
+  multiplier As "multiplier: Quantity",
+  underlying,
+  option_type As "option_type: OptionType",
+  strike As "strike: Money",
+  expires_on,
+  initial_margin As "initial_margin: Money",
+  maintenance_margin As "maintenance_margin: Money",
+  roll_on,
+  settled_on
+From
+  instruments
+Where
+  symbol = $1
@@ -0,0 +1,39 @@
+-- exact symbols first, then symbols starting with the query, then names
+-- containing it, leaving out option contracts unless they're exact
+Select
+  symbol,
+  name,
+  exchange,
+  asset_class As "asset_class: AssetClass",
+  currency,
+  tick_size As "tick_size: Money",
+  lot_size As "lot_size: Quantity",
+  fractional,
+  shortable,
+  active,
+  multiplier As "multiplier: Quantity",
+  underlying,
+  option_type As "option_type: OptionType",
+  strike As "strike: Money",
+  expires_on,
+  initial_margin As "initial_margin: Money",
+  maintenance_margin As "maintenance_margin: Money",
+  roll_on,
+  settled_on
+From
+  instruments
+Where
+  (
+    symbol Ilike $1 || '%'
+    Or name Ilike '%' || $1 || '%'
+  )
+  And (
+    asset_class <> 'option'
+    Or Upper(symbol) = Upper($1)
+  )
+Order By
+  Upper(symbol) <> Upper($1),
+  symbol Not Ilike $1 || '%',
+  symbol
+Limit
+  $2
@@ -0,0 +1,17 @@
+Select
+  id,
+  account_id,
+  currency,
+  date,
+  kind As "kind: InterestKind",
+  balance,
+  annual_rate,
+  amount,
+  posted_on
+From
+  interest_accruals
+Where
+  account_id = $1
+Order By
+  date Desc,
+  currency
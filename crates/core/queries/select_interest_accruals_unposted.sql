@@ -0,0 +1,15 @@
+Select
+  account_id,
+  currency,
+  Round(Sum(amount), 16) As "amount!: Money"
+From
+  interest_accruals
+Where
+  posted_on Is Null
+  And date < $1
+Group By
+  account_id,
+  currency
+Order By
+  account_id,
+  currency
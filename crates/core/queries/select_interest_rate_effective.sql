@@ -0,0 +1,16 @@
+Select
+  id,
+  currency,
+  kind As "kind: InterestKind",
+  effective_on,
+  annual_rate
+From
+  interest_rates
+Where
+  currency = $1
+  And kind = $2
+  And effective_on <= $3
+Order By
+  effective_on Desc
+Limit
+  1
@@ -0,0 +1,12 @@
+Select
+  id,
+  currency,
+  kind As "kind: InterestKind",
+  effective_on,
+  annual_rate
+From
+  interest_rates
+Order By
+  currency,
+  kind,
+  effective_on Desc
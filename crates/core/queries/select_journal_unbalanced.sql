@@ -0,0 +1,11 @@
+Select
+  journal_entries.id,
+  journal_entries.account_id,
+  Sum(journal_lines.amount) As "amount!"
+From
+  journal_entries
+  Join journal_lines On journal_lines.entry_id = journal_entries.id
+Group By
+  journal_entries.id
+Having
+  Sum(journal_lines.amount) <> 0
@@ -0,0 +1,18 @@
+Select
+  journal_entries.account_id,
+  journal_lines.ledger As "ledger: LedgerAccount",
+  journal_lines.symbol,
+  journal_entries.currency,
+  Sum(journal_lines.amount) As "amount!",
+  Sum(journal_lines.quantity) As "quantity!"
+From
+  journal_lines
+  Join journal_entries On journal_entries.id = journal_lines.entry_id
+Where
+  $1::Integer Is Null
+  Or journal_entries.account_id = $1
+Group By
+  journal_entries.account_id,
+  journal_lines.ledger,
+  journal_lines.symbol,
+  journal_entries.currency
@@ -0,0 +1,11 @@
+Select
+  account_id,
+  symbol,
+  Sum(quantity) As "quantity!"
+From
+  lots
+Where
+  quantity <> 0
+Group By
+  account_id,
+  symbol
@@ -0,0 +1,18 @@
+Select
+  id,
+  account_id,
+  symbol,
+  fill_id,
+  quantity As "quantity: Quantity",
+  price As "price: Money",
+  opened_at
+From
+  lots
+Where
+  account_id = $1
+  And symbol = $2
+  And quantity <> 0
+Order By
+  opened_at,
+  id
+For Update
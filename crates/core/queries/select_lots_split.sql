@@ -0,0 +1,33 @@
+-- what each account's lots gain by a split, kept to whole lots of $4 unless
+-- it's null, & what's left over to be paid in cash instead, at its cost
+With
+  split As (
+    Select
+      account_id,
+      quantity,
+      price,
+      Trim_Scale(Round(quantity * $3, 8)) As split_quantity,
+      Trim_Scale(Round(price / $3, 16)) As split_price
+    From
+      lots
+    Where
+      symbol = $1
+      And quantity <> 0
+      And opened_at < $2
+  ),
+  kept As (
+    Select
+      *,
+      Coalesce(Trunc(split_quantity / $4) * $4, split_quantity) As kept
+    From
+      split
+  )
+Select
+  account_id,
+  Sum(kept - quantity) As "added!",
+  Sum(split_quantity - kept) As "in_lieu!",
+  Trim_Scale(Round(Sum((split_quantity - kept) * split_price), 16)) As "in_lieu_cost!: Money"
+From
+  kept
+Group By
+  account_id
@@ -0,0 +1,46 @@
+-- market orders reserve what they were quoted at as they were placed. Only one
+-- order of a set of one-cancels-other siblings can ever fill, so each set
+-- reserves only its largest order, scaled by its symbol's multiplier, or the
+-- initial margin of its contracts for futures
+Select
+  Coalesce(Sum(notional), 0) As "notional!"
+From
+  (
+    Select
+      Max(
+        (quantity - filled_quantity) * Coalesce(
+          initial_margin,
+          Coalesce(limit_price, stop_price, quoted_price) * Coalesce(multiplier, 1)
+        )
+      ) As notional
+    From
+      orders
+      Left Join instruments Using (symbol)
+    Where
+      account_id = $1
+      And status = 'open'
+      And side = 'buy'
+    Group By
+      group_id,
+      parent_id,
+      Case
+        When group_id Is Null Then id
+      End
+    Union All
+    -- multi-leg groups reserve their net debit, priced per unit of their
+    -- largest multiplier, while their legs are open
+    Select
+      Greatest(order_groups.limit_price, 0) * order_groups.quantity * Max(
+        Coalesce(multiplier, 1)
+      ) As notional
+    From
+      order_groups
+      Join orders On orders.group_id = order_groups.id
+      Left Join instruments Using (symbol)
+    Where
+      order_groups.account_id = $1
+      And order_groups.kind = 'multi_leg'
+      And orders.status = 'open'
+    Group By
+      order_groups.id
+  ) As sets
@@ -0,0 +1,22 @@
+-- only one order of a set of one-cancels-other siblings can ever fill, so
+-- each set commits only its largest order
+Select
+  Coalesce(Sum(quantity), 0) As "quantity!"
+From
+  (
+    Select
+      Max(quantity - filled_quantity) As quantity
+    From
+      orders
+    Where
+      account_id = $1
+      And symbol = $2
+      And status = 'open'
+      And side = 'sell'
+    Group By
+      group_id,
+      parent_id,
+      Case
+        When group_id Is Null Then id
+      End
+  ) As sets
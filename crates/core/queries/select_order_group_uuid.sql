@@ -0,0 +1,14 @@
+Select
+  order_groups.id,
+  order_groups.uuid,
+  order_groups.account_id,
+  order_groups.kind As "kind: OrderGroupKind",
+  order_groups.quantity As "quantity: Quantity",
+  order_groups.limit_price As "limit_price: Money",
+  order_groups.created_at
+From
+  order_groups
+  Join accounts On accounts.id = order_groups.account_id
+Where
+  order_groups.uuid = $1
+  And accounts.user_id = $2
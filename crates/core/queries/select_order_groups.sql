@@ -0,0 +1,14 @@
+Select
+  id,
+  uuid,
+  account_id,
+  kind As "kind: OrderGroupKind",
+  quantity As "quantity: Quantity",
+  limit_price As "limit_price: Money",
+  created_at
+From
+  order_groups
+Where
+  account_id = $1
+Order By
+  id Desc
@@ -0,0 +1,22 @@
+Select
+  id,
+  uuid,
+  account_id,
+  kind As "kind: OrderGroupKind",
+  quantity As "quantity: Quantity",
+  limit_price As "limit_price: Money",
+  created_at
+From
+  order_groups
+Where
+  kind = 'multi_leg'
+  And Exists (
+    Select
+    From
+      orders
+    Where
+      orders.group_id = order_groups.id
+      And orders.status = 'open'
+  )
+Order By
+  id
@@ -0,0 +1,30 @@
+Select
+  orders.id,
+  orders.uuid,
+  orders.account_id,
+  orders.symbol,
+  orders.side As "side: OrderSide",
+  orders.order_type As "order_type: OrderType",
+  orders.quantity As "quantity: Quantity",
+  orders.limit_price As "limit_price: Money",
+  orders.stop_price As "stop_price: Money",
+  orders.trail_amount As "trail_amount: Money",
+  orders.trail_percent,
+  orders.trail_reference As "trail_reference: Money",
+  orders.triggered_at,
+  orders.time_in_force As "time_in_force: TimeInForce",
+  orders.expires_at,
+  orders.extended_hours,
+  orders.group_id,
+  orders.parent_id,
+  orders.origin As "origin: OrderOrigin",
+  orders.status As "status: OrderStatus",
+  orders.filled_quantity As "filled_quantity: Quantity",
+  orders.created_at,
+  orders.updated_at
+From
+  orders
+  Join accounts On accounts.id = orders.account_id
+Where
+  orders.uuid = $1
+  And accounts.user_id = $2
@@ -0,0 +1,10 @@
+Select
+  Exists (
+    Select
+    From
+      orders
+    Where
+      account_id = $1
+      And origin = 'liquidation'
+      And status = 'open'
+  ) As "liquidating!"
@@ -0,0 +1,43 @@
+Select
+  id,
+  uuid,
+  account_id,
+  symbol,
+  side As "side: OrderSide",
+  order_type As "order_type: OrderType",
+  quantity As "quantity: Quantity",
+  limit_price As "limit_price: Money",
+  stop_price As "stop_price: Money",
+  trail_amount As "trail_amount: Money",
+  trail_percent,
+  trail_reference As "trail_reference: Money",
+  triggered_at,
+  time_in_force As "time_in_force: TimeInForce",
+  expires_at,
+  extended_hours,
+  group_id,
+  parent_id,
+  origin As "origin: OrderOrigin",
+  status As "status: OrderStatus",
+  filled_quantity As "filled_quantity: Quantity",
+  created_at,
+  updated_at
+From
+  orders
+Where
+  status = 'open'
+  And (
+    expires_at Is Null
+    Or expires_at > now()
+  )
+  And Exists (
+    Select
+    From
+      order_groups
+    Where
+      order_groups.id = orders.group_id
+      And order_groups.kind = 'multi_leg'
+  )
+Order By
+  group_id,
+  id
@@ -0,0 +1,18 @@
+Select
+  id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  cost_basis As "cost_basis: Money",
+  updated_at,
+  realized_pnl As "realized_pnl: Money",
+  fees As "fees: Money",
+  borrow_fees As "borrow_fees: Money",
+  dividends As "dividends: Money",
+  drip
+From
+  positions
+Where
+  account_id = $1
+  And symbol = $2
+For Update
@@ -0,0 +1,28 @@
+-- futures are held without paying for them, so only require their margin
+Select
+  Coalesce(
+    Sum(cost_basis) Filter (
+      Where
+        asset_class Is Distinct From 'future'
+    ),
+    0
+  ) As "net!",
+  Coalesce(
+    Sum(Abs(cost_basis)) Filter (
+      Where
+        asset_class Is Distinct From 'future'
+    ),
+    0
+  ) As "gross!",
+  Coalesce(
+    Sum(Abs(quantity) * initial_margin) Filter (
+      Where
+        asset_class = 'future'
+    ),
+    0
+  ) As "futures!"
+From
+  positions
+  Left Join instruments Using (symbol)
+Where
+  account_id = $1
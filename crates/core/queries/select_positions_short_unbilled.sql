@@ -0,0 +1,37 @@
+Select
+  id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  cost_basis As "cost_basis: Money",
+  updated_at,
+  realized_pnl As "realized_pnl: Money",
+  fees As "fees: Money",
+  borrow_fees As "borrow_fees: Money",
+  dividends As "dividends: Money",
+  drip
+From
+  positions
+Where
+  quantity < 0
+  -- written option contracts & sold futures aren't borrowed
+  And Not Exists (
+    Select
+    From
+      instruments
+    Where
+      instruments.symbol = positions.symbol
+      And asset_class In ('option', 'future')
+  )
+  And Not Exists (
+    Select
+    From
+      borrow_fees
+    Where
+      borrow_fees.account_id = positions.account_id
+      And borrow_fees.symbol = positions.symbol
+      And borrow_fees.date = $1
+  )
+Order By
+  account_id,
+  symbol
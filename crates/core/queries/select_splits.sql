@@ -0,0 +1,16 @@
+Select
+  id,
+  symbol,
+  kind As "kind: CorporateActionKind",
+  ex_date,
+  pay_date,
+  ratio As "ratio: Money",
+  amount As "amount: Money",
+  applied_at
+From
+  corporate_actions
+Where
+  symbol = $1
+  And kind = 'split'
+Order By
+  ex_date
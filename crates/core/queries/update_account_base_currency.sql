@@ -0,0 +1,7 @@
+Update
+  accounts
+Set
+  base_currency = $2,
+  cash = $3
+Where
+  id = $1
@@ -0,0 +1,6 @@
+Update
+  accounts
+Set
+  margin_call_at = $2
+Where
+  id = $1
@@ -0,0 +1,59 @@
+Update
+  accounts
+Set
+  lot_method = Coalesce($2, lot_method),
+  account_type = Coalesce($6, account_type),
+  initial_margin = Coalesce($7, initial_margin),
+  maintenance_margin = Coalesce($8, maintenance_margin),
+  auto_liquidate = Coalesce($9, auto_liquidate),
+  drip = Coalesce($10, drip),
+  base_currency = Coalesce($11, base_currency),
+  auto_convert = Coalesce($12, auto_convert),
+  fx_spread_bps = Coalesce($13, fx_spread_bps),
+  slippage_model = Coalesce($4, slippage_model),
+  slippage_bps = Case
+    When $4 Is Null Then slippage_bps
+    Else $5
+  End,
+  fee_schedule_id = Coalesce(
+    (
+      Select
+        id
+      From
+        fee_schedules
+      Where
+        name = $3
+    ),
+    fee_schedule_id
+  )
+Where
+  id = $1
+Returning
+  id,
+  uuid,
+  user_id,
+  name,
+  starting_cash As "starting_cash: Money",
+  cash As "cash: Money",
+  account_type As "account_type: AccountType",
+  initial_margin,
+  maintenance_margin,
+  auto_liquidate,
+  drip,
+  base_currency,
+  auto_convert,
+  fx_spread_bps,
+  margin_call_at,
+  lot_method As "lot_method: LotMethod",
+  slippage_model As "slippage_model: SlippageKind",
+  slippage_bps,
+  fee_schedule_id,
+  (
+    Select
+      name
+    From
+      fee_schedules
+    Where
+      fee_schedules.id = accounts.fee_schedule_id
+  ) As "fee_schedule!",
+  created_at
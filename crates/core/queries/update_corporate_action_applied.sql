@@ -0,0 +1,6 @@
+Update
+  corporate_actions
+Set
+  applied_at = now()
+Where
+  id = $1
@@ -0,0 +1,6 @@
+Update
+  dividends
+Set
+  order_id = $2
+Where
+  id = $1
@@ -0,0 +1,17 @@
+Update
+  dividends
+Set
+  paid_at = now()
+Where
+  id = $1
+  And paid_at Is Null
+Returning
+  id,
+  action_id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  amount As "amount: Money",
+  pay_date,
+  paid_at,
+  order_id
@@ -0,0 +1,10 @@
+Update
+  instruments
+Set
+  active = False,
+  updated_at = now()
+Where
+  symbol = $1
+  And active
+Returning
+  symbol
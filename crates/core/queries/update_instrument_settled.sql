@@ -0,0 +1,16 @@
+-- a future settles once a day, and for the last time after it expires
+Update
+  instruments
+Set
+  settled_on = $2,
+  active = expires_on >= $2,
+  updated_at = now()
+Where
+  symbol = $1
+  And active
+  And (
+    settled_on Is Null
+    Or settled_on < $2
+  )
+Returning
+  symbol
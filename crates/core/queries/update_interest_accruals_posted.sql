@@ -0,0 +1,11 @@
+Update
+  interest_accruals
+Set
+  posted_on = $3
+Where
+  account_id = $1
+  And currency = $2
+  And date < $3
+  And posted_on Is Null
+Returning
+  amount
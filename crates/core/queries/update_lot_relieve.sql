@@ -0,0 +1,6 @@
+Update
+  lots
+Set
+  quantity = quantity - $2
+Where
+  id = $1
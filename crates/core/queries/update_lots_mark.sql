@@ -0,0 +1,8 @@
+Update
+  lots
+Set
+  price = $3
+Where
+  account_id = $1
+  And symbol = $2
+  And quantity <> 0
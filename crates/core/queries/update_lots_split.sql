@@ -0,0 +1,12 @@
+Update
+  lots
+Set
+  quantity = Coalesce(
+    Trunc(Round(quantity * $3, 8) / $4) * $4,
+    Trim_Scale(Round(quantity * $3, 8))
+  ),
+  price = Trim_Scale(Round(price / $3, 16))
+Where
+  symbol = $1
+  And quantity <> 0
+  And opened_at < $2
@@ -0,0 +1,8 @@
+Update
+  orders
+Set
+  status = 'cancelled',
+  updated_at = now()
+Where
+  account_id = $1
+  And status In ('open', 'pending')
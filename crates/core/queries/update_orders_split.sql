@@ -0,0 +1,21 @@
+-- quantities are kept to whole lots of $5 unless it's null, & at least one
+Update
+  orders
+Set
+  quantity = Coalesce(
+    Greatest(Trunc(Round(quantity * $3, 8) / $5) * $5, $5),
+    Trim_Scale(Round(quantity * $3, 8))
+  ),
+  filled_quantity = Coalesce(
+    Trunc(Round(filled_quantity * $3, 8) / $5) * $5,
+    Trim_Scale(Round(filled_quantity * $3, 8))
+  ),
+  limit_price = Greatest(Round(limit_price / $3 / $4) * $4, $4),
+  stop_price = Greatest(Round(stop_price / $3 / $4) * $4, $4),
+  trail_amount = Greatest(Round(trail_amount / $3 / $4) * $4, $4),
+  trail_reference = Trim_Scale(Round(trail_reference / $3, 16)),
+  updated_at = now()
+Where
+  symbol = $1
+  And status In ('open', 'pending')
+  And created_at < $2
@@ -0,0 +1,7 @@
+Update
+  positions
+Set
+  borrow_fees = borrow_fees + $2,
+  updated_at = now()
+Where
+  id = $1
@@ -0,0 +1,10 @@
+-- what's settled is realized, and the position's cost marked to the market
+Update
+  positions
+Set
+  cost_basis = cost_basis + $3,
+  realized_pnl = realized_pnl + $3,
+  updated_at = now()
+Where
+  account_id = $1
+  And symbol = $2
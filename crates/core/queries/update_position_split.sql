@@ -0,0 +1,10 @@
+Update
+  positions
+Set
+  quantity = quantity + $3,
+  cost_basis = cost_basis - $4,
+  realized_pnl = realized_pnl + $5,
+  updated_at = now()
+Where
+  account_id = $1
+  And symbol = $2
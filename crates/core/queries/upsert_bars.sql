@@ -0,0 +1,21 @@
+Insert Into
+  bars (symbol, time, open, high, low, close, volume)
+Select
+  $1,
+  *
+From
+  Unnest(
+    $2::Timestamptz[],
+    $3::Numeric[],
+    $4::Numeric[],
+    $5::Numeric[],
+    $6::Numeric[],
+    $7::Numeric[]
+  )
+On Conflict (symbol, time) Do Update
+Set
+  open = excluded.open,
+  high = excluded.high,
+  low = excluded.low,
+  close = excluded.close,
+  volume = excluded.volume
@@ -0,0 +1,10 @@
+Insert Into
+  cash_balances (account_id, currency, amount)
+Values
+  ($1, $2, $3)
+On Conflict (account_id, currency) Do Update
+Set
+  amount = cash_balances.amount + excluded.amount,
+  updated_at = now()
+Returning
+  amount
@@ -0,0 +1,11 @@
+Insert Into
+  corporate_actions (symbol, kind, ex_date, pay_date, ratio, amount)
+Values
+  ($1, $2, $3, $4, $5, $6)
+On Conflict (symbol, kind, ex_date) Do Update
+Set
+  pay_date = excluded.pay_date,
+  ratio = excluded.ratio,
+  amount = excluded.amount
+Where
+  corporate_actions.applied_at Is Null
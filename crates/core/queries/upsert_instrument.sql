@@ -0,0 +1,62 @@
+Insert Into
+  instruments (
+    symbol,
+    name,
+    exchange,
+    asset_class,
+    currency,
+    tick_size,
+    lot_size,
+    fractional,
+    shortable,
+    active,
+    multiplier,
+    underlying,
+    option_type,
+    strike,
+    expires_on,
+    initial_margin,
+    maintenance_margin,
+    roll_on
+  )
+Values
+  (
+    $1,
+    $2,
+    $3,
+    $4,
+    $5,
+    $6,
+    $7,
+    $8,
+    $9,
+    $10,
+    $11,
+    $12,
+    $13,
+    $14,
+    $15,
+    $16,
+    $17,
+    $18
+  )
+On Conflict (symbol) Do Update
+Set
+  name = excluded.name,
+  exchange = excluded.exchange,
+  asset_class = excluded.asset_class,
+  currency = excluded.currency,
+  tick_size = excluded.tick_size,
+  lot_size = excluded.lot_size,
+  fractional = excluded.fractional,
+  shortable = excluded.shortable,
+  active = excluded.active,
+  multiplier = excluded.multiplier,
+  underlying = excluded.underlying,
+  option_type = excluded.option_type,
+  strike = excluded.strike,
+  expires_on = excluded.expires_on,
+  initial_margin = excluded.initial_margin,
+  maintenance_margin = excluded.maintenance_margin,
+  roll_on = excluded.roll_on,
+  updated_at = now()
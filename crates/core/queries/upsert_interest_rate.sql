@@ -0,0 +1,13 @@
+Insert Into
+  interest_rates (currency, kind, effective_on, annual_rate)
+Values
+  ($1, $2, $3, $4)
+On Conflict (currency, kind, effective_on) Do Update
+Set
+  annual_rate = excluded.annual_rate
+Returning
+  id,
+  currency,
+  kind As "kind: InterestKind",
+  effective_on,
+  annual_rate
@@ -0,0 +1,30 @@
+Insert Into
+  positions (
+    account_id,
+    symbol,
+    quantity,
+    cost_basis,
+    realized_pnl,
+    fees
+  )
+Values
+  ($1, $2, $3, $4, $5, $6)
+On Conflict (account_id, symbol) Do Update
+Set
+  quantity = excluded.quantity,
+  cost_basis = excluded.cost_basis,
+  realized_pnl = positions.realized_pnl + excluded.realized_pnl,
+  fees = positions.fees + excluded.fees,
+  updated_at = now()
+Returning
+  id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  cost_basis As "cost_basis: Money",
+  updated_at,
+  realized_pnl As "realized_pnl: Money",
+  fees As "fees: Money",
+  borrow_fees As "borrow_fees: Money",
+  dividends As "dividends: Money",
+  drip
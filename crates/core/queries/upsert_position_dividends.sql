@@ -0,0 +1,16 @@
+Insert Into
+  positions (
+    account_id,
+    symbol,
+    quantity,
+    cost_basis,
+    realized_pnl,
+    fees,
+    dividends
+  )
+Values
+  ($1, $2, 0, 0, 0, 0, $3)
+On Conflict (account_id, symbol) Do Update
+Set
+  dividends = positions.dividends + excluded.dividends,
+  updated_at = now()
@@ -0,0 +1,28 @@
+Insert Into
+  positions (
+    account_id,
+    symbol,
+    quantity,
+    cost_basis,
+    realized_pnl,
+    fees,
+    drip
+  )
+Values
+  ($1, $2, 0, 0, 0, 0, $3)
+On Conflict (account_id, symbol) Do Update
+Set
+  drip = excluded.drip,
+  updated_at = now()
+Returning
+  id,
+  account_id,
+  symbol,
+  quantity As "quantity: Quantity",
+  cost_basis As "cost_basis: Money",
+  updated_at,
+  realized_pnl As "realized_pnl: Money",
+  fees As "fees: Money",
+  borrow_fees As "borrow_fees: Money",
+  dividends As "dividends: Money",
+  drip
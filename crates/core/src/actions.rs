@@ -0,0 +1,225 @@
+use std::{io::Read, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use crate::{
+    market::{
+        import::{parse_date, parse_decimal},
+        MarketData,
+    },
+    models::{CorporateActionKind, NewCorporateAction},
+    numeric::Money,
+    state::persist::Database,
+};
+
+/// Import corporate actions from a csv file into the database
+///
+/// See [`read_actions`] for the accepted format. Returns the number of actions
+/// written.
+///
+/// # Errors
+///
+/// See [`ActionError`]
+pub async fn import_actions(database: &Database, reader: impl Read) -> Result<u64, ActionError> {
+    let actions = read_actions(reader)?;
+    Ok(database.add_corporate_actions(&actions).await?)
+}
+
+/// Read corporate actions from a csv file
+///
+/// Takes `symbol,kind,ex_date,pay_date,ratio,amount` columns, matched by name
+/// in any order & case. Kinds are `split` or `dividend`. Splits need a ratio,
+/// either the shares given for each held, like `4` or `0.1`, or as `new:old`,
+/// like `4:1` or `1:10`. Dividends need the cash paid a share & a pay date no
+/// earlier than their ex-date. Dates may be `YYYY-MM-DD` or `YYYYMMDD`.
+///
+/// # Errors
+///
+/// See [`ActionError`]
+pub fn read_actions(reader: impl Read) -> Result<Vec<NewCorporateAction>, ActionError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers: Vec<_> = reader
+        .headers()?
+        .iter()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let column = |name| headers.iter().position(|h| h == name);
+    let required = |name: &'static str| column(name).ok_or(ActionError::MissingColumn(name));
+    let symbol = required("symbol")?;
+    let kind = required("kind")?;
+    let ex_date = required("ex_date")?;
+    let pay_date = column("pay_date");
+    let ratio = column("ratio");
+    let amount = column("amount");
+
+    let mut actions = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .filter(|field| !field.is_empty())
+        };
+        // the header is line 1
+        let line = row + 2;
+        let invalid = |column: &'static str| ActionError::InvalidRow { line, column };
+        let date = |i, column| {
+            field(i)
+                .map(|field| parse_date(field).ok_or_else(|| invalid(column)))
+                .transpose()
+        };
+        let mut action = NewCorporateAction {
+            symbol: field(Some(symbol))
+                .ok_or_else(|| invalid("symbol"))?
+                .to_ascii_uppercase(),
+            kind: match field(Some(kind)).map(str::to_ascii_lowercase).as_deref() {
+                Some("split") => CorporateActionKind::Split,
+                Some("dividend") => CorporateActionKind::Dividend,
+                _ => return Err(invalid("kind")),
+            },
+            ex_date: date(Some(ex_date), "ex_date")?.ok_or_else(|| invalid("ex_date"))?,
+            pay_date: date(pay_date, "pay_date")?,
+            ratio: None,
+            amount: None,
+        };
+        match action.kind {
+            CorporateActionKind::Split => {
+                action.pay_date = None;
+                action.ratio = Some(
+                    field(ratio)
+                        .and_then(parse_ratio)
+                        .ok_or_else(|| invalid("ratio"))?,
+                );
+            }
+            CorporateActionKind::Dividend => {
+                action.amount = Some(
+                    field(amount)
+                        .and_then(parse_decimal)
+                        .and_then(|amount| Money::try_from(amount).ok())
+                        .filter(|amount| !amount.is_sign_negative())
+                        .ok_or_else(|| invalid("amount"))?,
+                );
+                if action.pay_date.is_none_or(|pay| pay < action.ex_date) {
+                    return Err(invalid("pay_date"));
+                }
+            }
+        }
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+/// Parses a split ratio, as a number or `new:old`
+///
+/// Ratios like `1:3` don't divide exactly, so are rounded to the places a
+/// [`Money`] keeps.
+fn parse_ratio(s: &str) -> Option<Money> {
+    let ratio = match s.split_once(':') {
+        Some((new, old)) => Money::round(parse_decimal(new)?.checked_div(parse_decimal(old)?)?),
+        None => Money::try_from(parse_decimal(s)?).ok()?,
+    };
+    (ratio > Money::ZERO).then_some(ratio)
+}
+
+/// Applies corporate actions to holdings as they go ex, & pays dividends,
+/// reinvesting them for holdings that do so
+#[derive(Debug)]
+pub struct ActionProcessor {
+    database: Database,
+    market: Arc<dyn MarketData>,
+}
+
+impl ActionProcessor {
+    /// Creates a new processor, pricing reinvested dividends with the given
+    /// market data provider
+    #[must_use]
+    pub fn new(database: Database, market: Arc<dyn MarketData>) -> Self {
+        Self { database, market }
+    }
+
+    /// Runs a single pass over every action gone ex, & every dividend due
+    ///
+    /// Splits are applied to what was held & ordered before their ex-date,
+    /// paying for shares left over in whole lots at the symbol's last price.
+    /// Dividends are owed on what was held then, & paid on their pay date.
+    /// Those reinvested are bought back at the symbol's last price as they're
+    /// paid, or paid as cash if it isn't quoted.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let today = OffsetDateTime::now_utc().date();
+        for action in self.database.get_due_actions(today).await? {
+            match action.kind {
+                CorporateActionKind::Split => {
+                    let price = self.price(&action.symbol).await;
+                    if let Some(positions) = self.database.apply_split(&action, price).await? {
+                        let ratio = action.ratio.unwrap_or_default();
+                        info!(symbol = action.symbol, %ratio, positions, "split applied");
+                    }
+                }
+                CorporateActionKind::Dividend => {
+                    if let Some(accounts) = self.database.declare_dividend(&action).await? {
+                        let amount = action.amount.unwrap_or_default();
+                        info!(symbol = action.symbol, %amount, accounts, "dividend declared");
+                    }
+                }
+            }
+        }
+        for dividend in self.database.get_payable_dividends(today).await? {
+            let price = if dividend.amount > Money::ZERO
+                && self
+                    .database
+                    .is_reinvested(dividend.account_id, &dividend.symbol)
+                    .await?
+            {
+                self.price(&dividend.symbol).await
+            } else {
+                None
+            };
+            if let Some(paid) = self.database.pay_dividend(&dividend, price).await? {
+                let amount = paid.amount;
+                let reinvested = paid.order_id.is_some();
+                info!(symbol = paid.symbol, %amount, reinvested, "dividend paid");
+            }
+        }
+        Ok(())
+    }
+
+    /// A symbol's last price, if it's quoted
+    async fn price(&self, symbol: &str) -> Option<Decimal> {
+        match self.market.quote(symbol).await {
+            Ok(quote) => quote.map(|quote| quote.last),
+            Err(e) => {
+                warn!(symbol, "failed to quote: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// An error while importing corporate actions
+#[derive(Debug, thiserror::Error)]
+pub enum ActionError {
+    /// The file couldn't be read as csv
+    #[error("Csv error: {0}")]
+    Csv(#[from] csv::Error),
+    /// A required column is missing from the header
+    #[error("Missing column: {0}")]
+    MissingColumn(&'static str),
+    /// A row's value is missing or couldn't be parsed
+    #[error("Invalid {column} on line {line}")]
+    InvalidRow {
+        /// The 1-based line of the row
+        line: usize,
+        /// The column that failed to parse
+        column: &'static str,
+    },
+    /// The actions couldn't be stored
+    #[error("Database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
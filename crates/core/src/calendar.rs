@@ -0,0 +1,388 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
+
+use serde::Deserialize;
+use time::{
+    macros::format_description, Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+    Weekday,
+};
+
+use crate::models::Instrument;
+
+/// The calendar bundled with the server
+const BUNDLED: &str = include_str!("../data/calendar.csv");
+
+/// How far ahead to look for the next session, past weekends & holidays
+const MAX_CLOSED_DAYS: i64 = 14;
+
+/// When each exchange trades
+///
+/// Exchanges trade on weekdays, in their local time, besides their holidays.
+/// Early closes shorten both the regular & the extended session.
+#[derive(Debug, Clone, Default)]
+pub struct Calendar {
+    exchanges: HashMap<String, Exchange>,
+    around_the_clock: bool,
+}
+
+/// The trading hours of a single exchange
+#[derive(Debug, Clone, Default)]
+struct Exchange {
+    hours: Option<(Time, Time)>,
+    extended: Option<(Time, Time)>,
+    /// Each offset from UTC, from the date it takes effect, in order
+    offsets: Vec<(Date, UtcOffset)>,
+    holidays: HashSet<Date>,
+    early_closes: HashMap<Date, Time>,
+}
+
+/// A single trading session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    /// When the session opens
+    pub open: OffsetDateTime,
+    /// When the session closes
+    pub close: OffsetDateTime,
+}
+
+impl Calendar {
+    /// The calendar bundled with the server, from `data/calendar.csv`
+    ///
+    /// # Panics
+    ///
+    /// If the bundled file is malformed
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::read(BUNDLED.as_bytes()).expect("the bundled calendar is valid")
+    }
+
+    /// A calendar whose every exchange is always open
+    #[must_use]
+    pub fn around_the_clock() -> Self {
+        Self {
+            exchanges: HashMap::new(),
+            around_the_clock: true,
+        }
+    }
+
+    /// Read a calendar from a csv file
+    ///
+    /// Each row has an `exchange` & a `kind`, with the other columns it uses:
+    /// - `hours`: the regular session's local `open` & `close`, `HH:MM`
+    /// - `extended`: the extended session's local `open` & `close`
+    /// - `offset`: the exchange's UTC `offset`, `+HH:MM`, from `date` on
+    /// - `holiday`: a weekday `date` the exchange doesn't trade
+    /// - `early_close`: a `date` the exchange closes early, at `close`
+    ///
+    /// # Errors
+    ///
+    /// See [`CalendarError`]
+    pub fn read(reader: impl Read) -> Result<Self, CalendarError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let mut exchanges: HashMap<String, Exchange> = HashMap::new();
+        for (row, record) in reader.deserialize().enumerate() {
+            let record: Row = record?;
+            // the header is line 1
+            let line = row + 2;
+            let invalid = |column: &'static str| CalendarError::InvalidRow { line, column };
+            let date = || parse_date(record.date.as_deref()).ok_or_else(|| invalid("date"));
+            let open = || parse_time(record.open.as_deref()).ok_or_else(|| invalid("open"));
+            let close = || parse_time(record.close.as_deref()).ok_or_else(|| invalid("close"));
+            let exchange = exchanges.entry(record.exchange.clone()).or_default();
+            match record.kind {
+                Kind::Hours => exchange.hours = Some((open()?, close()?)),
+                Kind::Extended => exchange.extended = Some((open()?, close()?)),
+                Kind::Offset => {
+                    let offset =
+                        parse_offset(record.offset.as_deref()).ok_or_else(|| invalid("offset"))?;
+                    exchange.offsets.push((date()?, offset));
+                }
+                Kind::Holiday => {
+                    exchange.holidays.insert(date()?);
+                }
+                Kind::EarlyClose => {
+                    exchange.early_closes.insert(date()?, close()?);
+                }
+            }
+        }
+        for (code, exchange) in &mut exchanges {
+            if exchange.hours.is_none() {
+                return Err(CalendarError::MissingHours(code.clone()));
+            }
+            exchange.offsets.sort_by_key(|(date, _)| *date);
+        }
+        Ok(Self {
+            exchanges,
+            around_the_clock: false,
+        })
+    }
+
+    /// The session an exchange trades on a (local) date, if it trades
+    ///
+    /// The extended session is used if `extended` is set, and the exchange has
+    /// one. Returns `None` for unknown exchanges.
+    #[must_use]
+    pub fn session(&self, exchange: &str, date: Date, extended: bool) -> Option<Session> {
+        if self.around_the_clock {
+            return Some(Session::whole_day(date));
+        }
+        self.exchanges.get(exchange)?.session(date, extended)
+    }
+
+    /// Whether an exchange is trading at a point in time
+    #[must_use]
+    pub fn is_open(&self, exchange: &str, at: OffsetDateTime, extended: bool) -> bool {
+        self.current_or_next(exchange, at, extended)
+            .is_some_and(|session| session.open <= at)
+    }
+
+    /// The session an exchange is trading at a point in time, or otherwise
+    /// the next session it trades
+    ///
+    /// Returns `None` for unknown exchanges.
+    #[must_use]
+    pub fn current_or_next(
+        &self,
+        exchange: &str,
+        at: OffsetDateTime,
+        extended: bool,
+    ) -> Option<Session> {
+        // local dates are at most a day either side of the UTC date
+        let date = at.date().previous_day()?;
+        (0..=MAX_CLOSED_DAYS)
+            .filter_map(|days| date.checked_add(Duration::days(days)))
+            .filter_map(|date| self.session(exchange, date, extended))
+            .find(|session| at < session.close)
+    }
+
+    /// The session a symbol is trading at a point in time, or otherwise the
+    /// next session it trades
+    ///
+    /// Crypto trades around the clock, whatever its exchange, in sessions of
+    /// a whole UTC day. Everything else trades as its exchange does, see
+    /// [`Calendar::current_or_next`].
+    #[must_use]
+    pub fn trading_session(
+        &self,
+        instrument: &Instrument,
+        at: OffsetDateTime,
+        extended: bool,
+    ) -> Option<Session> {
+        if instrument.is_crypto() {
+            return Some(Session::whole_day(at.to_offset(UtcOffset::UTC).date()));
+        }
+        self.current_or_next(&instrument.exchange, at, extended)
+    }
+
+    /// When a symbol's regular session closes on a date, or the end of the
+    /// date in UTC if it doesn't trade then, as for crypto
+    #[must_use]
+    pub fn close_on(&self, instrument: &Instrument, date: Date) -> OffsetDateTime {
+        self.session(&instrument.exchange, date, false)
+            .filter(|_| !instrument.is_crypto())
+            .unwrap_or_else(|| Session::whole_day(date))
+            .close
+    }
+
+    /// Whether a symbol is trading at a point in time
+    ///
+    /// See [`Calendar::trading_session`].
+    #[must_use]
+    pub fn is_trading(&self, instrument: &Instrument, at: OffsetDateTime, extended: bool) -> bool {
+        self.trading_session(instrument, at, extended)
+            .is_some_and(|session| session.open <= at)
+    }
+}
+
+impl Session {
+    /// A session lasting a whole UTC date
+    fn whole_day(date: Date) -> Self {
+        let open = date.midnight().assume_utc();
+        Self {
+            open,
+            close: open + Duration::DAY,
+        }
+    }
+}
+
+impl Exchange {
+    fn session(&self, date: Date, extended: bool) -> Option<Session> {
+        if matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday)
+            || self.holidays.contains(&date)
+        {
+            return None;
+        }
+        let (open, close) = self.extended.filter(|_| extended).or(self.hours)?;
+        let close = self
+            .early_closes
+            .get(&date)
+            .map_or(close, |&early| early.min(close));
+        let offset = self
+            .offsets
+            .iter()
+            .take_while(|(from, _)| *from <= date)
+            .last()
+            .or_else(|| self.offsets.first())
+            .map_or(UtcOffset::UTC, |(_, offset)| *offset);
+        let at = |time| PrimitiveDateTime::new(date, time).assume_offset(offset);
+        Some(Session {
+            open: at(open),
+            close: at(close),
+        })
+    }
+}
+
+/// A row of a calendar file
+#[derive(Debug, Deserialize)]
+struct Row {
+    exchange: String,
+    kind: Kind,
+    date: Option<String>,
+    open: Option<String>,
+    close: Option<String>,
+    offset: Option<String>,
+}
+
+/// What a row of a calendar file describes
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Kind {
+    Hours,
+    Extended,
+    Offset,
+    Holiday,
+    EarlyClose,
+}
+
+fn parse_date(s: Option<&str>) -> Option<Date> {
+    Date::parse(s?, format_description!("[year]-[month]-[day]")).ok()
+}
+
+fn parse_time(s: Option<&str>) -> Option<Time> {
+    Time::parse(s?, format_description!("[hour]:[minute]")).ok()
+}
+
+fn parse_offset(s: Option<&str>) -> Option<UtcOffset> {
+    UtcOffset::parse(
+        s?,
+        format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+    )
+    .ok()
+}
+
+/// An error while reading a calendar
+#[derive(Debug, thiserror::Error)]
+pub enum CalendarError {
+    /// The file couldn't be read as csv
+    #[error("Csv error: {0}")]
+    Csv(#[from] csv::Error),
+    /// A row's value couldn't be parsed
+    #[error("Invalid {column} on line {line}")]
+    InvalidRow {
+        /// The 1-based line of the row
+        line: usize,
+        /// The column that failed to parse
+        column: &'static str,
+    },
+    /// An exchange has no regular hours
+    #[error("Missing hours for {0}")]
+    MissingHours(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::models::AssetClass;
+
+    #[test]
+    fn weekdays_trade_regular_hours_in_local_time() {
+        let calendar = Calendar::bundled();
+
+        // eastern standard & daylight time
+        let winter = calendar.session("XNYS", date!(2026 - 01 - 05), false);
+        let summer = calendar.session("XNYS", date!(2026 - 06 - 01), true);
+
+        assert_eq!(
+            winter,
+            Some(Session {
+                open: datetime!(2026-01-05 14:30 UTC),
+                close: datetime!(2026-01-05 21:00 UTC),
+            })
+        );
+        assert_eq!(
+            summer,
+            Some(Session {
+                open: datetime!(2026-06-01 08:00 UTC),
+                close: datetime!(2026-06-02 00:00 UTC),
+            })
+        );
+    }
+
+    #[test]
+    fn weekends_and_holidays_dont_trade() {
+        let calendar = Calendar::bundled();
+
+        assert_eq!(calendar.session("XNYS", date!(2026 - 10 - 17), false), None);
+        assert_eq!(calendar.session("XNYS", date!(2026 - 10 - 18), false), None);
+        // thanksgiving
+        assert_eq!(calendar.session("XNYS", date!(2026 - 11 - 26), false), None);
+        assert_eq!(calendar.session("XNAS", date!(2026 - 11 - 26), false), None);
+    }
+
+    #[test]
+    fn early_closes_shorten_both_sessions() {
+        let calendar = Calendar::bundled();
+        let close = datetime!(2026-11-27 18:00 UTC);
+
+        let regular = calendar.session("XNYS", date!(2026 - 11 - 27), false);
+        let extended = calendar.session("XNYS", date!(2026 - 11 - 27), true);
+
+        assert_eq!(regular.map(|session| session.close), Some(close));
+        assert_eq!(extended.map(|session| session.close), Some(close));
+    }
+
+    #[test]
+    fn closed_exchanges_wait_for_the_next_session() {
+        let calendar = Calendar::bundled();
+        let saturday = datetime!(2026-10-17 15:00 UTC);
+
+        let next = calendar.current_or_next("XNYS", saturday, false);
+
+        assert!(!calendar.is_open("XNYS", saturday, false));
+        assert_eq!(
+            next.map(|session| session.open),
+            Some(datetime!(2026-10-19 13:30 UTC))
+        );
+        assert_eq!(calendar.current_or_next("XXXX", saturday, false), None);
+    }
+
+    #[test]
+    fn crypto_trades_around_the_clock_whatever_its_exchange() {
+        let calendar = Calendar::bundled();
+        let crypto = Instrument {
+            exchange: "XNYS".to_owned(),
+            asset_class: AssetClass::Crypto,
+            ..Instrument::unlisted("BTC-USD".to_owned())
+        };
+        let thanksgiving = datetime!(2026-11-26 03:00 UTC);
+
+        assert!(calendar.is_trading(&crypto, thanksgiving, false));
+        assert_eq!(
+            calendar.trading_session(&crypto, thanksgiving, false),
+            Some(Session {
+                open: datetime!(2026-11-26 00:00 UTC),
+                close: datetime!(2026-11-27 00:00 UTC),
+            })
+        );
+        assert_eq!(
+            calendar.close_on(&crypto, date!(2026 - 11 - 27)),
+            datetime!(2026-11-28 00:00 UTC)
+        );
+    }
+}
@@ -0,0 +1,628 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+use crate::{
+    calendar::Calendar,
+    margin,
+    market::{MarketData, Quote},
+    models::{Instrument, Order, OrderGroup, OrderSide, OrderStatus, OrderType, TimeInForce},
+    numeric::{Money, Quantity},
+    slippage::{self, Execution, SlippageModel},
+    state::persist::{Database, FillAction, Leg, Settlement, SpreadFillAction},
+};
+
+/// Fills resting orders against the market
+#[derive(Debug)]
+pub struct Engine {
+    database: Database,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
+    slippage: Arc<dyn SlippageModel>,
+}
+
+impl Engine {
+    /// Creates a new engine, trading against the given market during the
+    /// calendar's sessions
+    ///
+    /// Fills slip by `slippage`, for accounts that don't pick their own model.
+    #[must_use]
+    pub fn new(
+        database: Database,
+        market: Arc<dyn MarketData>,
+        calendar: Arc<Calendar>,
+        slippage: Arc<dyn SlippageModel>,
+    ) -> Self {
+        Self {
+            database,
+            market,
+            calendar,
+            slippage,
+        }
+    }
+
+    /// Runs a single pass over every open order, filling those the market
+    /// has crossed
+    ///
+    /// Orders in symbols that have been delisted are cancelled. Stop orders
+    /// are first moved & triggered, so a stop the market has just moved
+    /// through fills in the same pass. Immediate or cancel orders that can't
+    /// fill are cancelled, and fill or kill orders rejected. Orders only trade
+    /// while their symbol's exchange is open, or in extended hours if they
+    /// allow it.
+    /// Fills slip by their account's slippage model, rounded to the symbol's
+    /// tick, and limit orders only fill if the slipped price is still within
+    /// their limit. Symbols quoted in another currency than their account's
+    /// base currency only trade while its exchange rate is quoted too.
+    /// Multi-leg groups are then filled as units, see [`Self::fill_spread`].
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let mut quotes = HashMap::new();
+        let mut volumes = HashMap::new();
+        let mut models = HashMap::new();
+        let mut instruments = HashMap::new();
+        let mut rates = HashMap::new();
+        let now = OffsetDateTime::now_utc();
+        for order in self.database.get_open_orders().await? {
+            if let Entry::Vacant(entry) = instruments.entry(order.symbol.clone()) {
+                entry.insert(self.database.get_instrument(&order.symbol).await?);
+            }
+            let Some(instrument) = instruments[&order.symbol].as_ref().filter(|i| i.active) else {
+                self.delist(&order).await?;
+                continue;
+            };
+            if !self
+                .calendar
+                .is_trading(instrument, now, order.extended_hours)
+            {
+                self.kill(&order).await?;
+                continue;
+            }
+            if !quotes.contains_key(&order.symbol) {
+                let quote = self.quote(&order.symbol).await;
+                quotes.insert(order.symbol.clone(), quote);
+            }
+            let Some(quote) = &quotes[&order.symbol] else {
+                self.kill(&order).await?;
+                continue;
+            };
+            let Some(order) = self.update_stop(order, quote.last, instrument).await? else {
+                continue;
+            };
+            if fill_price(&order, quote.last).is_none() {
+                self.kill(&order).await?;
+                continue;
+            }
+            if let Entry::Vacant(entry) = models.entry(order.account_id) {
+                entry.insert(self.slippage_model(order.account_id).await?);
+            }
+            let (model, base_currency) = &models[&order.account_id];
+            let pair = (instrument.currency.clone(), base_currency.clone());
+            if !rates.contains_key(&pair) {
+                let rate = margin::fx_rate(self.market.as_ref(), &pair.0, &pair.1).await;
+                rates.insert(pair.clone(), rate);
+            }
+            let Some(rate) = rates[&pair] else {
+                self.kill(&order).await?;
+                continue;
+            };
+            let volume = if model.uses_volume() {
+                if !volumes.contains_key(&order.symbol) {
+                    let volume = self.volume(&order.symbol, now).await;
+                    volumes.insert(order.symbol.clone(), volume);
+                }
+                volumes[&order.symbol]
+            } else {
+                None
+            };
+            let execution = Execution { quote, volume };
+            let price = Money::round(instrument.round_price(
+                model.price(order.side, *order.remaining(), &execution),
+                against(order.side),
+            ));
+            if !within_limit(&order, *price) {
+                self.kill(&order).await?;
+                continue;
+            }
+            match self
+                .database
+                .fill_order(
+                    &order,
+                    order.remaining(),
+                    price,
+                    Settlement {
+                        currency: &instrument.currency,
+                        rate,
+                        multiplier: *instrument.multiplier,
+                        margin: instrument.initial_margin.map(Money::get),
+                    },
+                )
+                .await?
+            {
+                FillAction::Filled(order, fill) => {
+                    info!(order = %order.uuid, %fill.quantity, %fill.price, "order filled");
+                }
+                FillAction::Rejected(order) => info!(order = %order.uuid, "order rejected"),
+                FillAction::Stale => debug!(order = %order.uuid, "order no longer open"),
+            }
+        }
+        for (group, legs) in self.database.get_open_spreads().await? {
+            self.fill_spread(&group, &legs, now).await?;
+        }
+        Ok(())
+    }
+
+    /// Fills a multi-leg group's open legs together, if the market has
+    /// reached its net limit
+    ///
+    /// Groups with a leg in a symbol that's been delisted are cancelled. Legs
+    /// only trade while every one of their exchanges is open, in regular
+    /// hours, and each is quoted, as is their currency's exchange rate. Each
+    /// leg slips as an order would, and the group's net price at the slipped
+    /// prices must be within its limit, see [`net_price`]. Groups that must
+    /// fill at once but can't are killed as their legs would be.
+    async fn fill_spread(
+        &self,
+        group: &OrderGroup,
+        legs: &[Order],
+        now: OffsetDateTime,
+    ) -> Result<(), sqlx::Error> {
+        let mut instruments = Vec::with_capacity(legs.len());
+        for leg in legs {
+            let Some(instrument) = self.database.get_instrument(&leg.symbol).await? else {
+                return self.close_spread(group, OrderStatus::Cancelled).await;
+            };
+            if !instrument.active {
+                return self.close_spread(group, OrderStatus::Cancelled).await;
+            }
+            instruments.push(instrument);
+        }
+        let kill = match legs.first().map(|leg| leg.time_in_force) {
+            Some(TimeInForce::Ioc) => Some(OrderStatus::Cancelled),
+            Some(TimeInForce::Fok) => Some(OrderStatus::Rejected),
+            _ => None,
+        };
+        let killed = || async {
+            match kill {
+                Some(status) => self.close_spread(group, status).await,
+                None => Ok(()),
+            }
+        };
+        if !instruments
+            .iter()
+            .all(|instrument| self.calendar.is_trading(instrument, now, false))
+        {
+            return killed().await;
+        }
+        let (model, base_currency) = self.slippage_model(group.account_id).await?;
+        let currency = &instruments[0].currency;
+        let Some(rate) = margin::fx_rate(self.market.as_ref(), currency, &base_currency).await
+        else {
+            return killed().await;
+        };
+        let mut prices = Vec::with_capacity(legs.len());
+        for (leg, instrument) in legs.iter().zip(&instruments) {
+            let Some(quote) = self.quote(&leg.symbol).await else {
+                return killed().await;
+            };
+            let volume = if model.uses_volume() {
+                self.volume(&leg.symbol, now).await
+            } else {
+                None
+            };
+            let execution = Execution {
+                quote: &quote,
+                volume,
+            };
+            prices.push(Money::round(instrument.round_price(
+                model.price(leg.side, *leg.remaining(), &execution),
+                against(leg.side),
+            )));
+        }
+        let legs: Vec<_> = legs
+            .iter()
+            .zip(prices)
+            .zip(&instruments)
+            .map(|((order, price), instrument)| Leg {
+                order,
+                price,
+                instrument,
+            })
+            .collect();
+        let quantity = *group.quantity.unwrap_or(Quantity::ONE);
+        let net = net_price(legs.iter().map(|leg| {
+            let ratio = *leg.order.quantity / quantity;
+            (
+                leg.order.side,
+                ratio,
+                *leg.price,
+                *leg.instrument.multiplier,
+            )
+        }));
+        if group.limit_price.is_some_and(|limit| net > *limit) {
+            return killed().await;
+        }
+        match self.database.fill_spread(group, &legs, rate).await? {
+            SpreadFillAction::Filled(fills) => {
+                info!(group = %group.uuid, %net, legs = fills.len(), "spread filled");
+            }
+            SpreadFillAction::Rejected(_) => info!(group = %group.uuid, "spread rejected"),
+            SpreadFillAction::Stale => debug!(group = %group.uuid, "spread no longer open"),
+        }
+        Ok(())
+    }
+
+    /// Takes a multi-leg group's open legs off the book with the given
+    /// status
+    async fn close_spread(
+        &self,
+        group: &OrderGroup,
+        status: OrderStatus,
+    ) -> Result<(), sqlx::Error> {
+        let legs = self.database.close_order_group(group, status).await?;
+        if !legs.is_empty() {
+            info!(group = %group.uuid, ?status, "spread closed");
+        }
+        Ok(())
+    }
+
+    /// Trails & triggers a stop order against the market price
+    ///
+    /// Returns the updated order, or `None` if it's no longer open.
+    async fn update_stop(
+        &self,
+        order: Order,
+        market: Decimal,
+        instrument: &Instrument,
+    ) -> Result<Option<Order>, sqlx::Error> {
+        if !order.order_type.is_stop() || order.triggered_at.is_some() {
+            return Ok(Some(order));
+        }
+        let (reference, stop) = match trail(&order, market, instrument) {
+            Some((reference, stop)) => (Some(reference), Some(stop)),
+            None => (order.trail_reference, order.stop_price),
+        };
+        let triggered = stop
+            .is_some_and(|stop| stop_triggered(order.side, *stop, market))
+            .then(OffsetDateTime::now_utc);
+        if reference == order.trail_reference && triggered.is_none() {
+            return Ok(Some(order));
+        }
+        if triggered.is_some() {
+            info!(order = %order.uuid, %market, "stop triggered");
+        }
+        self.database
+            .update_order_stop(&order, stop, reference, triggered)
+            .await
+    }
+
+    /// Takes an order that must fill at once off the book, when it can't
+    ///
+    /// Orders are only ever filled in full, so immediate or cancel and fill or
+    /// kill orders differ only in how they're closed.
+    async fn kill(&self, order: &Order) -> Result<(), sqlx::Error> {
+        let status = match order.time_in_force {
+            TimeInForce::Ioc => OrderStatus::Cancelled,
+            TimeInForce::Fok => OrderStatus::Rejected,
+            TimeInForce::Day | TimeInForce::Gtc | TimeInForce::Gtd => return Ok(()),
+        };
+        if let Some(order) = self.database.close_order(order, status).await? {
+            info!(order = %order.uuid, status = ?order.status, "order killed");
+        }
+        Ok(())
+    }
+
+    /// Cancels an order in a symbol that's been delisted, or isn't listed
+    async fn delist(&self, order: &Order) -> Result<(), sqlx::Error> {
+        if let Some(order) = self
+            .database
+            .close_order(order, OrderStatus::Cancelled)
+            .await?
+        {
+            info!(order = %order.uuid, symbol = order.symbol, "order cancelled, symbol delisted");
+        }
+        Ok(())
+    }
+
+    /// The slippage model an account's fills use, & its base currency
+    async fn slippage_model(
+        &self,
+        account_id: i32,
+    ) -> Result<(Arc<dyn SlippageModel>, String), sqlx::Error> {
+        let account = self.database.get_account_by_id(account_id).await?;
+        Ok((
+            slippage::model(account.slippage_model, account.slippage_bps)
+                .unwrap_or_else(|| self.slippage.clone()),
+            account.base_currency,
+        ))
+    }
+
+    /// A symbol's volume over the day before `now`, logging any failure
+    async fn volume(&self, symbol: &str, now: OffsetDateTime) -> Option<Decimal> {
+        let day = time::Duration::DAY;
+        match self
+            .market
+            .bars(symbol, day.try_into().ok()?, now - day, now)
+            .await
+        {
+            Ok(bars) => Some(bars.iter().map(|bar| bar.volume).sum()),
+            Err(e) => {
+                warn!(symbol, "failed to get volume: {e}");
+                None
+            }
+        }
+    }
+
+    /// The latest quote for a symbol, logging any failure
+    async fn quote(&self, symbol: &str) -> Option<Quote> {
+        match self.market.quote(symbol).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                warn!(symbol, "failed to quote: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// The price an order fills at, if the market price crosses it
+///
+/// Market orders, and stop orders once triggered, fill at the market price.
+/// Limit orders, and stop limit orders once triggered, fill at the market
+/// price once it is at or better than their limit. This is before any
+/// slippage.
+#[must_use]
+pub fn fill_price(order: &Order, market: Decimal) -> Option<Decimal> {
+    let triggered = order.triggered_at.is_some();
+    let limit = || match (order.side, order.limit_price?) {
+        (OrderSide::Buy, limit) if market <= *limit => Some(market),
+        (OrderSide::Sell, limit) if market >= *limit => Some(market),
+        _ => None,
+    };
+    match order.order_type {
+        OrderType::Market => Some(market),
+        OrderType::Limit => limit(),
+        OrderType::Stop | OrderType::TrailingStop => triggered.then_some(market),
+        OrderType::StopLimit => limit().filter(|_| triggered),
+    }
+}
+
+/// How a slipped price is rounded to the tick, against the trader
+const fn against(side: OrderSide) -> RoundingStrategy {
+    match side {
+        OrderSide::Buy => RoundingStrategy::ToPositiveInfinity,
+        OrderSide::Sell => RoundingStrategy::ToNegativeInfinity,
+    }
+}
+
+/// The net price of a spread's legs, each given with its side, its quantity
+/// in a unit of the spread, its price & its symbol's multiplier
+///
+/// This is what the legs bought cost less what those sold raise, per unit of
+/// the largest multiplier, a debit when positive and a credit when negative.
+#[must_use]
+pub fn net_price(
+    legs: impl IntoIterator<Item = (OrderSide, Decimal, Decimal, Decimal)>,
+) -> Decimal {
+    let legs: Vec<_> = legs.into_iter().collect();
+    let Some(unit) = legs.iter().map(|(_, _, _, multiplier)| *multiplier).max() else {
+        return Decimal::ZERO;
+    };
+    legs.iter()
+        .map(|(side, ratio, price, multiplier)| {
+            let cash = ratio * price * multiplier;
+            match side {
+                OrderSide::Buy => cash,
+                OrderSide::Sell => -cash,
+            }
+        })
+        .sum::<Decimal>()
+        / unit
+}
+
+/// Whether an order accepts a price, given its limit if it has one
+#[must_use]
+pub fn within_limit(order: &Order, price: Decimal) -> bool {
+    order.limit_price.is_none_or(|limit| match order.side {
+        OrderSide::Buy => price <= *limit,
+        OrderSide::Sell => price >= *limit,
+    })
+}
+
+/// Whether the market has moved through a stop price
+///
+/// Buy stops sit above the market & trigger as it rises to them, sell stops
+/// sit below it & trigger as it falls to them.
+#[must_use]
+pub fn stop_triggered(side: OrderSide, stop: Decimal, market: Decimal) -> bool {
+    match side {
+        OrderSide::Buy => market >= stop,
+        OrderSide::Sell => market <= stop,
+    }
+}
+
+/// Moves a trailing stop's reference to the market, if the market has moved
+/// in the holder's favour
+///
+/// Returns the new reference & stop price, or `None` if the order isn't a
+/// trailing stop or the market hasn't moved in its favour. Sell stops trail
+/// the highest price, buy stops the lowest.
+#[must_use]
+pub fn trail(order: &Order, market: Decimal, instrument: &Instrument) -> Option<(Money, Money)> {
+    if order.order_type != OrderType::TrailingStop {
+        return None;
+    }
+    let favoured = order
+        .trail_reference
+        .is_none_or(|reference| match order.side {
+            OrderSide::Buy => market < *reference,
+            OrderSide::Sell => market > *reference,
+        });
+    let stop = trail_stop(
+        order.side,
+        market,
+        order.trail_amount,
+        order.trail_percent,
+        instrument,
+    )?;
+    favoured.then_some((Money::round(market), stop))
+}
+
+/// The stop price trailing `reference` by either an amount or a percentage
+///
+/// The stop is rounded to the instrument's tick, and never falls below one.
+#[must_use]
+pub fn trail_stop(
+    side: OrderSide,
+    reference: Decimal,
+    amount: Option<Money>,
+    percent: Option<Decimal>,
+    instrument: &Instrument,
+) -> Option<Money> {
+    let offset = amount
+        .map(Money::get)
+        .or_else(|| percent.map(|percent| reference * percent / Decimal::ONE_HUNDRED))?;
+    let stop = match side {
+        OrderSide::Buy => reference + offset,
+        OrderSide::Sell => reference - offset,
+    };
+    let stop = instrument.round_price(stop, RoundingStrategy::MidpointNearestEven);
+    Some(Money::round(stop))
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::OrderOrigin;
+
+    /// An open order for 10 AAPL, untriggered
+    fn order(side: OrderSide, order_type: OrderType, limit_price: Option<Money>) -> Order {
+        Order {
+            id: 1,
+            uuid: Uuid::nil(),
+            account_id: 1,
+            symbol: "AAPL".to_owned(),
+            side,
+            order_type,
+            quantity: Quantity::from(10),
+            limit_price,
+            stop_price: None,
+            trail_amount: None,
+            trail_percent: None,
+            trail_reference: None,
+            triggered_at: None,
+            time_in_force: TimeInForce::default(),
+            expires_at: None,
+            extended_hours: false,
+            group_id: None,
+            parent_id: None,
+            origin: OrderOrigin::default(),
+            status: OrderStatus::Open,
+            filled_quantity: Quantity::ZERO,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            updated_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn market_orders_fill_at_the_market() {
+        let buy = order(OrderSide::Buy, OrderType::Market, None);
+        assert_eq!(fill_price(&buy, Decimal::TEN), Some(Decimal::TEN));
+    }
+
+    #[test]
+    fn limit_orders_fill_once_the_market_crosses_their_limit() {
+        let limit = Some(Money::from(10));
+        let buy = order(OrderSide::Buy, OrderType::Limit, limit);
+        let sell = order(OrderSide::Sell, OrderType::Limit, limit);
+
+        assert_eq!(fill_price(&buy, Decimal::from(9)), Some(Decimal::from(9)));
+        assert_eq!(fill_price(&buy, Decimal::TEN), Some(Decimal::TEN));
+        assert_eq!(fill_price(&buy, Decimal::from(11)), None);
+        assert_eq!(fill_price(&sell, Decimal::from(9)), None);
+        assert_eq!(
+            fill_price(&sell, Decimal::from(11)),
+            Some(Decimal::from(11))
+        );
+    }
+
+    #[test]
+    fn stop_orders_fill_once_triggered() {
+        let mut stop = order(OrderSide::Sell, OrderType::Stop, None);
+        let mut stop_limit = order(OrderSide::Sell, OrderType::StopLimit, Some(Money::from(10)));
+        assert_eq!(fill_price(&stop, Decimal::TEN), None);
+        assert_eq!(fill_price(&stop_limit, Decimal::TEN), None);
+
+        stop.triggered_at = Some(OffsetDateTime::UNIX_EPOCH);
+        stop_limit.triggered_at = Some(OffsetDateTime::UNIX_EPOCH);
+
+        assert_eq!(fill_price(&stop, Decimal::from(9)), Some(Decimal::from(9)));
+        assert_eq!(fill_price(&stop_limit, Decimal::from(9)), None);
+        assert_eq!(fill_price(&stop_limit, Decimal::TEN), Some(Decimal::TEN));
+    }
+
+    #[test]
+    fn slipped_prices_stay_within_the_limit() {
+        let market = order(OrderSide::Buy, OrderType::Market, None);
+        let buy = order(OrderSide::Buy, OrderType::Limit, Some(Money::from(10)));
+        let sell = order(OrderSide::Sell, OrderType::Limit, Some(Money::from(10)));
+
+        assert!(within_limit(&market, Decimal::MAX));
+        assert!(within_limit(&buy, Decimal::TEN));
+        assert!(!within_limit(&buy, Decimal::new(1001, 2)));
+        assert!(within_limit(&sell, Decimal::TEN));
+        assert!(!within_limit(&sell, Decimal::new(999, 2)));
+    }
+
+    #[test]
+    fn trailing_stops_trail_by_an_amount_or_a_percentage() {
+        let instrument = Instrument::unlisted("AAPL".to_owned());
+        let reference = Decimal::ONE_HUNDRED;
+        let amount = Some(Money::new(250, 2));
+        let percent = Some(Decimal::new(15, 1));
+
+        let sell = trail_stop(OrderSide::Sell, reference, amount, None, &instrument);
+        let buy = trail_stop(OrderSide::Buy, reference, None, percent, &instrument);
+
+        assert_eq!(sell, Some(Money::new(9750, 2)));
+        assert_eq!(buy, Some(Money::new(10150, 2)));
+        assert_eq!(
+            trail_stop(OrderSide::Sell, reference, None, None, &instrument),
+            None
+        );
+    }
+
+    #[test]
+    fn trailing_stops_round_to_the_tick_and_stay_above_it() {
+        let instrument = Instrument::unlisted("AAPL".to_owned());
+        let percent = Some(Decimal::new(33, 1));
+
+        let stop = trail_stop(
+            OrderSide::Sell,
+            Decimal::new(1234, 2),
+            None,
+            percent,
+            &instrument,
+        );
+        let floor = trail_stop(
+            OrderSide::Sell,
+            Decimal::ONE,
+            Some(Money::from(10)),
+            None,
+            &instrument,
+        );
+
+        assert_eq!(stop, Some(Money::new(1193, 2)));
+        assert_eq!(floor, Some(instrument.tick_size));
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use time::{Date, OffsetDateTime};
+use tracing::{info, warn};
+
+use crate::{
+    calendar::Calendar,
+    market::{self, MarketData},
+    models::Instrument,
+    state::persist::Database,
+};
+
+/// Settles futures daily, and for the last time as they expire
+#[derive(Debug)]
+pub struct FuturesSettler {
+    database: Database,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
+}
+
+impl FuturesSettler {
+    /// Creates a new settler, settling futures at their price in the given
+    /// market, and for the last time at their close as their exchange closes
+    /// on their expiry date
+    #[must_use]
+    pub fn new(database: Database, market: Arc<dyn MarketData>, calendar: Arc<Calendar>) -> Self {
+        Self {
+            database,
+            market,
+            calendar,
+        }
+    }
+
+    /// Runs a single pass over every future yet to settle today
+    ///
+    /// Futures settle once a day, in UTC, at their last price, posting each
+    /// position's variation margin to its account's cash, see
+    /// [`Database::settle_future`]. Those that expired before today settle
+    /// for the last time at their close on their expiry date, however long
+    /// after, closing their positions. Futures without a price wait until
+    /// they have one. Positions still held past their future's roll date are
+    /// logged, as they'll soon be closed.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let today = OffsetDateTime::now_utc().date();
+        for future in self.database.get_unsettled_futures(today).await? {
+            let Some(price) = self.price(&future, today).await else {
+                continue;
+            };
+            let Some(positions) = self.database.settle_future(&future, price, today).await? else {
+                continue;
+            };
+            let expired = future
+                .expires_on
+                .is_some_and(|expires_on| expires_on < today);
+            info!(
+                symbol = future.symbol,
+                %price,
+                expired,
+                positions,
+                "future settled"
+            );
+            if !expired && positions > 0 && future.roll_on.is_some_and(|roll_on| roll_on < today) {
+                warn!(
+                    symbol = future.symbol,
+                    positions, "positions held past their roll date"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The price a future settles at today, its last price, or its close on
+    /// its expiry date once it's expired
+    ///
+    /// Returns `None`, logging why, if it has no price.
+    async fn price(&self, future: &Instrument, today: Date) -> Option<Decimal> {
+        let symbol = &future.symbol;
+        let price = match future.expires_on.filter(|expires_on| *expires_on < today) {
+            Some(expires_on) => {
+                let close = self.calendar.close_on(future, expires_on);
+                market::close_as_of(self.market.as_ref(), symbol, close).await
+            }
+            None => self
+                .market
+                .quote(symbol)
+                .await
+                .map(|quote| quote.map(|quote| quote.last)),
+        };
+        match price {
+            Ok(Some(price)) => Some(price),
+            Ok(None) => {
+                warn!(symbol, "future has no price, can't settle");
+                None
+            }
+            Err(e) => {
+                warn!(symbol, "failed to price: {e}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::Read;
+
+use crate::{
+    models::{is_currency, Instrument},
+    numeric::{Money, Quantity},
+    state::persist::Database,
+};
+
+/// The registry bundled with the server
+const BUNDLED: &str = include_str!("../data/instruments.csv");
+/// The futures bundled with the server, apart from the rest as they've
+/// columns of their own
+const BUNDLED_FUTURES: &str = include_str!("../data/futures.csv");
+
+/// Seed the registry with the symbols bundled with the server, from
+/// `data/instruments.csv` & `data/futures.csv`
+///
+/// Bundled symbols replace those already listed, so delistings & corrections
+/// take effect as the server starts. Returns the number of symbols written.
+///
+/// # Errors
+///
+/// See [`InstrumentError`]
+pub async fn seed_instruments(database: &Database) -> Result<u64, InstrumentError> {
+    Ok(import_instruments(database, BUNDLED.as_bytes()).await?
+        + import_instruments(database, BUNDLED_FUTURES.as_bytes()).await?)
+}
+
+/// Import symbols from a csv file into the registry
+///
+/// See [`read_instruments`] for the accepted format. Returns the number of
+/// symbols written.
+///
+/// # Errors
+///
+/// See [`InstrumentError`]
+pub async fn import_instruments(
+    database: &Database,
+    reader: impl Read,
+) -> Result<u64, InstrumentError> {
+    let instruments = read_instruments(reader)?;
+    Ok(database.add_instruments(&instruments).await?)
+}
+
+/// Read symbols from a csv file
+///
+/// Takes `symbol,name,exchange,asset_class,currency,tick_size,lot_size,
+/// fractional,shortable,active` columns, matched by name. Asset classes are
+/// `equity`, `etf`, `option`, `future` or `crypto`, currencies three letter
+/// codes, and the flags `true` or `false`. Tick & lot sizes must be positive.
+/// Optional `multiplier,underlying,option_type,strike,expires_on` columns
+/// describe option contracts, which need all but the multiplier, the option
+/// type being `call` or `put` & the expiry `YYYY-MM-DD`. Multipliers default
+/// to 1, and must be positive. Futures need an expiry & positive
+/// `initial_margin,maintenance_margin` columns, per contract, the
+/// maintenance margin no more than the initial one, and may have a
+/// `roll_on` date no later than their expiry. Crypto symbols are pairs of
+/// their base & quote currencies, like `BTC-USD`, priced in the quote.
+///
+/// # Errors
+///
+/// See [`InstrumentError`]
+pub fn read_instruments(reader: impl Read) -> Result<Vec<Instrument>, InstrumentError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut instruments = Vec::new();
+    for (row, record) in reader.deserialize().enumerate() {
+        let mut instrument: Instrument = record?;
+        // the header is line 1
+        let line = row + 2;
+        let invalid = |column: &'static str| InstrumentError::InvalidRow { line, column };
+        instrument.symbol = instrument.symbol.to_ascii_uppercase();
+        instrument.exchange = instrument.exchange.to_ascii_uppercase();
+        instrument.currency = instrument.currency.to_ascii_uppercase();
+        instrument.underlying = instrument
+            .underlying
+            .map(|underlying| underlying.to_ascii_uppercase());
+        if instrument.symbol.is_empty() {
+            return Err(invalid("symbol"));
+        }
+        if instrument.exchange.is_empty() {
+            return Err(invalid("exchange"));
+        }
+        if !is_currency(&instrument.currency) {
+            return Err(invalid("currency"));
+        }
+        if instrument.tick_size <= Money::ZERO {
+            return Err(invalid("tick_size"));
+        }
+        if instrument.lot_size <= Quantity::ZERO {
+            return Err(invalid("lot_size"));
+        }
+        if instrument.multiplier <= Quantity::ZERO {
+            return Err(invalid("multiplier"));
+        }
+        if instrument.is_option() {
+            if instrument.underlying.is_none() {
+                return Err(invalid("underlying"));
+            }
+            if instrument.option_type.is_none() {
+                return Err(invalid("option_type"));
+            }
+            if instrument.strike.is_none_or(|strike| strike <= Money::ZERO) {
+                return Err(invalid("strike"));
+            }
+            if instrument.expires_on.is_none() {
+                return Err(invalid("expires_on"));
+            }
+        }
+        if instrument.is_crypto()
+            && Instrument::pair(&instrument.symbol)
+                .is_none_or(|(_, quote)| quote != instrument.currency)
+        {
+            return Err(invalid("symbol"));
+        }
+        if instrument.is_future() {
+            let Some(expires_on) = instrument.expires_on else {
+                return Err(invalid("expires_on"));
+            };
+            let Some(initial) = instrument
+                .initial_margin
+                .filter(|margin| *margin > Money::ZERO)
+            else {
+                return Err(invalid("initial_margin"));
+            };
+            if instrument
+                .maintenance_margin
+                .is_none_or(|margin| margin <= Money::ZERO || margin > initial)
+            {
+                return Err(invalid("maintenance_margin"));
+            }
+            if instrument
+                .roll_on
+                .is_some_and(|roll_on| roll_on > expires_on)
+            {
+                return Err(invalid("roll_on"));
+            }
+        }
+        instruments.push(instrument);
+    }
+    Ok(instruments)
+}
+
+/// An error while importing symbols
+#[derive(Debug, thiserror::Error)]
+pub enum InstrumentError {
+    /// The file couldn't be read as csv, or a column is missing or couldn't
+    /// be parsed
+    #[error("Csv error: {0}")]
+    Csv(#[from] csv::Error),
+    /// A row's value is out of range
+    #[error("Invalid {column} on line {line}")]
+    InvalidRow {
+        /// The 1-based line of the row
+        line: usize,
+        /// The column that's out of range
+        column: &'static str,
+    },
+    /// The symbols couldn't be stored
+    #[error("Database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
@@ -0,0 +1,64 @@
+use time::{Duration, OffsetDateTime};
+use tracing::info;
+
+use crate::state::persist::Database;
+
+/// Accrues interest on cash daily, and posts it monthly
+#[derive(Debug)]
+pub struct InterestAccruer {
+    database: Database,
+}
+
+impl InterestAccruer {
+    /// Creates a new accruer
+    #[must_use]
+    pub const fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Runs a single pass, accruing interest for every day that's over &
+    /// posting what accrued before this month
+    ///
+    /// Every balance accrues a day's interest once the day is over, in UTC,
+    /// on the cash held at its close, see [`Database::accrue_interest`].
+    /// Days missed while the server wasn't running are back-filled, each at
+    /// the rate in effect that day. Once a month begins, what each account
+    /// accrued in each currency before it is posted to the account's cash,
+    /// see [`Database::post_interest`].
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let today = OffsetDateTime::now_utc().date();
+        let yesterday = today - Duration::days(1);
+        for (date, balance) in self.database.get_unaccrued_balances(yesterday).await? {
+            if let Some(interest) = self.database.accrue_interest(&balance, date).await? {
+                info!(
+                    account = balance.account_id,
+                    currency = balance.currency,
+                    %date,
+                    %interest,
+                    "interest accrued"
+                );
+            }
+        }
+        let month = today - Duration::days(i64::from(today.day()) - 1);
+        for unposted in self.database.get_unposted_interest(month).await? {
+            let currency = &unposted.currency;
+            if let Some(interest) = self
+                .database
+                .post_interest(unposted.account_id, currency, month)
+                .await?
+            {
+                info!(
+                    account = unposted.account_id,
+                    currency,
+                    %interest,
+                    "interest posted"
+                );
+            }
+        }
+        Ok(())
+    }
+}
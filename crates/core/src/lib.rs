@@ -3,13 +3,13 @@
 #![allow(clippy::wildcard_imports)]
 #![allow(clippy::module_name_repetitions)]
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use axum::{
     extract::State,
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, patch, post},
     Json, Router,
 };
 use axum_login::{login_required, AuthManagerLayerBuilder};
@@ -28,39 +28,109 @@ use tracing_subscriber::{
     filter::FromEnvError, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
 };
 
+use actions::ActionProcessor;
 use auth::{AuthSession, Backend, Credentials};
-use state::{persist::error::ConnectionError, AddUserAction, Context, ValidationRules};
+use calendar::Calendar;
+use engine::Engine;
+use futures::FuturesSettler;
+use interest::InterestAccruer;
+use margin::MarginMonitor;
+use market::MarketData;
+use options::OptionExpirer;
+use slippage::SlippageModel;
+use state::{
+    persist::{error::ConnectionError, Database},
+    AddUserAction, Context, ValidationRules,
+};
 
 // Re-Exports for binary crates
 pub use anyhow;
 pub use axum;
+pub use rust_decimal;
 pub use sqlx;
+pub use time;
 pub use tokio;
 pub use tower_sessions;
 pub use tracing;
 pub use tracing_subscriber;
 
+/// Corporate actions
+pub mod actions;
 /// Handles auth
 pub mod auth;
+/// Exchange trading calendars
+pub mod calendar;
+/// Fills orders
+pub mod engine;
+/// Futures & their daily settlement
+pub mod futures;
+/// The symbol registry
+pub mod instruments;
+/// Interest on cash & margin debit balances
+pub mod interest;
+/// Tax lot relief
+pub mod lots;
+/// Margin requirements & calls
+pub mod margin;
+/// Prices symbols
+pub mod market;
 /// models
 pub mod models;
+/// Exact money & quantities, as stored
+pub mod numeric;
+/// Equity options
+pub mod options;
+/// Handlers for the trading routes
+mod routes;
+/// Slippage models for simulated fills
+pub mod slippage;
 /// Handles state
 pub mod state;
 
 /// The main app
+///
+/// Background tasks log a pass that fails & carry on with the next, so their
+/// handles only finish if a task panics.
 pub struct App {
     /// The main router
     pub router: Router,
     /// The main deletion handle
     pub deletion_handle: JoinHandle<tower_sessions::session_store::Result<()>>,
+    /// The fill engine's handle
+    pub engine_handle: JoinHandle<()>,
+    /// The order expiry handle
+    pub expiry_handle: JoinHandle<()>,
+    /// The borrow fee handle
+    pub borrow_handle: JoinHandle<()>,
+    /// The margin monitor's handle
+    pub margin_handle: JoinHandle<()>,
+    /// The corporate action processor's handle
+    pub actions_handle: JoinHandle<()>,
+    /// The option expirer's handle
+    pub options_handle: JoinHandle<()>,
+    /// The futures settler's handle
+    pub futures_handle: JoinHandle<()>,
+    /// The interest accruer's handle
+    pub interest_handle: JoinHandle<()>,
 }
 
 /// Creates the standard router
 ///
+/// Orders are priced & filled against the given market data provider, while
+/// the calendar says their symbol's exchange is open. Fills slip by
+/// `slippage`, unless their account picks its own model. The bundled symbol
+/// registry is seeded first.
+///
 /// # Errors
 ///
 /// See [`CreateRouterError`]
-pub async fn router(key: Key, pool: PgPool) -> Result<App, CreateRouterError> {
+pub async fn router(
+    key: Key,
+    pool: PgPool,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
+    slippage: Arc<dyn SlippageModel>,
+) -> Result<App, CreateRouterError> {
     let session_store = PostgresStore::new(pool.clone());
     session_store
         .migrate()
@@ -72,6 +142,27 @@ pub async fn router(key: Key, pool: PgPool) -> Result<App, CreateRouterError> {
         Duration::from_secs(60),
     ));
 
+    let database = Database::new(pool.clone()).await?;
+    instruments::seed_instruments(&database).await?;
+    let expiry_handle = tokio::spawn(expiry_task(database.clone(), Duration::from_secs(60)));
+    let borrow_handle = tokio::spawn(borrow_task(
+        database.clone(),
+        market.clone(),
+        Duration::from_secs(60 * 60),
+    ));
+    let monitor = MarginMonitor::new(database.clone(), market.clone(), calendar.clone());
+    let margin_handle = tokio::spawn(margin_task(monitor, Duration::from_secs(10)));
+    let processor = ActionProcessor::new(database.clone(), market.clone());
+    let actions_handle = tokio::spawn(actions_task(processor, Duration::from_secs(60 * 60)));
+    let expirer = OptionExpirer::new(database.clone(), market.clone(), calendar.clone());
+    let options_handle = tokio::spawn(options_task(expirer, Duration::from_secs(60 * 60)));
+    let settler = FuturesSettler::new(database.clone(), market.clone(), calendar.clone());
+    let futures_handle = tokio::spawn(futures_task(settler, Duration::from_secs(60 * 60)));
+    let accruer = InterestAccruer::new(database.clone());
+    let interest_handle = tokio::spawn(interest_task(accruer, Duration::from_secs(60 * 60)));
+    let engine = Engine::new(database, market.clone(), calendar.clone(), slippage);
+    let engine_handle = tokio::spawn(engine_task(engine, Duration::from_secs(1)));
+
     let session_manager_layer = SessionManagerLayer::new(session_store)
         .with_secure(false)
         .with_expiry(Expiry::OnInactivity(time::Duration::days(1)))
@@ -92,7 +183,7 @@ pub async fn router(key: Key, pool: PgPool) -> Result<App, CreateRouterError> {
         name_min: 1,
         name_max: 128,
     };
-    let api = Context::new(pool, rules).await?;
+    let api = Context::new(pool, rules, market, calendar).await?;
     let api = Arc::new(api);
 
     let router = auth_routes()
@@ -103,6 +194,14 @@ pub async fn router(key: Key, pool: PgPool) -> Result<App, CreateRouterError> {
     Ok(App {
         router,
         deletion_handle,
+        engine_handle,
+        expiry_handle,
+        borrow_handle,
+        margin_handle,
+        actions_handle,
+        options_handle,
+        futures_handle,
+        interest_handle,
     })
 }
 
@@ -117,6 +216,134 @@ async fn deletion_task(
     }
 }
 
+async fn engine_task(engine: Engine, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = engine.run().await {
+            tracing::error!("engine pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn margin_task(monitor: MarginMonitor, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = monitor.run().await {
+            tracing::error!("margin pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn actions_task(processor: ActionProcessor, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = processor.run().await {
+            tracing::error!("actions pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn options_task(expirer: OptionExpirer, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = expirer.run().await {
+            tracing::error!("options pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn futures_task(settler: FuturesSettler, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = settler.run().await {
+            tracing::error!("futures pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn interest_task(accruer: InterestAccruer, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = accruer.run().await {
+            tracing::error!("interest pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+async fn expiry_task(database: Database, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = expire_orders(&database).await {
+            tracing::error!("expiry pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+/// Expires every order past its time in force
+async fn expire_orders(database: &Database) -> Result<(), sqlx::Error> {
+    for order in database
+        .expire_orders(time::OffsetDateTime::now_utc())
+        .await?
+    {
+        tracing::info!(order = %order.uuid, "order expired");
+    }
+    Ok(())
+}
+
+async fn borrow_task(database: Database, market: Arc<dyn MarketData>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        if let Err(e) = charge_borrow_fees(&database, market.as_ref()).await {
+            tracing::error!("borrow pass failed: {e}");
+        }
+        interval.tick().await;
+    }
+}
+
+/// Charges each short position a day's borrow fee, once a day, at its last
+/// price
+async fn charge_borrow_fees(
+    database: &Database,
+    market: &dyn MarketData,
+) -> Result<(), sqlx::Error> {
+    let today = time::OffsetDateTime::now_utc().date();
+    let rates: HashMap<_, _> = database
+        .get_borrow_rates()
+        .await?
+        .into_iter()
+        .map(|rate| (rate.symbol.clone(), rate))
+        .collect();
+    for position in database.get_unbilled_shorts(today).await? {
+        let symbol = &position.symbol;
+        let Some(rate) = rates.get(symbol) else {
+            tracing::warn!(symbol, "no borrow rate for short position");
+            continue;
+        };
+        let price = match market.quote(symbol).await {
+            Ok(Some(quote)) => quote.last,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(symbol, "failed to quote: {e}");
+                continue;
+            }
+        };
+        if let Some(fee) = database
+            .charge_borrow_fee(&position, rate, today, price)
+            .await?
+        {
+            tracing::info!(symbol, %fee, "borrow fee charged");
+        }
+    }
+    Ok(())
+}
+
 /// Creates the actual routes
 fn auth_routes() -> Router<Api> {
     Router::new()
@@ -129,6 +356,36 @@ fn protected_routes() -> Router<Api> {
     Router::new()
         .route("/api/check-login", get(check_login))
         .route("/api/log-out", post(logout))
+        .route(
+            "/api/orders",
+            post(routes::place_order).get(routes::list_orders),
+        )
+        .route("/api/orders/:id", delete(routes::cancel_order))
+        .route(
+            "/api/order-groups",
+            post(routes::place_order_group).get(routes::list_order_groups),
+        )
+        .route("/api/order-groups/:id", delete(routes::cancel_order_group))
+        .route("/api/accounts", get(routes::list_accounts))
+        .route("/api/accounts/:id", patch(routes::update_account))
+        .route("/api/fee-schedules", get(routes::list_fee_schedules))
+        .route("/api/borrow-rates", get(routes::list_borrow_rates))
+        .route("/api/interest-rates", get(routes::list_interest_rates))
+        .route("/api/instruments", get(routes::search_instruments))
+        .route("/api/options/:symbol", get(routes::get_option_chain))
+        .route(
+            "/api/corporate-actions",
+            get(routes::list_corporate_actions),
+        )
+        .route("/api/margin", get(routes::get_margin))
+        .route("/api/positions", get(routes::list_positions))
+        .route("/api/positions/:symbol", patch(routes::update_position))
+        .route("/api/balances", get(routes::list_cash_balances))
+        .route(
+            "/api/conversions",
+            post(routes::convert_currency).get(routes::list_conversions),
+        )
+        .route("/api/interest", get(routes::list_interest_accruals))
         .route_layer(login_required!(Backend))
 }
 
@@ -175,6 +432,9 @@ pub enum CreateRouterError {
     /// db connection/setup error
     #[error(transparent)]
     ConnectionError(#[from] ConnectionError),
+    /// The bundled symbol registry couldn't be seeded
+    #[error(transparent)]
+    InstrumentError(#[from] instruments::InstrumentError),
 }
 
 type Api = Arc<Context>;
@@ -0,0 +1,129 @@
+use rust_decimal::Decimal;
+
+use crate::models::{Lot, LotMethod};
+
+/// A quantity taken from a [`Lot`] by a closing trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relief {
+    /// The relieved lot's id
+    pub lot_id: i32,
+    /// The quantity taken, negative for short lots
+    pub quantity: Decimal,
+    /// The price the lot was opened at
+    pub price: Decimal,
+}
+
+/// Picks the lots a closing trade of `quantity` relieves, in the order
+/// `method` gives
+///
+/// Sales relieve long lots, and buys to cover relieve short lots, so the lots
+/// are all on one side. Lots are taken whole until the last, which may only
+/// be taken in part. If the lots hold less than `quantity`, they're all taken.
+#[must_use]
+pub fn relieve(mut lots: Vec<Lot>, method: LotMethod, quantity: Decimal) -> Vec<Relief> {
+    match method {
+        LotMethod::Fifo => lots.sort_by_key(|lot| (lot.opened_at, lot.id)),
+        LotMethod::Lifo => lots.sort_by_key(|lot| std::cmp::Reverse((lot.opened_at, lot.id))),
+        LotMethod::HighestCost => {
+            lots.sort_by_key(|lot| (std::cmp::Reverse(lot.price), lot.opened_at, lot.id));
+        }
+    }
+    let mut left = quantity;
+    lots.into_iter()
+        .map_while(|lot| {
+            let taken = lot.quantity.abs().get().min(left);
+            left -= taken;
+            (taken > Decimal::ZERO).then_some(Relief {
+                lot_id: lot.id,
+                quantity: if lot.quantity.is_sign_negative() {
+                    -taken
+                } else {
+                    taken
+                },
+                price: *lot.price,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use super::*;
+    use crate::numeric::{Money, Quantity};
+
+    /// A lot of `quantity` AAPL at `price`, opened `days` after the epoch
+    fn lot(id: i32, quantity: i32, price: u32, days: i64) -> Lot {
+        Lot {
+            id,
+            account_id: 1,
+            symbol: "AAPL".to_owned(),
+            fill_id: id,
+            quantity: Quantity::try_from(Decimal::from(quantity)).unwrap(),
+            price: Money::from(price),
+            opened_at: OffsetDateTime::UNIX_EPOCH + Duration::days(days),
+        }
+    }
+
+    /// Three long lots, the cheapest opened first & the dearest second
+    fn lots() -> Vec<Lot> {
+        vec![lot(1, 10, 100, 0), lot(2, 10, 120, 1), lot(3, 10, 110, 2)]
+    }
+
+    /// The lots relieved, & how much of each
+    fn taken(reliefs: &[Relief]) -> Vec<(i32, Decimal)> {
+        reliefs
+            .iter()
+            .map(|relief| (relief.lot_id, relief.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn fifo_relieves_the_oldest_lots_first() {
+        let reliefs = relieve(lots(), LotMethod::Fifo, Decimal::from(15));
+
+        assert_eq!(taken(&reliefs), [(1, Decimal::TEN), (2, Decimal::from(5))]);
+        assert_eq!(reliefs[0].price, Decimal::ONE_HUNDRED);
+    }
+
+    #[test]
+    fn lifo_relieves_the_newest_lots_first() {
+        let reliefs = relieve(lots(), LotMethod::Lifo, Decimal::from(15));
+
+        assert_eq!(taken(&reliefs), [(3, Decimal::TEN), (2, Decimal::from(5))]);
+    }
+
+    #[test]
+    fn highest_cost_relieves_the_dearest_lots_first() {
+        let reliefs = relieve(lots(), LotMethod::HighestCost, Decimal::from(15));
+
+        assert_eq!(taken(&reliefs), [(2, Decimal::TEN), (3, Decimal::from(5))]);
+    }
+
+    #[test]
+    fn the_last_lot_may_be_taken_in_part() {
+        let reliefs = relieve(lots(), LotMethod::Fifo, Decimal::new(25, 1));
+
+        assert_eq!(taken(&reliefs), [(1, Decimal::new(25, 1))]);
+    }
+
+    #[test]
+    fn short_lots_are_relieved_negative() {
+        let lots = vec![lot(1, -10, 100, 0), lot(2, -10, 120, 1)];
+
+        let reliefs = relieve(lots, LotMethod::Fifo, Decimal::from(12));
+
+        assert_eq!(taken(&reliefs), [(1, -Decimal::TEN), (2, -Decimal::TWO)]);
+    }
+
+    #[test]
+    fn selling_more_than_is_held_takes_every_lot() {
+        let reliefs = relieve(lots(), LotMethod::Fifo, Decimal::from(50));
+
+        assert_eq!(
+            taken(&reliefs),
+            [(1, Decimal::TEN), (2, Decimal::TEN), (3, Decimal::TEN)]
+        );
+    }
+}
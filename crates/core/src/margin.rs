@@ -0,0 +1,681 @@
+use std::{cmp::Reverse, collections::HashMap, sync::Arc};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+use time::{Date, OffsetDateTime};
+use tracing::{info, warn};
+
+use crate::{
+    calendar::Calendar,
+    market::MarketData,
+    models::{
+        Account, AccountType, Instrument, NewOrder, OptionType, OrderOrigin, OrderSide, OrderType,
+        Position, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+    state::persist::Database,
+};
+
+/// An account's equity against its margin requirements, valued at the market
+#[derive(Debug, Clone, Serialize)]
+pub struct MarginReport {
+    /// Whether the account trades on margin
+    pub account_type: AccountType,
+    /// The currency the report is valued in, the account's base currency
+    pub currency: String,
+    /// The account's cash, in every currency, negative when borrowing
+    pub cash: Decimal,
+    /// The value of the account's long positions
+    pub long_value: Decimal,
+    /// The value of the account's short positions, as a positive amount
+    pub short_value: Decimal,
+    /// The account's cash & long positions, less what its shorts owe
+    pub equity: Decimal,
+    /// The equity needed to open the account's positions
+    pub initial_requirement: Decimal,
+    /// The equity needed to keep the account's positions open
+    pub maintenance_requirement: Decimal,
+    /// The value of new positions the account can still open
+    pub buying_power: Decimal,
+    /// When the account's equity fell below its maintenance requirement, if
+    /// it still is
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub margin_call_at: Option<OffsetDateTime>,
+}
+
+impl MarginReport {
+    /// Values an account holding `cash` & its positions, after `reserved` is
+    /// committed to open buys
+    ///
+    /// Everything's given in the account's base currency. Unquoted positions
+    /// are valued at cost, and futures at the profit they've yet to settle,
+    /// see [`ValuedPosition::value`]. Cash accounts have no margin
+    /// requirements, and can buy with their cash less the proceeds their
+    /// shorts hold. Margin
+    /// accounts require a share of their positions' value, see
+    /// [`requirement`], and can buy as much as their equity past the initial
+    /// requirement covers.
+    #[must_use]
+    pub fn new(
+        account: &Account,
+        cash: Decimal,
+        positions: &[ValuedPosition],
+        reserved: Decimal,
+    ) -> Self {
+        let (mut long_value, mut short_value, mut short_basis) =
+            (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+        for valued in positions {
+            let value = valued.value();
+            if valued.position.quantity < Quantity::ZERO {
+                short_value -= value;
+                if !valued.instrument.is_future() {
+                    short_basis += valued.cost_basis;
+                }
+            } else {
+                long_value += value;
+            }
+        }
+        let equity = cash + long_value - short_value;
+        let holdings: Vec<_> = positions.iter().map(ValuedPosition::holding).collect();
+        let requirement = |kind: Requirement| match account.account_type {
+            AccountType::Cash => Decimal::ZERO,
+            AccountType::Margin => requirement(&holdings, account, kind),
+        };
+        let initial_requirement = requirement(Requirement::Initial);
+        let buying_power = match account.account_type {
+            AccountType::Cash => cash + short_basis,
+            AccountType::Margin => {
+                (equity - initial_requirement) * Decimal::ONE_HUNDRED / account.initial_margin
+            }
+        } - reserved;
+        Self {
+            account_type: account.account_type,
+            currency: account.base_currency.clone(),
+            cash,
+            long_value,
+            short_value,
+            equity,
+            initial_requirement,
+            maintenance_requirement: requirement(Requirement::Maintenance),
+            buying_power,
+            margin_call_at: account.margin_call_at,
+        }
+    }
+
+    /// Whether the account's equity is below its maintenance requirement
+    #[must_use]
+    pub fn is_margin_call(&self) -> bool {
+        self.equity < self.maintenance_requirement
+    }
+}
+
+/// A position, with its symbol & last price, valued in its account's base
+/// currency
+#[derive(Debug, Clone)]
+pub struct ValuedPosition {
+    /// The position itself
+    pub position: Position,
+    /// The position's cost basis, in the base currency if its rate is quoted
+    pub cost_basis: Decimal,
+    /// The value of a unit of the position, its last price scaled by its
+    /// symbol's multiplier, if both it & its rate are quoted
+    pub price: Option<Decimal>,
+    /// The market's units of the base currency a unit of the symbol's
+    /// currency, if it's quoted
+    pub rate: Option<Decimal>,
+    /// The position's symbol
+    pub instrument: Instrument,
+}
+
+impl ValuedPosition {
+    /// The position's value, at its last price or else at cost, negative
+    /// when short
+    ///
+    /// Futures are held without paying for them, so are only worth what
+    /// they've made since they last settled, or nothing unquoted.
+    #[must_use]
+    pub fn value(&self) -> Decimal {
+        let (quantity, basis) = (*self.position.quantity, self.cost_basis);
+        match self.price {
+            Some(price) if self.instrument.is_future() => price * quantity - basis,
+            None if self.instrument.is_future() => Decimal::ZERO,
+            Some(price) => price * quantity,
+            None => basis,
+        }
+    }
+
+    /// The position as it's margined
+    #[must_use]
+    pub fn holding(&self) -> Holding<'_> {
+        Holding {
+            instrument: &self.instrument,
+            quantity: *self.position.quantity,
+            value: self.value(),
+            scale: self
+                .rate
+                .filter(|_| self.price.is_some())
+                .map(|rate| *self.instrument.multiplier * rate),
+        }
+    }
+}
+
+/// A quantity of a symbol held, as it's margined
+#[derive(Debug, Clone, Copy)]
+pub struct Holding<'a> {
+    /// The held symbol
+    pub instrument: &'a Instrument,
+    /// The quantity held, negative when short
+    pub quantity: Decimal,
+    /// What the quantity is worth, in the account's base currency, negative
+    /// when short
+    pub value: Decimal,
+    /// What a unit of the symbol's price is worth in the base currency, if
+    /// its rate is quoted & the holding is valued at the market
+    pub scale: Option<Decimal>,
+}
+
+/// One of an account's margin requirements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// The equity needed to open positions
+    Initial,
+    /// The equity needed to keep positions open
+    Maintenance,
+}
+
+/// The equity an account's holdings require
+///
+/// Each holding requires the account's margin of its value, long or short,
+/// unless it's a future or an option contract in a spread. Futures require
+/// their own margin for each contract, long or short. Contracts on the same
+/// underlying & expiry, valued at the market, form a spread if some are held
+/// & others written, and one whose loss is bounded requires its maximum loss
+/// instead, what it's worth less the least it may be worth at expiry,
+/// whatever the margin.
+#[must_use]
+pub fn requirement(holdings: &[Holding<'_>], account: &Account, kind: Requirement) -> Decimal {
+    let margin = match kind {
+        Requirement::Initial => account.initial_margin,
+        Requirement::Maintenance => account.maintenance_margin,
+    };
+    let percent = |value: Decimal| value.abs() * margin / Decimal::ONE_HUNDRED;
+    let mut required = Decimal::ZERO;
+    let mut spreads: HashMap<(&str, Date), Vec<Holding<'_>>> = HashMap::new();
+    for holding in holdings {
+        let instrument = holding.instrument;
+        if instrument.is_future() {
+            let contract = match kind {
+                Requirement::Initial => instrument.initial_margin,
+                Requirement::Maintenance => instrument.maintenance_margin,
+            };
+            // margins are in the future's currency
+            let rate = holding
+                .scale
+                .map_or(Decimal::ONE, |scale| scale / *instrument.multiplier);
+            required += holding.quantity.abs() * *contract.unwrap_or_default() * rate;
+            continue;
+        }
+        match (&instrument.underlying, instrument.expires_on, holding.scale) {
+            (Some(underlying), Some(expires_on), Some(_)) if instrument.is_option() => {
+                spreads
+                    .entry((underlying.as_str(), expires_on))
+                    .or_default()
+                    .push(*holding);
+            }
+            _ => required += percent(holding.value),
+        }
+    }
+    for legs in spreads.values() {
+        let held = legs.iter().any(|leg| leg.quantity > Decimal::ZERO);
+        let written = legs.iter().any(|leg| leg.quantity < Decimal::ZERO);
+        match worst_payoff(legs).filter(|_| held && written) {
+            Some(worst) => {
+                let value: Decimal = legs.iter().map(|leg| leg.value).sum();
+                required += (value - worst).max(Decimal::ZERO);
+            }
+            None => required += legs.iter().map(|leg| percent(leg.value)).sum::<Decimal>(),
+        }
+    }
+    required
+}
+
+/// The least option contracts on the same underlying & expiry are worth
+/// together at expiry, in the account's base currency, or `None` if their
+/// loss isn't bounded
+///
+/// What they're worth is piecewise linear in the underlying's price, bending
+/// only at strikes, so is least at a strike or with the underlying at zero,
+/// unless more calls are written than held, when it falls without bound as
+/// the underlying rises. Holdings that aren't contracts, or whose rate isn't
+/// quoted, leave it unbounded.
+#[must_use]
+pub fn worst_payoff(legs: &[Holding<'_>]) -> Option<Decimal> {
+    let mut calls = Decimal::ZERO;
+    let mut strikes = vec![Decimal::ZERO];
+    for leg in legs {
+        let scale = leg.scale.filter(|_| leg.instrument.is_option())?;
+        if leg.instrument.option_type == Some(OptionType::Call) {
+            calls += leg.quantity * scale;
+        }
+        strikes.extend(leg.instrument.strike.map(Money::get));
+    }
+    if calls < Decimal::ZERO {
+        return None;
+    }
+    strikes
+        .into_iter()
+        .map(|price| {
+            legs.iter()
+                .map(|leg| {
+                    leg.quantity
+                        * leg.scale.unwrap_or_default()
+                        * leg.instrument.intrinsic_value(price)
+                })
+                .sum::<Decimal>()
+        })
+        .min()
+}
+
+/// Gets an account's open positions, valued in its base currency
+///
+/// Prices are scaled by their symbol's multiplier, so are the value of a unit
+/// of quantity. Positions in other currencies than the account's base
+/// currency have their cost basis & price converted to it, at the market's
+/// rate. Those whose rate isn't quoted are left unpriced, at cost in their
+/// own currency.
+///
+/// # Errors
+///
+/// See [`sqlx`]
+pub async fn value_positions(
+    database: &Database,
+    market: &dyn MarketData,
+    account: &Account,
+) -> Result<Vec<ValuedPosition>, sqlx::Error> {
+    let mut rates = HashMap::new();
+    let mut valued = Vec::new();
+    for position in database.get_positions(account.id).await? {
+        if position.quantity.is_zero() {
+            continue;
+        }
+        let price = match market.quote(&position.symbol).await {
+            Ok(quote) => quote.map(|quote| quote.last),
+            Err(e) => {
+                warn!(symbol = position.symbol, "failed to quote: {e}");
+                None
+            }
+        };
+        let instrument = database
+            .get_instrument_or_unlisted(&position.symbol)
+            .await?;
+        let currency = &instrument.currency;
+        if !rates.contains_key(currency) {
+            let rate = fx_rate(market, currency, &account.base_currency).await;
+            rates.insert(currency.clone(), rate);
+        }
+        let rate = rates[currency];
+        let (cost_basis, price) = match rate {
+            Some(rate) => (
+                *position.cost_basis * rate,
+                price.map(|price| price * *instrument.multiplier * rate),
+            ),
+            None => (*position.cost_basis, None),
+        };
+        valued.push(ValuedPosition {
+            position,
+            cost_basis,
+            price,
+            rate,
+            instrument,
+        });
+    }
+    Ok(valued)
+}
+
+/// Values an account's cash in every currency, in its base currency, at the
+/// market's rates, to the base currency's minor unit
+///
+/// Currencies whose rate isn't quoted are left out.
+///
+/// # Errors
+///
+/// See [`sqlx`]
+pub async fn value_cash(
+    database: &Database,
+    market: &dyn MarketData,
+    account: &Account,
+) -> Result<Decimal, sqlx::Error> {
+    let mut cash = *account.cash;
+    for balance in database.get_cash_balances(account.id).await? {
+        if let Some(rate) = fx_rate(market, &balance.currency, &account.base_currency).await {
+            cash += *Money::round_minor(*balance.amount * rate, &account.base_currency);
+        }
+    }
+    Ok(cash)
+}
+
+/// The exchange rate between two currencies, logging any failure
+pub async fn fx_rate(market: &dyn MarketData, from: &str, to: &str) -> Option<Decimal> {
+    match market.fx_rate(from, to).await {
+        Ok(rate) => rate,
+        Err(e) => {
+            warn!(from, to, "failed to get exchange rate: {e}");
+            None
+        }
+    }
+}
+
+/// Watches margin accounts for margin calls
+#[derive(Debug)]
+pub struct MarginMonitor {
+    database: Database,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
+}
+
+impl MarginMonitor {
+    /// Creates a new monitor, valuing positions against the given market and
+    /// liquidating during the calendar's sessions
+    #[must_use]
+    pub fn new(database: Database, market: Arc<dyn MarketData>, calendar: Arc<Calendar>) -> Self {
+        Self {
+            database,
+            market,
+            calendar,
+        }
+    }
+
+    /// Runs a single pass over every margin account holding positions
+    ///
+    /// Accounts whose equity falls below their maintenance requirement are
+    /// flagged for a margin call, until it's met. Accounts that allow it are
+    /// liquidated while their positions' exchanges are open, unless their last
+    /// liquidation is still filling.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let now = OffsetDateTime::now_utc();
+        for account in self.database.get_margin_accounts().await? {
+            let market = self.market.as_ref();
+            let positions = value_positions(&self.database, market, &account).await?;
+            let cash = value_cash(&self.database, market, &account).await?;
+            let reserved = self.database.get_open_buy_notional(account.id).await?;
+            let report = MarginReport::new(&account, cash, &positions, reserved);
+            if !report.is_margin_call() {
+                if account.margin_call_at.is_some() {
+                    self.database.set_margin_call(account.id, None).await?;
+                    info!(account = %account.uuid, "margin call met");
+                }
+                continue;
+            }
+            if account.margin_call_at.is_none() {
+                self.database.set_margin_call(account.id, Some(now)).await?;
+                warn!(
+                    account = %account.uuid,
+                    %report.equity,
+                    %report.maintenance_requirement,
+                    "margin call"
+                );
+            }
+            if account.auto_liquidate && !self.database.is_liquidating(account.id).await? {
+                self.liquidate(&account, &report, positions, now).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Places market orders closing enough of an account's positions, those
+    /// requiring the most first, to meet its maintenance requirement
+    ///
+    /// Closing a unit of a position frees its share of the requirement, the
+    /// maintenance margin of its value, with its multiplier, or of a future's
+    /// contract, so enough units are closed to free the shortfall, rounded up
+    /// to the symbol's lots. An account with no equity left is closed out
+    /// entirely. Unquoted positions are left as is, as are those whose
+    /// exchange isn't open.
+    async fn liquidate(
+        &self,
+        account: &Account,
+        report: &MarginReport,
+        positions: Vec<ValuedPosition>,
+        now: OffsetDateTime,
+    ) -> Result<(), sqlx::Error> {
+        let mut left = if report.equity > Decimal::ZERO {
+            report.maintenance_requirement - report.equity
+        } else {
+            Decimal::MAX
+        };
+        let mut positions: Vec<_> = positions
+            .into_iter()
+            .filter_map(|valued| {
+                let price = valued.price?;
+                let freed = if valued.instrument.is_future() {
+                    *valued.instrument.maintenance_margin.unwrap_or_default()
+                        * valued.rate.unwrap_or(Decimal::ONE)
+                } else {
+                    price * account.maintenance_margin / Decimal::ONE_HUNDRED
+                };
+                // the quote, before it was scaled to the base currency
+                let quoted = price / (*valued.instrument.multiplier * valued.rate?);
+                Some((valued.position, quoted, freed, valued.instrument))
+            })
+            .filter(|(_, _, freed, _)| *freed > Decimal::ZERO)
+            .collect();
+        positions.sort_by_key(|(position, _, freed, _)| Reverse(*position.quantity.abs() * freed));
+        for (position, quoted, freed, instrument) in positions {
+            if left <= Decimal::ZERO {
+                break;
+            }
+            let Some(session) = self
+                .calendar
+                .trading_session(&instrument, now, false)
+                .filter(|session| session.open <= now && instrument.active)
+            else {
+                continue;
+            };
+            let held = *position.quantity.abs();
+            let needed = left.min(held * freed) / freed;
+            let quantity = liquidation_quantity(&instrument, needed, held);
+            if quantity.is_zero() {
+                warn!(
+                    account = %account.uuid,
+                    symbol = position.symbol,
+                    "position too small to liquidate"
+                );
+                continue;
+            }
+            left -= quantity * freed;
+            let order = NewOrder {
+                symbol: position.symbol,
+                side: if position.quantity < Quantity::ZERO {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                },
+                order_type: OrderType::Market,
+                quantity: Quantity::round(quantity),
+                limit_price: None,
+                stop_price: None,
+                trail_amount: None,
+                trail_percent: None,
+                time_in_force: TimeInForce::Day,
+                expires_at: Some(session.close),
+                extended_hours: false,
+                origin: OrderOrigin::Liquidation,
+                quoted_price: Some(Money::round(quoted)),
+            };
+            let order = self.database.add_order(account.id, &order, None).await?;
+            warn!(
+                account = %account.uuid,
+                order = %order.uuid,
+                symbol = order.symbol,
+                %order.quantity,
+                "position liquidated"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// How much of a position holding `held` a liquidation closes to free
+/// `needed` units' worth of its requirement
+///
+/// Rounded up to the symbol's lots, but never past what's held. A position
+/// held off its increments, say after a split, closes as much as it can.
+fn liquidation_quantity(instrument: &Instrument, needed: Decimal, held: Decimal) -> Decimal {
+    let quantity = instrument
+        .round_quantity(needed, RoundingStrategy::AwayFromZero)
+        .min(held);
+    if instrument.allows_quantity(quantity) {
+        quantity
+    } else {
+        instrument.round_quantity(quantity, RoundingStrategy::ToZero)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::{AssetClass, LotMethod, SlippageKind};
+
+    /// A margin account requiring 50% initial & 25% maintenance margin
+    fn account() -> Account {
+        Account {
+            id: 1,
+            uuid: Uuid::nil(),
+            user_id: 1,
+            name: "Default".to_owned(),
+            starting_cash: Money::from(100_000),
+            cash: Money::from(100_000),
+            account_type: AccountType::Margin,
+            initial_margin: Decimal::from(50),
+            maintenance_margin: Decimal::from(25),
+            auto_liquidate: true,
+            drip: false,
+            base_currency: "USD".to_owned(),
+            auto_convert: false,
+            fx_spread_bps: Decimal::ZERO,
+            margin_call_at: None,
+            lot_method: LotMethod::Fifo,
+            slippage_model: SlippageKind::Router,
+            slippage_bps: None,
+            fee_schedule_id: 1,
+            fee_schedule: "retail".to_owned(),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    /// A call on AAPL for 100 shares, struck at `strike`
+    fn call(strike: u32) -> Instrument {
+        Instrument {
+            asset_class: AssetClass::Option,
+            multiplier: Quantity::from(100),
+            underlying: Some("AAPL".to_owned()),
+            option_type: Some(OptionType::Call),
+            strike: Some(Money::from(strike)),
+            expires_on: Some(date!(2026 - 12 - 18)),
+            ..Instrument::unlisted(format!("AAPL261218C{strike:05}000"))
+        }
+    }
+
+    /// `quantity` of a symbol worth `value`, valued at the market in dollars
+    fn holding(instrument: &Instrument, quantity: i64, value: i64) -> Holding<'_> {
+        Holding {
+            instrument,
+            quantity: Decimal::from(quantity),
+            value: Decimal::from(value),
+            scale: Some(*instrument.multiplier),
+        }
+    }
+
+    #[test]
+    fn holdings_require_their_margin_long_or_short() {
+        let (long, short) = (
+            Instrument::unlisted("AAPL".to_owned()),
+            Instrument::unlisted("GME".to_owned()),
+        );
+        let holdings = [holding(&long, 10, 1500), holding(&short, -20, -400)];
+
+        let initial = requirement(&holdings, &account(), Requirement::Initial);
+        let maintenance = requirement(&holdings, &account(), Requirement::Maintenance);
+
+        assert_eq!(initial, Decimal::from(950));
+        assert_eq!(maintenance, Decimal::new(4750, 1));
+    }
+
+    #[test]
+    fn futures_require_their_own_margin_per_contract() {
+        let future = Instrument {
+            asset_class: AssetClass::Future,
+            multiplier: Quantity::from(50),
+            initial_margin: Some(Money::from(12_000)),
+            maintenance_margin: Some(Money::from(11_000)),
+            ..Instrument::unlisted("ESZ26".to_owned())
+        };
+        let holdings = [holding(&future, -2, 500)];
+
+        let initial = requirement(&holdings, &account(), Requirement::Initial);
+        let maintenance = requirement(&holdings, &account(), Requirement::Maintenance);
+
+        assert_eq!(initial, Decimal::from(24_000));
+        assert_eq!(maintenance, Decimal::from(22_000));
+    }
+
+    #[test]
+    fn spreads_require_their_maximum_loss() {
+        let (held, written) = (call(100), call(110));
+        let spread = [holding(&held, 1, 1000), holding(&written, -1, -400)];
+        let naked = [holding(&written, -1, -400)];
+
+        let required = requirement(&spread, &account(), Requirement::Initial);
+
+        // worth 600, & at least nothing at expiry
+        assert_eq!(required, Decimal::from(600));
+        assert_eq!(worst_payoff(&spread), Some(Decimal::ZERO));
+        assert_eq!(worst_payoff(&naked), None);
+        assert_eq!(
+            requirement(&naked, &account(), Requirement::Initial),
+            Decimal::from(200)
+        );
+    }
+
+    #[test]
+    fn liquidations_round_up_to_whole_lots() {
+        let board = Instrument {
+            lot_size: Quantity::from(100),
+            fractional: false,
+            ..Instrument::unlisted("7203.T".to_owned())
+        };
+        let shares = Instrument::unlisted("AAPL".to_owned());
+
+        assert_eq!(
+            liquidation_quantity(&board, Decimal::from(130), Decimal::from(500)),
+            Decimal::from(200)
+        );
+        assert_eq!(
+            liquidation_quantity(&shares, Decimal::new(12_345_678, 7), Decimal::TEN),
+            Decimal::new(1_234_568, 6)
+        );
+    }
+
+    #[test]
+    fn liquidations_never_close_more_than_is_held() {
+        let board = Instrument {
+            lot_size: Quantity::from(100),
+            fractional: false,
+            ..Instrument::unlisted("7203.T".to_owned())
+        };
+
+        // held off its lots after a split, so closes the whole lots it holds
+        let split = liquidation_quantity(&board, Decimal::from(430), Decimal::from(450));
+        let small = liquidation_quantity(&board, Decimal::from(30), Decimal::from(50));
+
+        assert_eq!(split, Decimal::from(400));
+        assert_eq!(small, Decimal::ZERO);
+    }
+}
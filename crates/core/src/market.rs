@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::{models::CorporateAction, numeric::Money};
+
+/// Importing bars from csv files
+pub mod import;
+/// Option contracts priced off their underlying
+pub mod options;
+/// Replays of stored bars
+pub mod replay;
+/// A deterministic synthetic feed
+pub mod synthetic;
+
+pub use options::OptionPricer;
+pub use replay::Replay;
+pub use synthetic::Synthetic;
+
+/// A source of prices
+#[async_trait]
+pub trait MarketData: Send + Sync + std::fmt::Debug {
+    /// The latest quote for a symbol
+    ///
+    /// Returns `None` if the provider doesn't price the symbol.
+    async fn quote(&self, symbol: &str) -> Result<Option<Quote>, Error>;
+
+    /// Bars for a symbol, each `interval` long, starting from `start` up to
+    /// (not including) `end`
+    async fn bars(
+        &self,
+        symbol: &str,
+        interval: Duration,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Bar>, Error>;
+
+    /// The units of `to` a unit of `from` exchanges for at the market,
+    /// before any spread
+    ///
+    /// Returns `None` if the provider doesn't price the pair. Unless a
+    /// provider knows better, rates are the last price of the pair's symbol,
+    /// like `EURUSD`, or the inverse of its reverse's, to [`FX_DP`] decimal
+    /// places.
+    async fn fx_rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, Error> {
+        if from == to {
+            return Ok(Some(Decimal::ONE));
+        }
+        if let Some(quote) = self.quote(&format!("{from}{to}")).await? {
+            return Ok(Some(quote.last));
+        }
+        Ok(self
+            .quote(&format!("{to}{from}"))
+            .await?
+            .and_then(|quote| Decimal::ONE.checked_div(quote.last))
+            .map(|rate| rate.round_dp(FX_DP)))
+    }
+}
+
+/// The decimal places exchange rates are given to
+pub const FX_DP: u32 = 6;
+
+/// A symbol's price at a point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct Quote {
+    /// The quoted symbol
+    pub symbol: String,
+    /// The best price a seller gets
+    pub bid: Decimal,
+    /// The best price a buyer gets
+    pub ask: Decimal,
+    /// The last traded price
+    pub last: Decimal,
+    /// When the quote was taken
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+}
+
+/// A summary of a symbol's trading over an interval
+#[derive(Debug, Clone, Serialize)]
+pub struct Bar {
+    /// The start of the interval
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The first price
+    pub open: Decimal,
+    /// The highest price
+    pub high: Decimal,
+    /// The lowest price
+    pub low: Decimal,
+    /// The last price
+    pub close: Decimal,
+    /// The quantity traded
+    pub volume: Decimal,
+}
+
+/// Adjusts bars for the splits that took effect after them, by `as_of`
+///
+/// Prices are divided by the ratio of every such split, to as many places as
+/// a [`Money`] keeps, and volumes multiplied by it, so history lines up with
+/// prices since.
+pub fn split_adjust(bars: &mut [Bar], splits: &[CorporateAction], as_of: OffsetDateTime) {
+    for bar in bars {
+        let ratio: Decimal = splits
+            .iter()
+            .filter(|split| bar.time < split.effective_at() && split.effective_at() <= as_of)
+            .filter_map(|split| split.ratio.map(Decimal::from))
+            .product();
+        if ratio == Decimal::ONE {
+            continue;
+        }
+        for price in [&mut bar.open, &mut bar.high, &mut bar.low, &mut bar.close] {
+            *price = *Money::round(*price / ratio);
+        }
+        bar.volume *= ratio;
+    }
+}
+
+/// A symbol's last price as of `at`, the close of its last bar in the day
+/// before
+///
+/// Returns `None` if the provider has no bars for the symbol then.
+///
+/// # Errors
+///
+/// See [`Error`]
+pub async fn close_as_of(
+    market: &dyn MarketData,
+    symbol: &str,
+    at: OffsetDateTime,
+) -> Result<Option<Decimal>, Error> {
+    let bars = market
+        .bars(
+            symbol,
+            Duration::from_secs(60 * 60),
+            at - time::Duration::DAY,
+            at,
+        )
+        .await?;
+    Ok(bars.last().map(|bar| bar.close))
+}
+
+/// An error while fetching market data
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The provider couldn't be reached, or failed to answer
+    #[error("Market data unavailable: {0}")]
+    Unavailable(String),
+    /// An error reading stored market data
+    #[error("Database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
@@ -0,0 +1,133 @@
+use std::{io::Read, str::FromStr};
+
+use rust_decimal::Decimal;
+use time::{
+    macros::{format_description, time},
+    Date, PrimitiveDateTime, Time,
+};
+
+use super::Bar;
+use crate::state::persist::Database;
+
+/// Import a symbol's bars from an OHLCV csv file into the database
+///
+/// See [`read_bars`] for the accepted formats. Returns the number of bars
+/// written.
+///
+/// # Errors
+///
+/// See [`ImportError`]
+pub async fn import_bars(
+    database: &Database,
+    symbol: &str,
+    reader: impl Read,
+) -> Result<u64, ImportError> {
+    let bars = read_bars(reader)?;
+    Ok(database.add_bars(symbol, &bars).await?)
+}
+
+/// Read bars from an OHLCV csv file
+///
+/// Accepts Yahoo (`Date,Open,High,Low,Close,Adj Close,Volume`) & Stooq
+/// (`Date,Open,High,Low,Close,Volume`, or the `<DATE>,<TIME>,<OPEN>,...` bulk
+/// format) exports. Columns are matched by name, in any order & case. Dates
+/// may be `YYYY-MM-DD` or `YYYYMMDD`, and an optional time column
+/// `HH:MM:SS` or `HHMMSS`; both are read as UTC. Rows with `null` prices, as
+/// Yahoo writes for non-trading days, are skipped.
+///
+/// `Adj Close` is ignored, splits are applied separately.
+///
+/// # Errors
+///
+/// See [`ImportError`]
+pub fn read_bars(reader: impl Read) -> Result<Vec<Bar>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers: Vec<_> = reader
+        .headers()?
+        .iter()
+        .map(|header| {
+            header
+                .trim_matches(|c| c == '<' || c == '>')
+                .to_ascii_lowercase()
+        })
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let required =
+        |name: &'static str, names: &[&str]| column(names).ok_or(ImportError::MissingColumn(name));
+    let date = required("date", &["date"])?;
+    let time = column(&["time"]);
+    let open = required("open", &["open"])?;
+    let high = required("high", &["high"])?;
+    let low = required("low", &["low"])?;
+    let close = required("close", &["close"])?;
+    let volume = required("volume", &["volume", "vol"])?;
+
+    let mut bars = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |i: usize| record.get(i).unwrap_or_default();
+        if [open, high, low, close].iter().any(|&i| field(i) == "null") {
+            continue;
+        }
+        // the header is line 1
+        let line = row + 2;
+        let invalid = |column: &'static str| ImportError::InvalidRow { line, column };
+        let price = |i: usize, column| parse_decimal(field(i)).ok_or_else(|| invalid(column));
+        let day = parse_date(field(date)).ok_or_else(|| invalid("date"))?;
+        let at = time.map_or(Ok(time!(0:00)), |i| {
+            parse_time(field(i)).ok_or_else(|| invalid("time"))
+        })?;
+        bars.push(Bar {
+            time: PrimitiveDateTime::new(day, at).assume_utc(),
+            open: price(open, "open")?,
+            high: price(high, "high")?,
+            low: price(low, "low")?,
+            close: price(close, "close")?,
+            volume: price(volume, "volume")?,
+        });
+    }
+    bars.sort_by_key(|bar| bar.time);
+    Ok(bars)
+}
+
+pub(crate) fn parse_decimal(s: &str) -> Option<Decimal> {
+    Decimal::from_str(s)
+        .or_else(|_| Decimal::from_scientific(s))
+        .ok()
+}
+
+pub(crate) fn parse_date(s: &str) -> Option<Date> {
+    Date::parse(s, format_description!("[year]-[month]-[day]"))
+        .or_else(|_| Date::parse(s, format_description!("[year][month][day]")))
+        .ok()
+}
+
+fn parse_time(s: &str) -> Option<Time> {
+    Time::parse(s, format_description!("[hour]:[minute]:[second]"))
+        .or_else(|_| Time::parse(s, format_description!("[hour][minute][second]")))
+        .ok()
+}
+
+/// An error while importing bars
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    /// The file couldn't be read as csv
+    #[error("Csv error: {0}")]
+    Csv(#[from] csv::Error),
+    /// A required column is missing from the header
+    #[error("Missing column: {0}")]
+    MissingColumn(&'static str),
+    /// A row's value couldn't be parsed
+    #[error("Invalid {column} on line {line}")]
+    InvalidRow {
+        /// The 1-based line of the row
+        line: usize,
+        /// The column that failed to parse
+        column: &'static str,
+    },
+    /// The bars couldn't be stored
+    #[error("Database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
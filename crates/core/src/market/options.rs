@@ -0,0 +1,103 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use time::OffsetDateTime;
+
+use super::{synthetic::to_price, Bar, Error, MarketData, Quote};
+use crate::options::{black_scholes, ContractTerms};
+
+/// The seconds in an average year, used to scale time to expiry
+const YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Prices option contracts with Black-Scholes, from their underlying's quotes
+/// in another market
+///
+/// Contracts are quoted by their OCC symbols, see [`ContractTerms`], and
+/// every other symbol, & exchange rate, as the other market quotes it.
+/// Contracts have no bars of their own.
+#[derive(Debug)]
+pub struct OptionPricer {
+    market: Arc<dyn MarketData>,
+    volatility: f64,
+    rate: f64,
+    spread: f64,
+}
+
+impl OptionPricer {
+    /// Creates a new pricer over the given market, at the given annual
+    /// volatility, e.g. `0.3` for 30%
+    ///
+    /// Contracts are priced with a 4% risk-free rate, and a 200 basis point
+    /// spread.
+    #[must_use]
+    pub fn new(market: Arc<dyn MarketData>, volatility: f64) -> Self {
+        Self {
+            market,
+            volatility,
+            rate: 0.04,
+            spread: 200.0,
+        }
+    }
+
+    /// Sets the annual risk-free rate, e.g. `0.04` for 4%
+    #[must_use]
+    pub const fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets the bid-ask spread, in basis points of the price
+    #[must_use]
+    pub const fn with_spread(mut self, spread: f64) -> Self {
+        self.spread = spread;
+        self
+    }
+}
+
+#[async_trait]
+impl MarketData for OptionPricer {
+    async fn quote(&self, symbol: &str) -> Result<Option<Quote>, Error> {
+        let Some(terms) = ContractTerms::parse(symbol) else {
+            return self.market.quote(symbol).await;
+        };
+        let Some(underlying) = self.market.quote(&terms.underlying).await? else {
+            return Ok(None);
+        };
+        let years = (terms.expires_at() - underlying.time).as_seconds_f64() / YEAR;
+        let price = black_scholes(
+            terms.option_type,
+            underlying.last.to_f64().unwrap_or_default(),
+            terms.strike.to_f64().unwrap_or_default(),
+            years,
+            self.volatility,
+            self.rate,
+        );
+        let half = (price * self.spread / 20_000.0).max(0.005);
+        let bid = to_price(price - half);
+        Ok(Some(Quote {
+            symbol: symbol.to_owned(),
+            bid,
+            ask: to_price(price + half).max(bid + Decimal::new(1, 2)),
+            last: to_price(price),
+            time: underlying.time,
+        }))
+    }
+
+    async fn bars(
+        &self,
+        symbol: &str,
+        interval: Duration,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Bar>, Error> {
+        if ContractTerms::parse(symbol).is_some() {
+            return Ok(Vec::new());
+        }
+        self.market.bars(symbol, interval, start, end).await
+    }
+
+    async fn fx_rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, Error> {
+        self.market.fx_rate(from, to).await
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use rust_decimal::{Decimal, RoundingStrategy};
+use time::OffsetDateTime;
+
+use super::{split_adjust, Bar, Error, MarketData, Quote};
+use crate::{
+    models::{CorporateAction, Instrument},
+    state::persist::Database,
+};
+
+/// Replays stored bars as a live feed
+///
+/// The replay clock starts at `from` when the feed is created, and runs
+/// `speed` times faster than the wall clock. Quotes are taken from the close
+/// of the latest bar at or before the replay clock, and bars after it are
+/// never returned. Bars are adjusted for the splits the replay clock has
+/// passed since. Each symbol's instrument & splits are read once, as it's
+/// first replayed, so those changed since are only seen by a new replay.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    database: Database,
+    from: OffsetDateTime,
+    began: OffsetDateTime,
+    speed: f64,
+    spread: Decimal,
+    symbols: Arc<Mutex<HashMap<String, Arc<Symbol>>>>,
+}
+
+/// What's read once about a replayed symbol
+#[derive(Debug)]
+struct Symbol {
+    /// The symbol's instrument, for its tick
+    instrument: Instrument,
+    /// The symbol's splits
+    splits: Vec<CorporateAction>,
+}
+
+impl Replay {
+    /// Creates a new replay of the database's bars, starting at `from`
+    ///
+    /// The replay runs a day of history per minute, with a 5 basis point
+    /// spread.
+    #[must_use]
+    pub fn new(database: Database, from: OffsetDateTime) -> Self {
+        Self {
+            database,
+            from,
+            began: OffsetDateTime::now_utc(),
+            speed: 24.0 * 60.0,
+            spread: Decimal::from(5),
+            symbols: Arc::default(),
+        }
+    }
+
+    /// Sets how many times faster than the wall clock the replay runs
+    #[must_use]
+    pub const fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Sets the bid-ask spread, in basis points of the price
+    #[must_use]
+    pub const fn with_spread(mut self, spread: Decimal) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// The current time of the replay clock
+    #[must_use]
+    pub fn now(&self) -> OffsetDateTime {
+        self.from + (OffsetDateTime::now_utc() - self.began) * self.speed
+    }
+
+    /// A symbol's instrument & splits, read from the database the first time
+    async fn symbol(&self, symbol: &str) -> Result<Arc<Symbol>, Error> {
+        if let Some(cached) = self.lock().get(symbol) {
+            return Ok(Arc::clone(cached));
+        }
+        let read = Arc::new(Symbol {
+            instrument: self.database.get_instrument_or_unlisted(symbol).await?,
+            splits: self.database.get_splits(symbol).await?,
+        });
+        Ok(Arc::clone(
+            self.lock().entry(symbol.to_owned()).or_insert(read),
+        ))
+    }
+
+    /// The cache of symbols read, even if a thread panicked holding it
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<Symbol>>> {
+        self.symbols.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl MarketData for Replay {
+    async fn quote(&self, symbol: &str) -> Result<Option<Quote>, Error> {
+        let time = self.now();
+        let Some(mut bar) = self.database.get_last_bar(symbol, time).await? else {
+            return Ok(None);
+        };
+        let read = self.symbol(symbol).await?;
+        split_adjust(std::slice::from_mut(&mut bar), &read.splits, time);
+        // the spread is rounded out to the tick on both sides, so they're
+        // always at least a tick apart
+        let half = bar.close * self.spread / Decimal::from(20_000);
+        let instrument = &read.instrument;
+        Ok(Some(Quote {
+            symbol: symbol.to_owned(),
+            bid: instrument.round_price(bar.close - half, RoundingStrategy::ToNegativeInfinity),
+            ask: instrument.round_price(bar.close + half, RoundingStrategy::ToPositiveInfinity),
+            last: bar.close,
+            time,
+        }))
+    }
+
+    async fn bars(
+        &self,
+        symbol: &str,
+        interval: Duration,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Bar>, Error> {
+        let interval = i64::try_from(interval.as_secs()).unwrap_or(i64::MAX);
+        if interval == 0 {
+            return Ok(Vec::new());
+        }
+        let now = self.now();
+        let mut stored = self.database.get_bars(symbol, start, end.min(now)).await?;
+        let read = self.symbol(symbol).await?;
+        split_adjust(&mut stored, &read.splits, now);
+        Ok(aggregate(stored, start, interval))
+    }
+}
+
+/// Merges bars into buckets `interval` seconds long, aligned to `start`
+fn aggregate(bars: Vec<Bar>, start: OffsetDateTime, interval: i64) -> Vec<Bar> {
+    let mut merged: Vec<Bar> = Vec::new();
+    for bar in bars {
+        let bucket = (bar.time - start).whole_seconds().div_euclid(interval) * interval;
+        let time = start + time::Duration::seconds(bucket);
+        match merged.last_mut() {
+            Some(last) if last.time == time => {
+                last.high = last.high.max(bar.high);
+                last.low = last.low.min(bar.low);
+                last.close = bar.close;
+                last.volume += bar.volume;
+            }
+            _ => merged.push(Bar { time, ..bar }),
+        }
+    }
+    merged
+}
@@ -0,0 +1,345 @@
+use std::{f64::consts::TAU, time::Duration};
+
+use async_trait::async_trait;
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use time::{macros::datetime, OffsetDateTime};
+
+use super::{Bar, Error, MarketData, Quote, FX_DP};
+use crate::models::Instrument;
+
+/// The seconds in an average year, used to scale annual rates
+const YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+/// The seconds the Brownian bridge covers, from the unix epoch
+const SPAN: u64 = 1 << 40;
+/// The points sampled within a bar to find its high & low
+const BAR_SAMPLES: u32 = 16;
+/// The currencies exchange rates are quoted for, with their value in dollars
+/// at the origin
+const CURRENCIES: [(&str, f64); 6] = [
+    ("USD", 1.0),
+    ("EUR", 1.08),
+    ("GBP", 1.27),
+    ("CHF", 1.12),
+    ("CAD", 0.73),
+    ("JPY", 0.0067),
+];
+/// The annual volatility of each currency against the dollar
+const FX_VOLATILITY: f64 = 0.08;
+
+/// Prices every symbol with geometric Brownian motion
+///
+/// Each symbol follows its own path, derived from the seed & its name. Paths
+/// are built as Brownian bridges over whole seconds, so a price is a pure
+/// function of the seed, the symbol & the time: quotes & bars always agree,
+/// whatever order they're asked for in, and no state is kept between calls.
+/// Crypto pairs, like `BTC-USD`, move with a volatility of their own, at any
+/// hour.
+#[derive(Debug, Clone, Copy)]
+pub struct Synthetic {
+    seed: u64,
+    origin: OffsetDateTime,
+    drift: f64,
+    volatility: f64,
+    crypto_volatility: f64,
+    spread: f64,
+}
+
+impl Default for Synthetic {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Synthetic {
+    /// Creates a new feed from the given seed
+    ///
+    /// Symbols start at their base price on 2024-01-01, with no drift, 30%
+    /// annual volatility, or 80% for crypto pairs, & a 5 basis point spread.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            origin: datetime!(2024-01-01 0:00 UTC),
+            drift: 0.0,
+            volatility: 0.3,
+            crypto_volatility: 0.8,
+            spread: 5.0,
+        }
+    }
+
+    /// Sets when symbols are at their base price
+    #[must_use]
+    pub const fn with_origin(mut self, origin: OffsetDateTime) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Sets the annual drift, e.g. `0.05` for 5%
+    #[must_use]
+    pub const fn with_drift(mut self, drift: f64) -> Self {
+        self.drift = drift;
+        self
+    }
+
+    /// Sets the annual volatility, e.g. `0.3` for 30%
+    #[must_use]
+    pub const fn with_volatility(mut self, volatility: f64) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    /// Sets the annual volatility of crypto pairs, e.g. `0.8` for 80%
+    #[must_use]
+    pub const fn with_crypto_volatility(mut self, volatility: f64) -> Self {
+        self.crypto_volatility = volatility;
+        self
+    }
+
+    /// Sets the bid-ask spread, in basis points of the price
+    #[must_use]
+    pub const fn with_spread(mut self, spread: f64) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// A symbol's price at the given time
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn price(&self, symbol: &str, at: OffsetDateTime) -> f64 {
+        let hash = fnv1a(symbol.as_bytes());
+        let base = 10.0 + (hash % 49_000) as f64 / 100.0;
+        let volatility = if Instrument::pair(symbol).is_some() {
+            self.crypto_volatility
+        } else {
+            self.volatility
+        };
+        base * self.growth(hash, at, self.drift, volatility)
+    }
+
+    /// A currency's value in dollars at the given time, if it's quoted
+    ///
+    /// Currencies follow their own paths, with no drift & 8% annual
+    /// volatility.
+    #[must_use]
+    pub fn dollar_value(&self, currency: &str, at: OffsetDateTime) -> Option<f64> {
+        let (_, base) = CURRENCIES.iter().find(|(code, _)| *code == currency)?;
+        if currency == "USD" {
+            return Some(1.0);
+        }
+        let hash = fnv1a(format!("{currency}/USD").as_bytes());
+        Some(base * self.growth(hash, at, 0.0, FX_VOLATILITY))
+    }
+
+    /// How far the path hashed to has grown from the origin by `at`, with
+    /// the given annual drift & volatility
+    #[allow(clippy::cast_precision_loss)]
+    fn growth(&self, hash: u64, at: OffsetDateTime, drift: f64, volatility: f64) -> f64 {
+        let key = splitmix64(self.seed ^ hash);
+        let (now, origin) = (seconds(at), seconds(self.origin));
+        let years = (now as f64 - origin as f64) / YEAR;
+        let shock = brownian(key, now) - brownian(key, origin);
+        let drift = volatility.mul_add(-volatility / 2.0, drift);
+        drift.mul_add(years, volatility * shock / YEAR.sqrt()).exp()
+    }
+
+    /// A symbol's bar, starting at `start` & `interval` long
+    #[allow(clippy::cast_precision_loss)]
+    fn bar(&self, symbol: &str, start: OffsetDateTime, interval: Duration) -> Bar {
+        let (mut high, mut low) = (f64::MIN, f64::MAX);
+        for i in 0..=BAR_SAMPLES {
+            let price = self.price(symbol, start + interval * i / BAR_SAMPLES);
+            high = high.max(price);
+            low = low.min(price);
+        }
+        let key = splitmix64(self.seed ^ fnv1a(symbol.as_bytes()));
+        let per_second = 10.0 + (key % 990) as f64;
+        let volume = per_second * interval.as_secs_f64() * (0.5 + uniform(key, seconds(start)));
+        Bar {
+            time: start,
+            open: to_price(self.price(symbol, start)),
+            high: to_price(high),
+            low: to_price(low),
+            close: to_price(self.price(symbol, start + interval)),
+            volume: Decimal::from_f64(volume.round()).unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl MarketData for Synthetic {
+    async fn quote(&self, symbol: &str) -> Result<Option<Quote>, Error> {
+        let time = OffsetDateTime::now_utc();
+        let price = self.price(symbol, time);
+        let half = (price * self.spread / 20_000.0).max(0.005);
+        let (bid, ask) = (to_price(price - half), to_price(price + half));
+        Ok(Some(Quote {
+            symbol: symbol.to_owned(),
+            bid,
+            ask: ask.max(bid + Decimal::new(1, 2)),
+            last: to_price(price),
+            time,
+        }))
+    }
+
+    async fn bars(
+        &self,
+        symbol: &str,
+        interval: Duration,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Bar>, Error> {
+        if interval.is_zero() {
+            return Ok(Vec::new());
+        }
+        let mut bars = Vec::new();
+        let mut time = start;
+        while time < end {
+            bars.push(self.bar(symbol, time, interval));
+            time += interval;
+        }
+        Ok(bars)
+    }
+
+    async fn fx_rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, Error> {
+        let now = OffsetDateTime::now_utc();
+        let (Some(from), Some(to)) = (self.dollar_value(from, now), self.dollar_value(to, now))
+        else {
+            return Ok(None);
+        };
+        Ok(Decimal::from_f64(from / to).map(|rate| rate.round_dp(FX_DP)))
+    }
+}
+
+/// Whole seconds since the unix epoch, clamped to the bridge's span
+fn seconds(at: OffsetDateTime) -> u64 {
+    u64::try_from(at.unix_timestamp()).map_or(0, |s| s.min(SPAN))
+}
+
+/// The value of a standard Brownian path at `step`
+///
+/// The path is pinned to zero at the epoch, and its value at [`SPAN`] drawn
+/// first. Each midpoint is then drawn from the bridge between its neighbours,
+/// halving the interval around `step` until it's hit, so only ~40 draws are
+/// needed for any step.
+#[allow(clippy::cast_precision_loss)]
+fn brownian(key: u64, step: u64) -> f64 {
+    let (mut lo, mut hi) = (0, SPAN);
+    let (mut w_lo, mut w_hi) = (0.0, normal(key, 0) * (SPAN as f64).sqrt());
+    let mut node = 1;
+    while lo < step && step < hi {
+        let mid = lo + (hi - lo) / 2;
+        let spread = ((hi - lo) as f64 / 4.0).sqrt();
+        let w_mid = spread.mul_add(normal(key, node), f64::midpoint(w_lo, w_hi));
+        if step < mid {
+            (hi, w_hi, node) = (mid, w_mid, node * 2);
+        } else {
+            (lo, w_lo, node) = (mid, w_mid, node * 2 + 1);
+        }
+    }
+    if step == lo {
+        w_lo
+    } else {
+        w_hi
+    }
+}
+
+/// A standard normal draw for the given node of a path
+fn normal(key: u64, node: u64) -> f64 {
+    let u1 = 1.0 - uniform(key, node);
+    let u2 = uniform(key ^ 0x5851_f42d_4c95_7f2d, node);
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// A uniform draw in `[0, 1)` for the given node of a path
+#[allow(clippy::cast_precision_loss)]
+fn uniform(key: u64, node: u64) -> f64 {
+    (splitmix64(key ^ splitmix64(node)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Rounds a simulated price to cents, never below a cent
+pub(super) fn to_price(price: f64) -> Decimal {
+    Decimal::from_f64(price)
+        .unwrap_or_default()
+        .round_dp(2)
+        .max(Decimal::new(1, 2))
+}
+
+const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A small, stable hash, so symbols price the same across runs
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    const START: OffsetDateTime = datetime!(2026-03-02 14:30 UTC);
+
+    #[tokio::test]
+    async fn same_seed_prices_the_same() {
+        let (feed, again) = (Synthetic::new(7), Synthetic::new(7));
+        let hour = Duration::from_secs(60 * 60);
+        let end = START + Duration::from_secs(24 * 60 * 60);
+
+        let quote = feed.quote("AAPL").await.unwrap().unwrap();
+        let bars = feed.bars("AAPL", hour, START, end).await.unwrap();
+
+        assert_eq!(quote.last, to_price(again.price("AAPL", quote.time)));
+        let prices = |bars: Vec<Bar>| {
+            bars.into_iter()
+                .map(|bar| (bar.time, bar.open, bar.high, bar.low, bar.close, bar.volume))
+                .collect::<Vec<_>>()
+        };
+        let same = again.bars("AAPL", hour, START, end).await.unwrap();
+        assert_eq!(prices(bars), prices(same));
+        let other = Synthetic::new(8).price("AAPL", quote.time);
+        assert_ne!(quote.last, to_price(other));
+    }
+
+    #[tokio::test]
+    async fn bars_agree_with_quotes() {
+        let feed = Synthetic::new(7);
+        let minute = Duration::from_secs(60);
+        let end = START + minute * 30;
+
+        let bars = feed.bars("MSFT", minute, START, end).await.unwrap();
+
+        assert_eq!(bars.len(), 30);
+        for (bar, next) in bars.iter().zip(&bars[1..]) {
+            assert_eq!(bar.close, next.open);
+        }
+        for bar in &bars {
+            assert_eq!(bar.open, to_price(feed.price("MSFT", bar.time)));
+            assert_eq!(bar.close, to_price(feed.price("MSFT", bar.time + minute)));
+            assert!(bar.low <= bar.open.min(bar.close));
+            assert!(bar.high >= bar.open.max(bar.close));
+        }
+    }
+
+    #[tokio::test]
+    async fn prices_are_positive() {
+        let feed = Synthetic::new(7).with_volatility(2.0).with_drift(-1.0);
+        let day = Duration::from_secs(24 * 60 * 60);
+        let end = START + day * 365;
+        for symbol in ["AAPL", "PENNY", "BTC-USD", "ZZZ26"] {
+            let quote = feed.quote(symbol).await.unwrap().unwrap();
+            assert!(quote.bid > Decimal::ZERO);
+            assert!(quote.ask > quote.bid);
+            for bar in feed.bars(symbol, day, START, end).await.unwrap() {
+                assert!(bar.low > Decimal::ZERO);
+            }
+        }
+        assert!(feed.dollar_value("JPY", end).unwrap() > 0.0);
+    }
+}
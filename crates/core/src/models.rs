@@ -1,8 +1,17 @@
 use axum_login::AuthUser;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use time::{Date, OffsetDateTime};
 // use tokio_postgres::Row;
 use uuid::Uuid;
 
+use crate::{
+    market::FX_DP,
+    numeric::{Money, Quantity},
+    state::DEFAULT_CURRENCY,
+};
+
 /// A user's data
 #[derive(Clone, FromRow)]
 pub struct User {
@@ -40,3 +49,1464 @@ impl AuthUser for User {
         self.password.as_bytes()
     }
 }
+
+/// A paper trading account, owned by a [`User`]
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Account {
+    /// An account's id
+    #[serde(skip)]
+    pub id: i32,
+    /// An account's uuid
+    pub uuid: Uuid,
+    /// The owning user's id
+    #[serde(skip)]
+    pub user_id: i32,
+    /// A display name, unique per user
+    pub name: String,
+    /// The cash the account opened with, restored on reset
+    pub starting_cash: Money,
+    /// The current cash balance, negative when borrowing on margin
+    ///
+    /// Kept from the journal, only moving as entries are posted to it, and
+    /// checked against it by
+    /// [`Database::reconcile`](crate::state::persist::Database::reconcile).
+    pub cash: Money,
+    /// Whether the account trades with cash only, or on margin
+    pub account_type: AccountType,
+    /// The equity needed to open positions, as a percentage of their value
+    pub initial_margin: Decimal,
+    /// The equity needed to keep positions open, as a percentage of their
+    /// value
+    pub maintenance_margin: Decimal,
+    /// Whether positions are liquidated to meet a margin call
+    pub auto_liquidate: bool,
+    /// Whether dividends are reinvested in the symbol paying them, unless a
+    /// position says otherwise
+    pub drip: bool,
+    /// The currency the account's cash is held & reported in
+    pub base_currency: String,
+    /// Whether trades in other currencies convert what they need from, and
+    /// what they raise to, the base currency as they fill
+    pub auto_convert: bool,
+    /// How far conversions between currencies are priced from the market
+    /// rate, in basis points
+    pub fx_spread_bps: Decimal,
+    /// When the account's equity fell below its maintenance margin, if it
+    /// still is
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub margin_call_at: Option<OffsetDateTime>,
+    /// Which lots sales relieve
+    pub lot_method: LotMethod,
+    /// How the account's fills slip from the market price
+    pub slippage_model: SlippageKind,
+    /// The slippage model's basis points, if it takes any
+    pub slippage_bps: Option<Decimal>,
+    /// The id of the fees the account pays
+    #[serde(skip)]
+    pub fee_schedule_id: i32,
+    /// The name of the fees the account pays
+    pub fee_schedule: String,
+    /// When the account was opened
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl Account {
+    /// The rate the account converts between currencies at, given the
+    /// market's, less its spread
+    #[must_use]
+    pub fn fx_rate(&self, market: Decimal) -> Decimal {
+        (market * (Decimal::ONE - self.fx_spread_bps / Decimal::from(10_000))).round_dp(FX_DP)
+    }
+}
+
+/// Changes to an [`Account`]'s settings, leaving those that are `None` as is
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccountSettings {
+    /// Which lots sales relieve
+    pub lot_method: Option<LotMethod>,
+    /// Whether the account trades with cash only, or on margin
+    pub account_type: Option<AccountType>,
+    /// The equity needed to open positions, in percent
+    pub initial_margin: Option<Decimal>,
+    /// The equity needed to keep positions open, in percent
+    pub maintenance_margin: Option<Decimal>,
+    /// Whether positions are liquidated to meet a margin call
+    pub auto_liquidate: Option<bool>,
+    /// Whether dividends are reinvested in the symbol paying them
+    pub drip: Option<bool>,
+    /// The currency the account's cash is held & reported in
+    pub base_currency: Option<String>,
+    /// Whether trades in other currencies convert to & from the base currency
+    /// as they fill
+    pub auto_convert: Option<bool>,
+    /// How far conversions are priced from the market rate, in basis points
+    pub fx_spread_bps: Option<Decimal>,
+    /// The name of the fees the account pays
+    pub fee_schedule: Option<String>,
+    /// How the account's fills slip from the market price
+    pub slippage_model: Option<SlippageKind>,
+    /// The slippage model's basis points, set along with the model, leaving
+    /// the model's default if `None`
+    pub slippage_bps: Option<Decimal>,
+}
+
+/// Whether an [`Account`] may borrow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "account_type", rename_all = "snake_case")]
+pub enum AccountType {
+    /// Buys are paid in full, and nothing is sold short
+    #[default]
+    Cash,
+    /// Buys may borrow cash & sells may borrow shares, against the account's
+    /// equity
+    Margin,
+}
+
+/// Which [`Lot`]s a sale relieves first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "lot_method", rename_all = "snake_case")]
+pub enum LotMethod {
+    /// First in, first out
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// The most expensive lots first
+    HighestCost,
+}
+
+/// Which slippage model an [`Account`]'s fills use
+///
+/// See [`crate::slippage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "slippage_model", rename_all = "snake_case")]
+pub enum SlippageKind {
+    /// Whichever model the router was configured with
+    Router,
+    /// Fill at the last price
+    Last,
+    /// Fill a fixed number of basis points worse than the last price
+    Fixed,
+    /// Buy at the ask & sell at the bid
+    Spread,
+    /// Fill worse the larger the order is against the market's volume
+    Volume,
+}
+
+/// A single movement of an account's cash
+#[derive(Debug, Clone, FromRow)]
+pub struct CashEntry {
+    /// An entry's id
+    pub id: i64,
+    /// The account the entry belongs to
+    pub account_id: i32,
+    /// What caused the movement
+    pub kind: CashEntryKind,
+    /// The currency of the cash moved
+    pub currency: String,
+    /// The signed change in cash
+    pub amount: Money,
+    /// The account's cash in the currency after this entry
+    pub balance: Money,
+    /// When the entry was posted
+    pub created_at: OffsetDateTime,
+}
+
+/// The cause of a [`CashEntry`], or of a journal entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "cash_entry_kind", rename_all = "snake_case")]
+pub enum CashEntryKind {
+    /// Cash added to the account
+    Deposit,
+    /// The account was reset to its starting cash
+    Reset,
+    /// Cash paid or received for a [`Fill`]
+    Trade,
+    /// Commission & regulatory fees paid for a [`Fill`]
+    Fee,
+    /// A day's interest on the shares borrowed for a short [`Position`]
+    BorrowFee,
+    /// The balances an account held when its journal was opened
+    Opening,
+    /// A [`CorporateAction`] split the shares of a [`Position`]
+    Split,
+    /// A [`Dividend`] paid on a [`Position`], or paid in lieu when short
+    Dividend,
+    /// Cash exchanged for another currency's, by a [`Conversion`]
+    Conversion,
+    /// A day's profit or loss on a [`Position`] in a future, as it's marked
+    /// to market
+    VariationMargin,
+    /// A month's interest earned on cash, or paid on a margin debit balance
+    Interest,
+}
+
+/// One of the ledgers an account's double-entry journal posts to
+///
+/// Debits are positive & credits negative, so every journal entry sums to
+/// zero. Cash, securities & fees are debited as they grow, capital, realized
+/// profit & dividends credited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type, Serialize)]
+#[sqlx(type_name = "ledger_account", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    /// The account's cash
+    Cash,
+    /// The cost of the symbols held, along with their quantity
+    Securities,
+    /// What's been deposited into the account
+    Capital,
+    /// The profit or loss of positions closed
+    RealizedPnl,
+    /// Commission & regulatory fees paid
+    Fees,
+    /// Interest paid on shares borrowed to sell short
+    BorrowFees,
+    /// Dividends received, or paid in lieu when short
+    Dividends,
+    /// What's been exchanged into or out of a currency, balancing the cash
+    /// each [`Conversion`] moves in it
+    Exchange,
+    /// What futures held are worth, as they're bought & sold without paying
+    /// for it, balancing their cost
+    Notional,
+    /// Interest earned on cash, net of that paid on margin debit balances
+    Interest,
+}
+
+/// A recorded balance the journal disagrees with
+#[derive(Debug, Clone, Serialize)]
+pub struct Drift {
+    /// The account holding the balance
+    pub account_id: i32,
+    /// The balance that drifted
+    pub balance: Balance,
+    /// The symbol the balance is kept for, or the currency of cash, if any
+    pub symbol: Option<String>,
+    /// The balance rebuilt from the journal
+    pub journal: Decimal,
+    /// The balance as recorded
+    pub recorded: Decimal,
+}
+
+/// A balance reconciled against the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Balance {
+    /// An account's cash
+    Cash,
+    /// The quantity of a [`Position`]
+    Quantity,
+    /// The cost basis of a [`Position`]
+    CostBasis,
+    /// The realized profit of a [`Position`]
+    RealizedPnl,
+    /// The fees paid for a [`Position`]
+    Fees,
+    /// The borrow fees paid for a [`Position`]
+    BorrowFees,
+    /// The dividends received for a [`Position`]
+    Dividends,
+    /// The quantity of a [`Position`]'s open [`Lot`]s
+    Lots,
+    /// A journal entry, by id, whose lines don't sum to zero
+    Entry(i64),
+}
+
+/// Cash an [`Account`] holds in a currency other than its base currency
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CashBalance {
+    /// The account holding the cash
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The cash's currency
+    pub currency: String,
+    /// The cash held, negative when owed, kept from the journal like
+    /// [`Account::cash`]
+    pub amount: Money,
+}
+
+/// An exchange of an [`Account`]'s cash for another currency's
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Conversion {
+    /// A conversion's id
+    #[serde(skip)]
+    pub id: i32,
+    /// A conversion's uuid
+    pub uuid: Uuid,
+    /// The account converting
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The fill the conversion paid for, or was paid by, when made
+    /// automatically
+    #[serde(skip)]
+    pub fill_id: Option<i32>,
+    /// The currency converted from
+    pub from_currency: String,
+    /// The currency converted to
+    pub to_currency: String,
+    /// The cash converted
+    pub amount: Money,
+    /// What a unit of the currency converted from fetched, after the spread
+    pub rate: Decimal,
+    /// The cash received
+    pub converted: Money,
+    /// When the conversion was made
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// A conversion of cash from one currency to another
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewConversion {
+    /// The currency to convert from
+    pub from: String,
+    /// The currency to convert to
+    pub to: String,
+    /// The cash to convert, in the currency converted from
+    pub amount: Money,
+}
+
+impl NewConversion {
+    /// Checks that the conversion is well formed
+    ///
+    /// Both currencies must be three letter codes, and differ, and the amount
+    /// must be positive.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        is_currency(&self.from)
+            && is_currency(&self.to)
+            && self.from != self.to
+            && self.amount > Money::ZERO
+    }
+}
+
+/// Whether a code looks like a currency's, three uppercase letters
+#[must_use]
+pub fn is_currency(code: &str) -> bool {
+    code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// The commission & regulatory fees an [`Account`] pays to trade
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct FeeSchedule {
+    /// A schedule's id
+    #[serde(skip)]
+    pub id: i32,
+    /// A schedule's unique name
+    pub name: String,
+    /// Commission per share traded
+    pub per_share: Decimal,
+    /// Commission per trade
+    pub per_trade: Decimal,
+    /// Commission as a percentage of the notional traded
+    pub percent: Decimal,
+    /// The least commission charged per trade
+    pub minimum: Decimal,
+    /// The SEC fee on sells, as a fraction of the notional sold
+    pub sec_fee_rate: Decimal,
+    /// The FINRA trading activity fee on sells, per share sold
+    pub taf_per_share: Decimal,
+    /// The most trading activity fee charged per trade, if capped
+    pub taf_maximum: Option<Decimal>,
+}
+
+impl FeeSchedule {
+    /// The commission charged for a trade, before regulatory fees
+    #[must_use]
+    pub fn commission(&self, quantity: Decimal, price: Decimal) -> Decimal {
+        let commission = self.per_share * quantity
+            + self.per_trade
+            + self.percent / Decimal::ONE_HUNDRED * quantity * price;
+        commission.max(self.minimum)
+    }
+
+    /// The regulatory fees passed through for a trade
+    ///
+    /// Only sells pay them.
+    #[must_use]
+    pub fn regulatory(&self, side: OrderSide, quantity: Decimal, price: Decimal) -> Decimal {
+        if side == OrderSide::Buy {
+            return Decimal::ZERO;
+        }
+        let taf = self.taf_per_share * quantity;
+        let taf = self.taf_maximum.map_or(taf, |maximum| taf.min(maximum));
+        self.sec_fee_rate * quantity * price + taf
+    }
+
+    /// The total fee for a trade, rounded up to the cent
+    #[must_use]
+    pub fn fee(&self, side: OrderSide, quantity: Decimal, price: Decimal) -> Decimal {
+        (self.commission(quantity, price) + self.regulatory(side, quantity, price))
+            .round_dp_with_strategy(2, RoundingStrategy::AwayFromZero)
+    }
+}
+
+/// What it costs to borrow a symbol, to sell it short
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BorrowRate {
+    /// The borrowed symbol
+    pub symbol: String,
+    /// Whether the symbol can be shorted without a locate
+    pub easy_to_borrow: bool,
+    /// The yearly interest on the borrowed value, in percent
+    pub annual_rate: Decimal,
+    /// When the rate last changed
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl BorrowRate {
+    /// The days a year's interest is spread over
+    pub const DAYS_PER_YEAR: u16 = 360;
+
+    /// A day's interest on `quantity` borrowed at `price`, rounded up to the
+    /// cent
+    #[must_use]
+    pub fn daily_fee(&self, quantity: Decimal, price: Decimal) -> Decimal {
+        (quantity.abs() * price * self.annual_rate
+            / Decimal::ONE_HUNDRED
+            / Decimal::from(Self::DAYS_PER_YEAR))
+        .round_dp_with_strategy(2, RoundingStrategy::AwayFromZero)
+    }
+}
+
+/// Whether interest is earned on cash, or paid on what's borrowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "interest_kind", rename_all = "snake_case")]
+pub enum InterestKind {
+    /// Earned on a positive cash balance
+    Credit,
+    /// Paid on a negative cash balance, borrowed on margin
+    Debit,
+}
+
+/// The yearly interest on cash in a currency, from the date it takes effect
+/// until the next rate for its currency & kind does
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct InterestRate {
+    /// A rate's id
+    #[serde(skip)]
+    pub id: i32,
+    /// The currency of the cash
+    pub currency: String,
+    /// Whether the rate is earned or paid
+    pub kind: InterestKind,
+    /// The first day the rate applies
+    #[serde(with = "iso_date")]
+    pub effective_on: Date,
+    /// The yearly interest, in percent
+    pub annual_rate: Decimal,
+}
+
+impl InterestRate {
+    /// The days a year's interest is spread over
+    pub const DAYS_PER_YEAR: u16 = 360;
+
+    /// The decimal places interest accrues to, finer than the cent it's
+    /// posted in
+    pub const ACCRUAL_DP: u32 = 8;
+
+    /// A day's interest on `balance`, positive when earned & negative when
+    /// paid
+    #[must_use]
+    pub fn daily_interest(&self, balance: Decimal) -> Decimal {
+        (balance * self.annual_rate / Decimal::ONE_HUNDRED / Decimal::from(Self::DAYS_PER_YEAR))
+            .round_dp(Self::ACCRUAL_DP)
+    }
+}
+
+/// A day's interest on the cash an [`Account`] holds in a currency, posted
+/// with the rest of its month's
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct InterestAccrual {
+    /// An accrual's id
+    #[serde(skip)]
+    pub id: i32,
+    /// The account holding the cash
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The currency of the cash
+    pub currency: String,
+    /// The day the interest accrued for
+    #[serde(with = "iso_date")]
+    pub date: Date,
+    /// Whether the interest was earned or paid
+    pub kind: InterestKind,
+    /// The cash held, negative when borrowed
+    pub balance: Decimal,
+    /// The yearly interest the day accrued at, in percent
+    pub annual_rate: Decimal,
+    /// The interest, to fractions of a cent, positive when earned & negative
+    /// when paid
+    pub amount: Decimal,
+    /// The first day of the month after the one the interest was posted
+    /// with, if it's been posted
+    #[serde(with = "iso_date::option")]
+    pub posted_on: Option<Date>,
+}
+
+/// What kind of asset an [`Instrument`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "asset_class", rename_all = "snake_case")]
+pub enum AssetClass {
+    /// A company's shares
+    Equity,
+    /// An exchange traded fund's shares
+    Etf,
+    /// A contract to buy or sell another symbol's shares at a strike price,
+    /// up to its expiry
+    Option,
+    /// A contract to buy or sell a quantity of an asset at its expiry, marked
+    /// to market daily
+    Future,
+    /// A cryptocurrency, priced in a quote currency as a pair like
+    /// `BTC-USD`, and traded around the clock
+    Crypto,
+}
+
+/// Whether an option contract gives the right to buy or to sell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "option_type", rename_all = "snake_case")]
+pub enum OptionType {
+    /// The right to buy the underlying at the strike
+    Call,
+    /// The right to sell the underlying at the strike
+    Put,
+}
+
+/// A symbol in the registry, where it trades & the increments its prices &
+/// quantities trade in
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Instrument {
+    /// The traded symbol
+    pub symbol: String,
+    /// What the symbol's issuer, or fund, is called
+    pub name: String,
+    /// The code of the exchange the symbol trades on, as the calendar knows
+    /// it
+    pub exchange: String,
+    /// What kind of asset the symbol is
+    pub asset_class: AssetClass,
+    /// The currency the symbol is priced in
+    pub currency: String,
+    /// The smallest step a price may move by
+    pub tick_size: Money,
+    /// The step whole quantities trade in
+    pub lot_size: Quantity,
+    /// Whether quantities may be fractions of a lot, to
+    /// [`Instrument::FRACTIONAL_DP`] decimal places, or
+    /// [`Instrument::CRYPTO_DP`] for crypto
+    pub fractional: bool,
+    /// Whether the symbol may be sold short, when it's easy to borrow
+    pub shortable: bool,
+    /// Whether the symbol still trades, or has been delisted
+    pub active: bool,
+    /// The units of the underlying each unit of quantity stands for, so the
+    /// cash a unit of price is worth, e.g. `100` for equity options
+    #[serde(default = "Instrument::default_multiplier")]
+    pub multiplier: Quantity,
+    /// The symbol an option contract is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlying: Option<String>,
+    /// Whether an option contract is a call or a put
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub option_type: Option<OptionType>,
+    /// The price an option contract's underlying is bought or sold at, if
+    /// it's exercised
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strike: Option<Money>,
+    /// The last date a contract trades, expiring at its end, & a future's
+    /// final settlement
+    #[serde(
+        default,
+        with = "iso_date::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expires_on: Option<Date>,
+    /// The equity a future needs to open each contract, in its currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_margin: Option<Money>,
+    /// The equity a future needs to keep each contract open, in its currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_margin: Option<Money>,
+    /// The date a future's positions are rolled to its next contract, ahead
+    /// of its expiry
+    #[serde(
+        default,
+        with = "iso_date::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub roll_on: Option<Date>,
+    /// The last date a future was settled as of, at its start
+    #[serde(skip)]
+    pub settled_on: Option<Date>,
+}
+
+impl Instrument {
+    /// The decimal places fractional quantities may have
+    pub const FRACTIONAL_DP: u32 = 6;
+    /// The decimal places fractional quantities of crypto may have
+    pub const CRYPTO_DP: u32 = Quantity::DP;
+
+    /// How a symbol missing from the registry is held & valued, in cents &
+    /// whole or fractional shares of the [`DEFAULT_CURRENCY`]
+    ///
+    /// It's on no exchange, so never trades.
+    #[must_use]
+    pub fn unlisted(symbol: String) -> Self {
+        Self {
+            symbol,
+            name: String::new(),
+            exchange: String::new(),
+            asset_class: AssetClass::Equity,
+            currency: DEFAULT_CURRENCY.to_owned(),
+            tick_size: Money::new(1, 2),
+            lot_size: Quantity::ONE,
+            fractional: true,
+            shortable: false,
+            active: false,
+            multiplier: Quantity::ONE,
+            underlying: None,
+            option_type: None,
+            strike: None,
+            expires_on: None,
+            initial_margin: None,
+            maintenance_margin: None,
+            roll_on: None,
+            settled_on: None,
+        }
+    }
+
+    /// Symbols stand for a single unit of themselves, unless they're
+    /// contracts
+    const fn default_multiplier() -> Quantity {
+        Quantity::ONE
+    }
+
+    /// Whether the symbol is an option contract
+    #[must_use]
+    pub fn is_option(&self) -> bool {
+        self.asset_class == AssetClass::Option
+    }
+
+    /// Whether the symbol is a future, marked to market
+    #[must_use]
+    pub fn is_future(&self) -> bool {
+        self.asset_class == AssetClass::Future
+    }
+
+    /// Whether the symbol is crypto, trading around the clock
+    #[must_use]
+    pub fn is_crypto(&self) -> bool {
+        self.asset_class == AssetClass::Crypto
+    }
+
+    /// The base & quote currencies of a crypto pair's symbol, like `BTC` &
+    /// `USD` for `BTC-USD`
+    ///
+    /// Returns `None` if the symbol isn't a pair, an alphanumeric base
+    /// followed by a dash & a currency code.
+    #[must_use]
+    pub fn pair(symbol: &str) -> Option<(&str, &str)> {
+        let (base, quote) = symbol.split_once('-')?;
+        (!base.is_empty() && base.bytes().all(|b| b.is_ascii_alphanumeric()) && is_currency(quote))
+            .then_some((base, quote))
+    }
+
+    /// What exercising a unit of an option contract is worth, with its
+    /// underlying at `price`, or zero if it's out of the money or not a
+    /// contract
+    #[must_use]
+    pub fn intrinsic_value(&self, price: Decimal) -> Decimal {
+        let Some(strike) = self.strike else {
+            return Decimal::ZERO;
+        };
+        match self.option_type {
+            Some(OptionType::Call) => (price - *strike).max(Decimal::ZERO),
+            Some(OptionType::Put) => (*strike - price).max(Decimal::ZERO),
+            None => Decimal::ZERO,
+        }
+    }
+
+    /// Whether a price is a whole number of ticks
+    #[must_use]
+    pub fn allows_price(&self, price: Decimal) -> bool {
+        (price % *self.tick_size).is_zero()
+    }
+
+    /// Whether a quantity is a whole number of lots, or a fraction with few
+    /// enough decimal places
+    #[must_use]
+    pub fn allows_quantity(&self, quantity: Decimal) -> bool {
+        if self.fractional && self.is_crypto() {
+            quantity.normalize().scale() <= Self::CRYPTO_DP
+        } else if self.fractional {
+            quantity.normalize().scale() <= Self::FRACTIONAL_DP
+        } else {
+            (quantity % *self.lot_size).is_zero()
+        }
+    }
+
+    /// Rounds a quantity to a whole number of lots, or to as many decimal
+    /// places as fractions may have
+    #[must_use]
+    pub fn round_quantity(&self, quantity: Decimal, strategy: RoundingStrategy) -> Decimal {
+        if self.fractional && self.is_crypto() {
+            quantity.round_dp_with_strategy(Self::CRYPTO_DP, strategy)
+        } else if self.fractional {
+            quantity.round_dp_with_strategy(Self::FRACTIONAL_DP, strategy)
+        } else {
+            (quantity / *self.lot_size).round_dp_with_strategy(0, strategy) * *self.lot_size
+        }
+    }
+
+    /// Whether an order's quantity & prices are in the symbol's increments
+    ///
+    /// Trailing stops' percentages aren't prices, so aren't checked.
+    #[must_use]
+    pub fn allows(&self, order: &NewOrder) -> bool {
+        self.allows_quantity(*order.quantity)
+            && [order.limit_price, order.stop_price, order.trail_amount]
+                .into_iter()
+                .flatten()
+                .all(|price| self.allows_price(*price))
+    }
+
+    /// Rounds a price to a whole number of ticks, never less than one
+    #[must_use]
+    pub fn round_price(&self, price: Decimal, strategy: RoundingStrategy) -> Decimal {
+        let tick = *self.tick_size;
+        ((price / tick).round_dp_with_strategy(0, strategy) * tick).max(tick)
+    }
+}
+
+/// A kind of [`CorporateAction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "corporate_action_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CorporateActionKind {
+    /// Each share becomes `ratio` shares, fewer for reverse splits
+    Split,
+    /// Each share held as the symbol goes ex-dividend is paid `amount` cash
+    Dividend,
+}
+
+time::serde::format_description!(iso_date, Date, "[year]-[month]-[day]");
+
+/// A change a company makes to its shares, applied to holdings from the
+/// start of its ex-date
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CorporateAction {
+    /// An action's id
+    #[serde(skip)]
+    pub id: i32,
+    /// The symbol acted on
+    pub symbol: String,
+    /// What the action does
+    pub kind: CorporateActionKind,
+    /// The first date the symbol trades with the action applied
+    #[serde(with = "iso_date")]
+    pub ex_date: Date,
+    /// The date a dividend is paid
+    #[serde(with = "iso_date::option")]
+    pub pay_date: Option<Date>,
+    /// The shares a split gives for each held, to as many places as the
+    /// prices it divides keep
+    pub ratio: Option<Money>,
+    /// The cash a dividend pays a share
+    pub amount: Option<Money>,
+    /// When the action was applied to holdings
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub applied_at: Option<OffsetDateTime>,
+}
+
+impl CorporateAction {
+    /// When the action takes effect, at the start of its ex-date
+    #[must_use]
+    pub const fn effective_at(&self) -> OffsetDateTime {
+        self.ex_date.midnight().assume_utc()
+    }
+}
+
+/// The values needed to record a [`CorporateAction`]
+#[derive(Debug, Clone)]
+pub struct NewCorporateAction {
+    /// The symbol acted on
+    pub symbol: String,
+    /// What the action does
+    pub kind: CorporateActionKind,
+    /// The first date the symbol trades with the action applied
+    pub ex_date: Date,
+    /// The date a dividend is paid
+    pub pay_date: Option<Date>,
+    /// The shares a split gives for each held, to as many places as the
+    /// prices it divides keep
+    pub ratio: Option<Money>,
+    /// The cash a dividend pays a share
+    pub amount: Option<Money>,
+}
+
+/// What an account is owed by a dividend, from what it held as the symbol
+/// went ex-dividend
+#[derive(Debug, Clone, FromRow)]
+pub struct Dividend {
+    /// A dividend's id
+    pub id: i32,
+    /// The dividend's [`CorporateAction`]
+    pub action_id: i32,
+    /// The account owed the dividend
+    pub account_id: i32,
+    /// The symbol paying the dividend
+    pub symbol: String,
+    /// The quantity held, negative when short
+    pub quantity: Quantity,
+    /// The cash owed, negative when paid in lieu by a short
+    pub amount: Money,
+    /// The date the dividend is paid
+    pub pay_date: Date,
+    /// When the dividend was paid
+    pub paid_at: Option<OffsetDateTime>,
+    /// The order reinvesting the dividend, if it was
+    pub order_id: Option<i32>,
+}
+
+/// An order to buy or sell a symbol
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Order {
+    /// An order's id
+    #[serde(skip)]
+    pub id: i32,
+    /// An order's uuid
+    pub uuid: Uuid,
+    /// The account the order trades for
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The traded symbol
+    pub symbol: String,
+    /// Whether the order buys or sells
+    pub side: OrderSide,
+    /// How the order is priced
+    pub order_type: OrderType,
+    /// The total quantity to trade
+    pub quantity: Quantity,
+    /// The worst price the order accepts, for limit & stop limit orders
+    pub limit_price: Option<Money>,
+    /// The price that triggers the order, for stop orders
+    ///
+    /// Trailing stops move this as the market moves in their favour.
+    pub stop_price: Option<Money>,
+    /// How far a trailing stop trails the market, as a price
+    pub trail_amount: Option<Money>,
+    /// How far a trailing stop trails the market, as a percentage
+    pub trail_percent: Option<Decimal>,
+    /// The best price a trailing stop has seen, which it trails
+    pub trail_reference: Option<Money>,
+    /// When a stop order was triggered
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub triggered_at: Option<OffsetDateTime>,
+    /// How long the order stays open
+    pub time_in_force: TimeInForce,
+    /// When the order expires, if it does
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Whether the order also trades outside regular trading hours
+    pub extended_hours: bool,
+    /// The group the order belongs to, if any
+    #[serde(skip)]
+    pub group_id: Option<i32>,
+    /// The order whose fill activates this one, if any
+    #[serde(skip)]
+    pub parent_id: Option<i32>,
+    /// Who placed the order
+    pub origin: OrderOrigin,
+    /// Where the order is in its lifecycle
+    pub status: OrderStatus,
+    /// The quantity traded so far
+    pub filled_quantity: Quantity,
+    /// When the order was placed
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// When the order last changed
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl Order {
+    /// The quantity left to trade
+    #[must_use]
+    pub fn remaining(&self) -> Quantity {
+        self.quantity - self.filled_quantity
+    }
+}
+
+/// The values needed to place an [`Order`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewOrder {
+    /// The traded symbol
+    pub symbol: String,
+    /// Whether the order buys or sells
+    pub side: OrderSide,
+    /// How the order is priced
+    pub order_type: OrderType,
+    /// The total quantity to trade
+    pub quantity: Quantity,
+    /// The worst price the order accepts, for limit & stop limit orders
+    pub limit_price: Option<Money>,
+    /// The price that triggers the order, for stop & stop limit orders
+    pub stop_price: Option<Money>,
+    /// How far a trailing stop trails the market, as a price
+    pub trail_amount: Option<Money>,
+    /// How far a trailing stop trails the market, as a percentage
+    pub trail_percent: Option<Decimal>,
+    /// How long the order stays open
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// When the order expires, for good 'til date orders
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Whether the order also trades outside regular trading hours
+    #[serde(default)]
+    pub extended_hours: bool,
+    /// Who places the order, always the user through the api
+    #[serde(skip)]
+    pub origin: OrderOrigin,
+    /// The price a market order was quoted at as it was placed, which it
+    /// reserves while it's open
+    #[serde(skip)]
+    pub quoted_price: Option<Money>,
+}
+
+impl NewOrder {
+    /// Checks that the order is well formed
+    ///
+    /// Symbols must be non-empty & at most 21 bytes, long enough for option
+    /// contracts' OCC symbols, and quantities positive. Each order type must
+    /// have exactly the (positive) prices it uses: trailing stops take either
+    /// an amount or a percentage below 100, and have their stop price set
+    /// from the market.
+    ///
+    /// Only good 'til date orders may have an expiry, and only market & limit
+    /// orders may be immediate or cancel, or fill or kill. Only limit orders
+    /// may trade in extended hours.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        let (limit, stop, trail) = match self.order_type {
+            OrderType::Market => (false, false, false),
+            OrderType::Limit => (true, false, false),
+            OrderType::Stop => (false, true, false),
+            OrderType::StopLimit => (true, true, false),
+            OrderType::TrailingStop => (false, false, true),
+        };
+        let price = |price: Option<Money>, wanted: bool| {
+            price.map_or(!wanted, |price| wanted && price > Money::ZERO)
+        };
+        let trail = match (self.trail_amount, self.trail_percent) {
+            (None, None) => !trail,
+            (Some(amount), None) => trail && amount > Money::ZERO,
+            (None, Some(percent)) => {
+                trail && percent > Decimal::ZERO && percent < Decimal::ONE_HUNDRED
+            }
+            (Some(_), Some(_)) => false,
+        };
+        let symbol = !self.symbol.is_empty() && self.symbol.len() <= 21;
+        let quantity = self.quantity > Quantity::ZERO;
+        let time_in_force = match self.time_in_force {
+            TimeInForce::Gtd => self.expires_at.is_some(),
+            TimeInForce::Ioc | TimeInForce::Fok => {
+                self.expires_at.is_none() && !self.order_type.is_stop()
+            }
+            TimeInForce::Day | TimeInForce::Gtc => self.expires_at.is_none(),
+        };
+        symbol
+            && quantity
+            && price(self.limit_price, limit)
+            && price(self.stop_price, stop)
+            && trail
+            && time_in_force
+            && (!self.extended_hours || self.order_type == OrderType::Limit)
+    }
+
+    /// The take profit & stop loss exiting this order, in a bracket
+    ///
+    /// Both are good 'til cancelled.
+    #[must_use]
+    pub fn exits(&self, take_profit: Money, stop_loss: Money) -> [Self; 2] {
+        let side = match self.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let exit = |order_type, limit_price, stop_price| Self {
+            symbol: self.symbol.clone(),
+            side,
+            order_type,
+            quantity: self.quantity,
+            limit_price,
+            stop_price,
+            trail_amount: None,
+            trail_percent: None,
+            time_in_force: TimeInForce::Gtc,
+            expires_at: None,
+            extended_hours: false,
+            origin: self.origin,
+            quoted_price: None,
+        };
+        [
+            exit(OrderType::Limit, Some(take_profit), None),
+            exit(OrderType::Stop, None, Some(stop_loss)),
+        ]
+    }
+}
+
+/// The direction of an [`Order`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "order_side", rename_all = "snake_case")]
+pub enum OrderSide {
+    /// Buy the symbol
+    Buy,
+    /// Sell the symbol
+    Sell,
+}
+
+/// How an [`Order`] is priced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "order_type", rename_all = "snake_case")]
+pub enum OrderType {
+    /// Trade at the current market price
+    Market,
+    /// Trade at the limit price or better
+    Limit,
+    /// Trade at the market price, once it reaches the stop price
+    Stop,
+    /// Trade at the limit price or better, once the market reaches the stop
+    /// price
+    StopLimit,
+    /// A stop order whose stop price trails the market as it moves in the
+    /// holder's favour
+    TrailingStop,
+}
+
+impl OrderType {
+    /// Whether the order waits for a stop price to be reached
+    #[must_use]
+    pub const fn is_stop(self) -> bool {
+        matches!(self, Self::Stop | Self::StopLimit | Self::TrailingStop)
+    }
+}
+
+/// Where an [`Order`] is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "order_status", rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Waiting to be filled
+    Open,
+    /// Completely traded
+    Filled,
+    /// Cancelled by its owner
+    Cancelled,
+    /// Refused by the server
+    Rejected,
+    /// Left unfilled past its time in force
+    Expired,
+    /// Waiting for its parent to fill before it opens
+    Pending,
+}
+
+/// How long an [`Order`] stays open
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "time_in_force", rename_all = "snake_case")]
+pub enum TimeInForce {
+    /// Until the end of the trading session
+    #[default]
+    Day,
+    /// Good 'til cancelled, open until filled or cancelled
+    Gtc,
+    /// Immediate or cancel, any quantity not filled at once is cancelled
+    Ioc,
+    /// Fill or kill, rejected unless the whole quantity fills at once
+    Fok,
+    /// Good 'til date, open until its expiry
+    Gtd,
+}
+
+/// Who placed an [`Order`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "order_origin", rename_all = "snake_case")]
+pub enum OrderOrigin {
+    /// The account's user
+    #[default]
+    User,
+    /// The server, closing positions to meet a margin call
+    Liquidation,
+    /// The server, reinvesting a dividend in the symbol paying it
+    Reinvestment,
+    /// The server, exercising an option contract held as it expired in the
+    /// money
+    Exercise,
+    /// The server, settling an option contract written that was assigned as
+    /// it expired in the money
+    Assignment,
+    /// The server, closing a position in an option contract as it expired
+    Expiration,
+    /// The server, closing a position in a future at its final settlement
+    Settlement,
+}
+
+/// Orders placed together, which fill & cancel one another
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderGroup {
+    /// A group's id
+    #[serde(skip)]
+    pub id: i32,
+    /// A group's uuid
+    pub uuid: Uuid,
+    /// The account the group trades for
+    #[serde(skip)]
+    pub account_id: i32,
+    /// How the group's orders relate
+    pub kind: OrderGroupKind,
+    /// The units of its legs a multi-leg group trades
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    /// The worst net price a multi-leg group accepts, see
+    /// [`NewOrderGroup::MultiLeg`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<Money>,
+    /// When the group was placed
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// How the orders of an [`OrderGroup`] relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "order_group_kind", rename_all = "snake_case")]
+pub enum OrderGroupKind {
+    /// An entry order, whose fill opens a take profit & a stop loss, the
+    /// first of which to fill cancels the other
+    Bracket,
+    /// Orders the first of which to fill cancels the others
+    Oco,
+    /// Orders that fill together, all at once or not at all
+    MultiLeg,
+}
+
+/// The values needed to place an [`OrderGroup`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NewOrderGroup {
+    /// An entry order, with a take profit & a stop loss to exit it
+    Bracket {
+        /// The order opening the position
+        entry: NewOrder,
+        /// The limit price of the exit taking profit
+        take_profit: Money,
+        /// The stop price of the exit limiting losses
+        stop_loss: Money,
+    },
+    /// Two orders, the first of which to fill cancels the other
+    Oco {
+        /// The orders themselves
+        orders: [NewOrder; 2],
+    },
+    /// Orders in several symbols, a spread, that trade as a unit at a net
+    /// price
+    ///
+    /// The net price is what the legs bought cost less what those sold raise,
+    /// each leg's price weighted by its ratio & multiplier and the total given
+    /// per unit of the largest multiplier, as spreads are quoted. It's a debit
+    /// when positive and a credit when negative.
+    MultiLeg {
+        /// The spread's legs
+        legs: Vec<NewLeg>,
+        /// The units of the spread to trade
+        quantity: Quantity,
+        /// The worst net price the spread accepts, the most it pays if it's
+        /// positive or the least it raises if it's negative
+        limit_price: Money,
+        /// How long the spread stays open
+        #[serde(default)]
+        time_in_force: TimeInForce,
+        /// When the spread expires, for good 'til date spreads
+        #[serde(default, with = "time::serde::rfc3339::option")]
+        expires_at: Option<OffsetDateTime>,
+    },
+}
+
+impl NewOrderGroup {
+    /// The most legs a multi-leg group may have
+    pub const MAX_LEGS: usize = 4;
+
+    /// How the group's orders relate
+    #[must_use]
+    pub const fn kind(&self) -> OrderGroupKind {
+        match self {
+            Self::Bracket { .. } => OrderGroupKind::Bracket,
+            Self::Oco { .. } => OrderGroupKind::Oco,
+            Self::MultiLeg { .. } => OrderGroupKind::MultiLeg,
+        }
+    }
+
+    /// Checks a group is well formed
+    ///
+    /// Each order must be valid. A bracket's take profit must be on the
+    /// profitable side of its stop loss. One-cancels-other orders must trade
+    /// the same symbol, and rest on the book rather than fill at once. A
+    /// multi-leg group must have between two & [`Self::MAX_LEGS`] legs, each
+    /// in a different symbol & with a positive ratio.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Bracket {
+                entry,
+                take_profit,
+                stop_loss,
+            } => {
+                let exits = match entry.side {
+                    OrderSide::Buy => stop_loss < take_profit,
+                    OrderSide::Sell => take_profit < stop_loss,
+                };
+                entry.is_valid() && *stop_loss > Money::ZERO && exits
+            }
+            Self::Oco { orders: [a, b] } => {
+                let resting = |order: &NewOrder| {
+                    !matches!(order.time_in_force, TimeInForce::Ioc | TimeInForce::Fok)
+                };
+                let symbol = a.symbol.trim().eq_ignore_ascii_case(b.symbol.trim());
+                a.is_valid() && b.is_valid() && symbol && resting(a) && resting(b)
+            }
+            Self::MultiLeg {
+                legs,
+                quantity,
+                time_in_force,
+                expires_at,
+                ..
+            } => {
+                let mut symbols: Vec<_> = legs
+                    .iter()
+                    .map(|leg| leg.symbol.trim().to_ascii_uppercase())
+                    .collect();
+                symbols.sort_unstable();
+                symbols.dedup();
+                (2..=Self::MAX_LEGS).contains(&legs.len())
+                    && symbols.len() == legs.len()
+                    && legs.iter().all(|leg| {
+                        leg.ratio > Decimal::ZERO
+                            && leg.order(*quantity, *time_in_force, *expires_at).is_valid()
+                    })
+            }
+        }
+    }
+}
+
+/// One leg of a [`NewOrderGroup::MultiLeg`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewLeg {
+    /// The traded symbol
+    pub symbol: String,
+    /// Whether the leg buys or sells
+    pub side: OrderSide,
+    /// The leg's quantity in each unit of the spread
+    #[serde(default = "NewLeg::default_ratio")]
+    pub ratio: Decimal,
+}
+
+impl NewLeg {
+    /// Legs trade one of their symbol in each unit of their spread, unless
+    /// given a ratio
+    const fn default_ratio() -> Decimal {
+        Decimal::ONE
+    }
+
+    /// The order trading the leg, for `quantity` units of its spread
+    ///
+    /// Legs are market orders, the spread's net price being the only limit.
+    /// Their quantity is rounded to the places a [`Quantity`] keeps.
+    #[must_use]
+    pub fn order(
+        &self,
+        quantity: Quantity,
+        time_in_force: TimeInForce,
+        expires_at: Option<OffsetDateTime>,
+    ) -> NewOrder {
+        NewOrder {
+            symbol: self.symbol.clone(),
+            side: self.side,
+            order_type: OrderType::Market,
+            quantity: Quantity::round(self.ratio * *quantity),
+            limit_price: None,
+            stop_price: None,
+            trail_amount: None,
+            trail_percent: None,
+            time_in_force,
+            expires_at,
+            extended_hours: false,
+            origin: OrderOrigin::User,
+            quoted_price: None,
+        }
+    }
+}
+
+/// An execution of (part of) an [`Order`]
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Fill {
+    /// A fill's id
+    #[serde(skip)]
+    pub id: i32,
+    /// A fill's uuid
+    pub uuid: Uuid,
+    /// The filled order's id
+    #[serde(skip)]
+    pub order_id: i32,
+    /// The account the fill trades for
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The traded symbol
+    pub symbol: String,
+    /// Whether the fill bought or sold
+    pub side: OrderSide,
+    /// The quantity traded
+    pub quantity: Quantity,
+    /// The price traded at
+    pub price: Money,
+    /// The commission & regulatory fees paid
+    pub fee: Money,
+    /// When the fill happened
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// An account's holding of a symbol
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Position {
+    /// A position's id
+    #[serde(skip)]
+    pub id: i32,
+    /// The account holding the position
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The held symbol
+    pub symbol: String,
+    /// The quantity held, negative when short
+    pub quantity: Quantity,
+    /// The total cost of the quantity held, negative when short
+    pub cost_basis: Money,
+    /// The profit or loss of everything closed so far, before fees
+    pub realized_pnl: Money,
+    /// The fees paid trading the symbol so far
+    pub fees: Money,
+    /// The fees paid borrowing the symbol to short it so far
+    pub borrow_fees: Money,
+    /// The dividends received holding the symbol so far, less those paid in
+    /// lieu while short
+    pub dividends: Money,
+    /// Whether dividends are reinvested in the symbol, following the
+    /// account's setting if `None`
+    pub drip: Option<bool>,
+    /// When the position last changed
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Changes to a [`Position`]'s settings
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PositionSettings {
+    /// Whether dividends are reinvested in the symbol, following the
+    /// account's setting if `None`
+    pub drip: Option<bool>,
+}
+
+/// A quantity of a symbol bought, or sold short, by a single [`Fill`], tracked
+/// for its cost
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Lot {
+    /// A lot's id
+    #[serde(skip)]
+    pub id: i32,
+    /// The account holding the lot
+    #[serde(skip)]
+    pub account_id: i32,
+    /// The held symbol
+    pub symbol: String,
+    /// The fill that opened the lot
+    #[serde(skip)]
+    pub fill_id: i32,
+    /// The quantity still held, negative for short lots
+    pub quantity: Quantity,
+    /// The price paid per unit, or received for short lots
+    pub price: Money,
+    /// When the lot was opened
+    #[serde(with = "time::serde::rfc3339")]
+    pub opened_at: OffsetDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The retail schedule, passing through regulatory fees only
+    fn retail() -> FeeSchedule {
+        FeeSchedule {
+            id: 1,
+            name: "retail".to_owned(),
+            per_share: Decimal::ZERO,
+            per_trade: Decimal::ZERO,
+            percent: Decimal::ZERO,
+            minimum: Decimal::ZERO,
+            sec_fee_rate: Decimal::new(278, 7),
+            taf_per_share: Decimal::new(166, 6),
+            taf_maximum: Some(Decimal::new(830, 2)),
+        }
+    }
+
+    #[test]
+    fn only_sells_pay_regulatory_fees() {
+        let schedule = retail();
+        let (quantity, price) = (Decimal::ONE_HUNDRED, Decimal::from(150));
+
+        assert_eq!(schedule.fee(OrderSide::Buy, quantity, price), Decimal::ZERO);
+        // 0.417 sec fee & 0.0166 taf, rounded up
+        assert_eq!(
+            schedule.fee(OrderSide::Sell, quantity, price),
+            Decimal::new(44, 2)
+        );
+    }
+
+    #[test]
+    fn trading_activity_fees_are_capped() {
+        let schedule = retail();
+
+        let fee = schedule.fee(OrderSide::Sell, Decimal::from(100_000), Decimal::ONE);
+
+        // 2.78 sec fee & 8.30 taf, capped from 16.60
+        assert_eq!(fee, Decimal::new(1108, 2));
+    }
+
+    #[test]
+    fn commissions_are_charged_at_least_the_minimum() {
+        let schedule = FeeSchedule {
+            per_trade: Decimal::ONE,
+            percent: Decimal::new(25, 2),
+            minimum: Decimal::from(5),
+            ..retail()
+        };
+
+        let small = schedule.fee(OrderSide::Buy, Decimal::TEN, Decimal::ONE_HUNDRED);
+        let large = schedule.fee(OrderSide::Buy, Decimal::ONE_HUNDRED, Decimal::ONE_HUNDRED);
+
+        assert_eq!(small, Decimal::from(5));
+        // 1 per trade & 25 at 0.25% of 10,000
+        assert_eq!(large, Decimal::from(26));
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Deref, Neg, Sub, SubAssign},
+};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef},
+    Decode, Encode, Postgres, Type,
+};
+use thiserror::Error;
+
+/// A decimal with more places than its type keeps
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("{value} has more than {places} decimal places")]
+pub struct ScaleError {
+    /// The decimal that was too fine
+    pub value: Decimal,
+    /// The most decimal places its type keeps
+    pub places: u32,
+}
+
+/// Defines a decimal newtype keeping at most `$dp` decimal places, stored as
+/// a Postgres `NUMERIC`
+macro_rules! scaled {
+    ($(#[$meta:meta])* $name:ident, $dp:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+        #[serde(transparent)]
+        pub struct $name(Decimal);
+
+        impl $name {
+            /// The most decimal places kept
+            pub const DP: u32 = $dp;
+            /// Zero
+            pub const ZERO: Self = Self(Decimal::ZERO);
+            /// One
+            pub const ONE: Self = Self(Decimal::ONE);
+
+            /// Wraps `num` scaled down by `scale` decimal places, for
+            /// constants
+            ///
+            /// # Panics
+            ///
+            /// If `scale` is more than [`Self::DP`]
+            #[must_use]
+            pub const fn new(num: u32, scale: u32) -> Self {
+                assert!(scale <= Self::DP, "too many decimal places");
+                Self(Decimal::from_parts(num, 0, 0, false, scale))
+            }
+
+            /// Wraps a decimal, rounding it half to even to [`Self::DP`]
+            /// places, as results of division & multiplication may need
+            #[must_use]
+            pub fn round(value: Decimal) -> Self {
+                Self::round_with(value, RoundingStrategy::MidpointNearestEven)
+            }
+
+            /// Wraps a decimal, rounding it to [`Self::DP`] places with the
+            /// given strategy
+            #[must_use]
+            pub fn round_with(value: Decimal, strategy: RoundingStrategy) -> Self {
+                Self(value.round_dp_with_strategy(Self::DP, strategy))
+            }
+
+            /// The wrapped decimal
+            #[must_use]
+            pub const fn get(self) -> Decimal {
+                self.0
+            }
+
+            /// The absolute value
+            #[must_use]
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+        }
+
+        impl TryFrom<Decimal> for $name {
+            type Error = ScaleError;
+
+            fn try_from(value: Decimal) -> Result<Self, Self::Error> {
+                if value.scale() <= Self::DP {
+                    return Ok(Self(value));
+                }
+                let value = value.normalize();
+                if value.scale() > Self::DP {
+                    return Err(ScaleError {
+                        value,
+                        places: Self::DP,
+                    });
+                }
+                Ok(Self(value))
+            }
+        }
+
+        impl From<$name> for Decimal {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(value: u32) -> Self {
+                Self(Decimal::from(value))
+            }
+        }
+
+        impl Deref for $name {
+            type Target = Decimal;
+
+            fn deref(&self) -> &Decimal {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|value| value.0).sum())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <Decimal as Deserialize>::deserialize(deserializer)?;
+                Self::try_from(value).map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl Type<Postgres> for $name {
+            fn type_info() -> PgTypeInfo {
+                <Decimal as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <Decimal as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl PgHasArrayType for $name {
+            fn array_type_info() -> PgTypeInfo {
+                <Decimal as PgHasArrayType>::array_type_info()
+            }
+        }
+
+        impl Encode<'_, Postgres> for $name {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+                <Decimal as Encode<Postgres>>::encode_by_ref(&self.0, buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Postgres> for $name {
+            fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                let value = <Decimal as Decode<Postgres>>::decode(value)?;
+                Ok(Self::try_from(value)?)
+            }
+        }
+    };
+}
+
+scaled!(
+    /// An exact amount of money, or a price, to at most 16 decimal places
+    ///
+    /// That's fine enough for prices split down, though a [`Quantity`] can't
+    /// always multiply by one exactly, see [`Quantity::at`].
+    Money,
+    16
+);
+
+scaled!(
+    /// An exact quantity of a symbol, to at most 8 decimal places, as finely
+    /// as crypto trades
+    Quantity,
+    8
+);
+
+impl Quantity {
+    /// What the quantity costs at `price`, exactly
+    ///
+    /// `None` if that takes more than the 28 significant digits a decimal
+    /// keeps, as large quantities at finely split prices can, rather than
+    /// rounding it.
+    #[must_use]
+    pub fn at(self, price: Money) -> Option<Decimal> {
+        let cost = self.0.checked_mul(price.0)?;
+        (cost.scale() == self.0.scale() + price.0.scale()).then_some(cost)
+    }
+}
+
+impl Money {
+    /// Rounds an amount of `currency` to its minor unit, see [`minor_units`]
+    #[must_use]
+    pub fn round_minor(value: Decimal, currency: &str) -> Self {
+        Self::round_minor_with(value, currency, RoundingStrategy::MidpointNearestEven)
+    }
+
+    /// Rounds an amount of `currency` to its minor unit, with the given
+    /// strategy
+    #[must_use]
+    pub fn round_minor_with(value: Decimal, currency: &str, strategy: RoundingStrategy) -> Self {
+        Self(value.round_dp_with_strategy(minor_units(currency), strategy))
+    }
+}
+
+/// The decimal places of a currency's minor unit, as ISO 4217 has them
+///
+/// Most currencies have cents, but some, like `JPY`, have none, and a few,
+/// like `KWD`, have thousandths.
+#[must_use]
+pub fn minor_units(currency: &str) -> u32 {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::{value, IntoDeserializer};
+
+    use super::*;
+
+    #[test]
+    fn decimals_within_scale_are_kept() {
+        let quantity = Quantity::try_from(Decimal::new(123_456_789, 8)).unwrap();
+        assert_eq!(quantity.get(), Decimal::new(123_456_789, 8));
+        assert_eq!(quantity.scale(), 8);
+    }
+
+    #[test]
+    fn trailing_zeros_dont_count_against_scale() {
+        let padded = Decimal::new(1_500_000_000, 9);
+        assert_eq!(Quantity::try_from(padded), Ok(Quantity::new(15, 1)));
+    }
+
+    #[test]
+    fn decimals_past_scale_are_refused() {
+        let fine = Decimal::new(1, 9);
+        assert_eq!(
+            Quantity::try_from(fine),
+            Err(ScaleError {
+                value: fine,
+                places: Quantity::DP,
+            })
+        );
+        assert!(Money::try_from(fine).is_ok());
+        assert!(Money::try_from(Decimal::new(1, 17)).is_err());
+    }
+
+    #[test]
+    fn rounding_is_half_to_even() {
+        assert_eq!(Quantity::round(Decimal::new(25, 9)), Quantity::new(2, 8));
+        assert_eq!(Quantity::round(Decimal::new(35, 9)), Quantity::new(4, 8));
+    }
+
+    #[test]
+    fn deserializing_enforces_scale() {
+        fn parse<T: for<'de> Deserialize<'de>>(value: &str) -> Result<T, value::Error> {
+            T::deserialize(value.into_deserializer())
+        }
+        assert_eq!(parse::<Money>("12.34"), Ok(Money::new(1234, 2)));
+        assert!(parse::<Quantity>("0.000000001").is_err());
+    }
+
+    #[test]
+    fn quantities_cost_exactly() {
+        let quantity = Quantity::new(3, 8);
+        let price = Money::new(7, 16);
+        assert_eq!(quantity.at(price), Some(Decimal::new(21, 24)));
+    }
+
+    #[test]
+    fn costs_too_fine_to_keep_are_refused() {
+        let quantity = Quantity::try_from(Decimal::new(12_345_678_912_345, 8)).unwrap();
+        let price = Decimal::from_i128_with_scale(12_340_123_456_789_012_345, 16);
+
+        assert_eq!(quantity.at(Money::try_from(price).unwrap()), None);
+        let whole = Quantity::try_from(Decimal::from(1_000_000_000)).unwrap();
+        assert_eq!(
+            whole.at(Money::new(1234, 2)),
+            Some(Decimal::from(12_340_000_000_u64))
+        );
+    }
+
+    #[test]
+    fn amounts_round_to_their_currencys_minor_unit() {
+        let amount = Decimal::new(12_345_678, 4);
+
+        assert_eq!(Money::round_minor(amount, "USD"), Money::new(123_457, 2));
+        assert_eq!(Money::round_minor(amount, "JPY"), Money::from(1235));
+        assert_eq!(Money::round_minor(amount, "KWD"), Money::new(1_234_568, 3));
+        assert_eq!(
+            Money::round_minor_with(amount, "USD", RoundingStrategy::ToZero),
+            Money::new(123_456, 2)
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use std::{f64::consts::TAU, sync::Arc};
+
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use time::{Date, Month, OffsetDateTime, Weekday};
+use tracing::{info, warn};
+
+use crate::{
+    calendar::Calendar,
+    market::{self, MarketData},
+    models::{AssetClass, Instrument, OptionType},
+    numeric::{Money, Quantity},
+    state::persist::Database,
+};
+
+/// The shares of its underlying an equity option contract is for
+pub const CONTRACT_MULTIPLIER: Quantity = Quantity::new(100, 0);
+/// The strikes a chain lists either side of the one nearest its underlying's
+/// price
+pub const STRIKES_EACH_SIDE: i64 = 10;
+/// The weekly expiries a chain lists, on the coming Fridays
+const WEEKLY_EXPIRIES: usize = 4;
+/// The monthly expiries a chain lists, on the third Friday of the coming
+/// months
+const MONTHLY_EXPIRIES: usize = 3;
+/// The length of an OCC symbol past its underlying, `YYMMDD`, `C` or `P` &
+/// eight digits of strike
+const OCC_SUFFIX: usize = 15;
+/// The highest strike an OCC symbol's eight digits of thousandths can spell
+pub const MAX_STRIKE: Decimal = Decimal::from_parts(99_999_999, 0, 0, false, 3);
+
+/// What tells an option contract apart, as its OCC symbol spells out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractTerms {
+    /// The symbol the contract is on
+    pub underlying: String,
+    /// The last date the contract trades
+    pub expires_on: Date,
+    /// Whether the contract is a call or a put
+    pub option_type: OptionType,
+    /// The price the underlying is bought or sold at, if it's exercised
+    pub strike: Decimal,
+}
+
+impl ContractTerms {
+    /// Parses an OCC symbol, the underlying followed by the expiry as
+    /// `YYMMDD`, `C` or `P`, and the strike in thousandths as eight digits,
+    /// like `AAPL261120C00150000`
+    ///
+    /// Returns `None` if the symbol isn't one.
+    #[must_use]
+    pub fn parse(symbol: &str) -> Option<Self> {
+        if !symbol.is_ascii() || symbol.len() <= OCC_SUFFIX {
+            return None;
+        }
+        let (underlying, terms) = symbol.split_at(symbol.len() - OCC_SUFFIX);
+        let number = |from: usize, to: usize| {
+            let digits = &terms[from..to];
+            digits
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| digits.parse::<u32>().ok())
+                .flatten()
+        };
+        let expires_on = Date::from_calendar_date(
+            2000 + i32::try_from(number(0, 2)?).ok()?,
+            Month::try_from(u8::try_from(number(2, 4)?).ok()?).ok()?,
+            u8::try_from(number(4, 6)?).ok()?,
+        )
+        .ok()?;
+        let option_type = match &terms[6..7] {
+            "C" => OptionType::Call,
+            "P" => OptionType::Put,
+            _ => return None,
+        };
+        let strike = Decimal::new(i64::from(number(7, OCC_SUFFIX)?), 3).normalize();
+        (!strike.is_zero()).then(|| Self {
+            underlying: underlying.to_owned(),
+            expires_on,
+            option_type,
+            strike,
+        })
+    }
+
+    /// The contract's OCC symbol
+    ///
+    /// Strikes are written to the thousandth, so finer ones are truncated,
+    /// and must be at most [`MAX_STRIKE`], as [`strikes`] lists them, to
+    /// parse back.
+    #[must_use]
+    pub fn symbol(&self) -> String {
+        let strike = (self.strike * Decimal::ONE_THOUSAND)
+            .trunc()
+            .to_u64()
+            .unwrap_or_default();
+        format!(
+            "{}{:02}{:02}{:02}{}{strike:08}",
+            self.underlying,
+            self.expires_on.year() % 100,
+            u8::from(self.expires_on.month()),
+            self.expires_on.day(),
+            match self.option_type {
+                OptionType::Call => 'C',
+                OptionType::Put => 'P',
+            },
+        )
+    }
+
+    /// When the contract expires, at the end of its expiry date in UTC
+    #[must_use]
+    pub fn expires_at(&self) -> OffsetDateTime {
+        self.expires_on
+            .next_day()
+            .unwrap_or(self.expires_on)
+            .midnight()
+            .assume_utc()
+    }
+
+    /// The contract as it's listed in the registry
+    ///
+    /// Contracts trade on their underlying's exchange, in its currency, in
+    /// whole contracts of [`CONTRACT_MULTIPLIER`] shares priced to the cent,
+    /// and may always be written.
+    #[must_use]
+    pub fn listing(&self, underlying: &Instrument) -> Instrument {
+        let kind = match self.option_type {
+            OptionType::Call => "Call",
+            OptionType::Put => "Put",
+        };
+        Instrument {
+            symbol: self.symbol(),
+            name: format!(
+                "{} {} {} {kind}",
+                underlying.symbol, self.expires_on, self.strike
+            ),
+            exchange: underlying.exchange.clone(),
+            asset_class: AssetClass::Option,
+            currency: underlying.currency.clone(),
+            tick_size: Money::new(1, 2),
+            lot_size: Quantity::ONE,
+            fractional: false,
+            shortable: true,
+            active: true,
+            multiplier: CONTRACT_MULTIPLIER,
+            underlying: Some(underlying.symbol.clone()),
+            option_type: Some(self.option_type),
+            strike: Some(Money::round(self.strike)),
+            expires_on: Some(self.expires_on),
+            initial_margin: None,
+            maintenance_margin: None,
+            roll_on: None,
+            settled_on: None,
+        }
+    }
+}
+
+/// The Black-Scholes price of a European option on an underlying at `spot`,
+/// `years` from expiry, at the given annual volatility & risk-free rate
+///
+/// Expired contracts, or those with no volatility, are worth what they'd
+/// exercise for.
+#[must_use]
+pub fn black_scholes(
+    option_type: OptionType,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    volatility: f64,
+    rate: f64,
+) -> f64 {
+    if years <= 0.0 || volatility <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        return match option_type {
+            OptionType::Call => (spot - strike).max(0.0),
+            OptionType::Put => (strike - spot).max(0.0),
+        };
+    }
+    let deviation = volatility * years.sqrt();
+    let d1 = volatility
+        .mul_add(volatility / 2.0, rate)
+        .mul_add(years, (spot / strike).ln())
+        / deviation;
+    let d2 = d1 - deviation;
+    let discounted = strike * (-rate * years).exp();
+    match option_type {
+        OptionType::Call => spot.mul_add(normal_cdf(d1), -discounted * normal_cdf(d2)),
+        OptionType::Put => discounted.mul_add(normal_cdf(-d2), -spot * normal_cdf(-d1)),
+    }
+    .max(0.0)
+}
+
+/// The standard normal distribution's cumulative probability at `x`
+///
+/// Uses Abramowitz & Stegun's 26.2.17, good to 7.5e-8.
+fn normal_cdf(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 5] = [
+        1.330_274_429,
+        -1.821_255_978,
+        1.781_477_937,
+        -0.356_563_782,
+        0.319_381_530,
+    ];
+    let t = 1.0 / 0.231_641_9_f64.mul_add(x.abs(), 1.0);
+    let density = (-x * x / 2.0).exp() / TAU.sqrt();
+    let tail = density
+        * t
+        * COEFFICIENTS
+            .iter()
+            .fold(0.0_f64, |sum, c| sum.mul_add(t, *c));
+    if x < 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
+}
+
+/// The expiries a chain lists as of `today`, soonest first
+///
+/// These are the coming [`WEEKLY_EXPIRIES`] Fridays, today's included, and
+/// the third Friday of the coming [`MONTHLY_EXPIRIES`] months, this one's
+/// included if it's still to come.
+#[must_use]
+pub fn expiries(today: Date) -> Vec<Date> {
+    let mut friday = if today.weekday() == Weekday::Friday {
+        today
+    } else {
+        today.next_occurrence(Weekday::Friday)
+    };
+    let mut expiries = Vec::new();
+    for _ in 0..WEEKLY_EXPIRIES {
+        expiries.push(friday);
+        friday = friday.next_occurrence(Weekday::Friday);
+    }
+    let (mut year, mut month) = (today.year(), today.month());
+    let mut monthly = 0;
+    while monthly < MONTHLY_EXPIRIES {
+        if let Some(third) = third_friday(year, month).filter(|third| *third >= today) {
+            expiries.push(third);
+            monthly += 1;
+        }
+        if month == Month::December {
+            year += 1;
+        }
+        month = month.next();
+    }
+    expiries.sort_unstable();
+    expiries.dedup();
+    expiries
+}
+
+/// The third Friday of a month, when monthly contracts expire
+fn third_friday(year: i32, month: Month) -> Option<Date> {
+    let first = Date::from_calendar_date(year, month, 1).ok()?;
+    let friday = if first.weekday() == Weekday::Friday {
+        first
+    } else {
+        first.next_occurrence(Weekday::Friday)
+    };
+    friday.checked_add(time::Duration::weeks(2))
+}
+
+/// The strikes a chain lists for an underlying at `price`, lowest first
+///
+/// Strikes are spaced by a step that grows with the price,
+/// [`STRIKES_EACH_SIDE`] either side of the one nearest it, and are always
+/// positive & at most [`MAX_STRIKE`], so their contracts' symbols parse.
+#[must_use]
+pub fn strikes(price: Decimal) -> Vec<Decimal> {
+    let step = if price < Decimal::from(25) {
+        Decimal::ONE
+    } else if price < Decimal::ONE_HUNDRED {
+        Decimal::new(25, 1)
+    } else if price < Decimal::from(250) {
+        Decimal::from(5)
+    } else {
+        Decimal::TEN
+    };
+    let nearest = (price / step).round() * step;
+    (-STRIKES_EACH_SIDE..=STRIKES_EACH_SIDE)
+        .map(|i| (nearest + Decimal::from(i) * step).normalize())
+        .filter(|strike| *strike > Decimal::ZERO && *strike <= MAX_STRIKE)
+        .collect()
+}
+
+/// Settles option contracts as they expire
+#[derive(Debug)]
+pub struct OptionExpirer {
+    database: Database,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
+}
+
+impl OptionExpirer {
+    /// Creates a new expirer, settling contracts at their underlying's close
+    /// in the given market, as its exchange closes on their expiry date
+    #[must_use]
+    pub fn new(database: Database, market: Arc<dyn MarketData>, calendar: Arc<Calendar>) -> Self {
+        Self {
+            database,
+            market,
+            calendar,
+        }
+    }
+
+    /// Runs a single pass over every contract that's expired
+    ///
+    /// Contracts expire at the end of their expiry date, in UTC, and are
+    /// settled once it's past at their underlying's close that date, however
+    /// long after, see [`market::close_as_of`]. Those in the money are
+    /// exercised & assigned, and the rest expire worthless, see
+    /// [`Database::expire_contract`]. Contracts whose underlying has no price
+    /// then wait until it does.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        let today = OffsetDateTime::now_utc().date();
+        for contract in self.database.get_expired_contracts(today).await? {
+            let (Some(underlying), Some(expires_on)) =
+                (contract.underlying.as_deref(), contract.expires_on)
+            else {
+                continue;
+            };
+            let close = self.calendar.close_on(&contract, expires_on);
+            let price = match market::close_as_of(self.market.as_ref(), underlying, close).await {
+                Ok(Some(price)) => price,
+                Ok(None) => {
+                    warn!(
+                        symbol = contract.symbol,
+                        "underlying has no price at expiry, can't settle"
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    warn!(symbol = underlying, "failed to quote: {e}");
+                    continue;
+                }
+            };
+            if let Some(positions) = self.database.expire_contract(&contract, price).await? {
+                let exercised = contract.intrinsic_value(price) > Decimal::ZERO;
+                info!(
+                    symbol = contract.symbol,
+                    %price,
+                    exercised,
+                    positions,
+                    "contract expired"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+
+    /// Whether two prices agree to within a hundredth of a cent
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn normal_cdf_matches_known_values() {
+        assert!(close(normal_cdf(0.0), 0.5));
+        assert!(close(normal_cdf(1.96), 0.975_002));
+        assert!(close(normal_cdf(-1.0), 0.158_655));
+        assert!(close(normal_cdf(1.0) + normal_cdf(-1.0), 1.0));
+    }
+
+    #[test]
+    fn black_scholes_matches_known_prices() {
+        let call = black_scholes(OptionType::Call, 100.0, 100.0, 1.0, 0.2, 0.05);
+        let put = black_scholes(OptionType::Put, 100.0, 100.0, 1.0, 0.2, 0.05);
+
+        assert!((call - 10.4506).abs() < 1e-3);
+        assert!((put - 5.5735).abs() < 1e-3);
+        // put-call parity
+        let discounted = 100.0 * (-0.05_f64).exp();
+        assert!(close(call - put, 100.0 - discounted));
+    }
+
+    #[test]
+    fn expired_contracts_are_worth_what_they_exercise_for() {
+        assert!(close(
+            black_scholes(OptionType::Call, 110.0, 100.0, 0.0, 0.2, 0.05),
+            10.0
+        ));
+        assert!(close(
+            black_scholes(OptionType::Put, 110.0, 100.0, 0.0, 0.2, 0.05),
+            0.0
+        ));
+        assert!(close(
+            black_scholes(OptionType::Put, 90.0, 100.0, 1.0, 0.0, 0.05),
+            10.0
+        ));
+    }
+
+    #[test]
+    fn occ_symbols_round_trip() {
+        let terms = ContractTerms::parse("AAPL261120C00150000").unwrap();
+        assert_eq!(
+            terms,
+            ContractTerms {
+                underlying: "AAPL".to_owned(),
+                expires_on: date!(2026 - 11 - 20),
+                option_type: OptionType::Call,
+                strike: Decimal::from(150),
+            }
+        );
+        assert_eq!(terms.symbol(), "AAPL261120C00150000");
+
+        let put = ContractTerms {
+            option_type: OptionType::Put,
+            strike: MAX_STRIKE,
+            ..terms
+        };
+        assert_eq!(ContractTerms::parse(&put.symbol()), Some(put));
+    }
+
+    #[test]
+    fn malformed_symbols_dont_parse() {
+        for symbol in [
+            "AAPL",
+            "261120C00150000",
+            "AAPL261120X00150000",
+            "AAPL261320C00150000",
+            "AAPL261120C0015000a",
+            "AAPL261120C00000000",
+        ] {
+            assert_eq!(ContractTerms::parse(symbol), None, "{symbol}");
+        }
+    }
+
+    #[test]
+    fn strikes_stay_within_what_symbols_spell() {
+        let listed = strikes(Decimal::from(99_995));
+        assert!(!listed.is_empty());
+        assert!(listed.iter().all(|strike| *strike <= MAX_STRIKE));
+        assert!(strikes(Decimal::from(1_000_000)).is_empty());
+    }
+}
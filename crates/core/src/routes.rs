@@ -0,0 +1,472 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use time::{format_description::well_known::Iso8601, Date};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthSession,
+    models::{AccountSettings, NewConversion, NewOrder, NewOrderGroup, PositionSettings},
+    state::{
+        CancelOrderAction, CancelOrderGroupAction, ConvertAction, OptionChainAction,
+        PlaceOrderAction, PlaceOrderGroupAction, UpdateAccountAction,
+    },
+    Api,
+};
+
+/// Selects one of the user's accounts, defaulting to their first
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct AccountQuery {
+    /// The account's uuid
+    pub account: Option<Uuid>,
+}
+
+/// Filters by symbol, if one's given
+#[derive(Debug, Clone, Deserialize)]
+pub struct SymbolQuery {
+    /// The symbol to filter by
+    pub symbol: Option<String>,
+}
+
+/// Searches for symbols
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstrumentQuery {
+    /// What the symbols start with, or are named with
+    pub query: Option<String>,
+}
+
+/// Picks an option chain's expiry, defaulting to its soonest
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainQuery {
+    /// The expiry, as `YYYY-MM-DD`
+    pub expiry: Option<String>,
+}
+
+/// The body of an order placement
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaceOrder {
+    /// The account's uuid
+    pub account: Option<Uuid>,
+    /// The order itself
+    #[serde(flatten)]
+    pub order: NewOrder,
+}
+
+pub async fn place_order(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Json(body): Json<PlaceOrder>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    api.place_order(user.id, body.account, body.order)
+        .await
+        .map_or_else(
+            |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            placed_order,
+        )
+}
+
+/// The response to placing an order
+fn placed_order(action: PlaceOrderAction) -> Response {
+    use PlaceOrderAction::*;
+
+    match action {
+        Placed(order) => (StatusCode::CREATED, Json(order)).into_response(),
+        NoAccount => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        InvalidOrder => (StatusCode::BAD_REQUEST, "invalid order").into_response(),
+        UnknownSymbol => (StatusCode::NOT_FOUND, "unknown symbol").into_response(),
+        Delisted => (StatusCode::UNPROCESSABLE_ENTITY, "symbol is delisted").into_response(),
+        OffIncrement => (
+            StatusCode::BAD_REQUEST,
+            "quantity or price off the symbol's increments",
+        )
+            .into_response(),
+        NoQuote => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "no market data for symbol",
+        )
+            .into_response(),
+        InsufficientBuyingPower => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "insufficient buying power",
+        )
+            .into_response(),
+        InsufficientHoldings => {
+            (StatusCode::UNPROCESSABLE_ENTITY, "insufficient holdings").into_response()
+        }
+        MarketClosed => (StatusCode::UNPROCESSABLE_ENTITY, "market is closed").into_response(),
+    }
+}
+
+pub async fn list_orders(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_orders(user.id, query.account).await {
+        Ok(Some(orders)) => Json(orders).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn cancel_order(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Path(uuid): Path<Uuid>,
+) -> Response {
+    use CancelOrderAction::*;
+
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.cancel_order(user.id, uuid).await {
+        Ok(Cancelled(order)) => Json(order).into_response(),
+        Ok(NotFound) => (StatusCode::NOT_FOUND, "order not found").into_response(),
+        Ok(NotOpen(_)) => (StatusCode::CONFLICT, "order is not open").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// The body of an order group placement
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaceOrderGroup {
+    /// The account's uuid
+    pub account: Option<Uuid>,
+    /// The group itself
+    #[serde(flatten)]
+    pub group: NewOrderGroup,
+}
+
+pub async fn place_order_group(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Json(body): Json<PlaceOrderGroup>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api
+        .place_order_group(user.id, body.account, body.group)
+        .await
+    {
+        Ok(PlaceOrderGroupAction::Placed(group)) => {
+            (StatusCode::CREATED, Json(group)).into_response()
+        }
+        Ok(PlaceOrderGroupAction::Refused(action)) => placed_order(action),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_order_groups(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_order_groups(user.id, query.account).await {
+        Ok(Some(groups)) => Json(groups).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn cancel_order_group(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Path(uuid): Path<Uuid>,
+) -> Response {
+    use CancelOrderGroupAction::*;
+
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.cancel_order_group(user.id, uuid).await {
+        Ok(Cancelled(orders)) => Json(orders).into_response(),
+        Ok(NotFound) => (StatusCode::NOT_FOUND, "order group not found").into_response(),
+        Ok(NotOpen) => (StatusCode::CONFLICT, "order group is not open").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_accounts(auth: AuthSession, State(api): State<Api>) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    api.get_accounts(user.id).await.map_or_else(
+        |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        |accounts| Json(accounts).into_response(),
+    )
+}
+
+pub async fn update_account(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Path(uuid): Path<Uuid>,
+    Json(settings): Json<AccountSettings>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.update_account_settings(user.id, uuid, &settings).await {
+        Ok(UpdateAccountAction::Updated(account)) => Json(account).into_response(),
+        Ok(UpdateAccountAction::NotFound) => {
+            (StatusCode::NOT_FOUND, "account not found").into_response()
+        }
+        Ok(UpdateAccountAction::UnknownFeeSchedule) => {
+            (StatusCode::BAD_REQUEST, "unknown fee schedule").into_response()
+        }
+        Ok(UpdateAccountAction::InvalidMargin) => {
+            (StatusCode::BAD_REQUEST, "invalid margin settings").into_response()
+        }
+        Ok(UpdateAccountAction::MarginInUse) => {
+            (StatusCode::CONFLICT, "account is borrowing on margin").into_response()
+        }
+        Ok(UpdateAccountAction::UnknownCurrency) => {
+            (StatusCode::BAD_REQUEST, "unknown currency").into_response()
+        }
+        Ok(UpdateAccountAction::InvalidSpread) => {
+            (StatusCode::BAD_REQUEST, "invalid exchange spread").into_response()
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_fee_schedules(auth: AuthSession, State(api): State<Api>) -> Response {
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    api.get_fee_schedules().await.map_or_else(
+        |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        |schedules| Json(schedules).into_response(),
+    )
+}
+
+pub async fn list_borrow_rates(auth: AuthSession, State(api): State<Api>) -> Response {
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    api.get_borrow_rates().await.map_or_else(
+        |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        |rates| Json(rates).into_response(),
+    )
+}
+
+pub async fn list_interest_rates(auth: AuthSession, State(api): State<Api>) -> Response {
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    api.get_interest_rates().await.map_or_else(
+        |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        |rates| Json(rates).into_response(),
+    )
+}
+
+pub async fn search_instruments(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<InstrumentQuery>,
+) -> Response {
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    api.search_instruments(query.query.as_deref())
+        .await
+        .map_or_else(
+            |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            |instruments| Json(instruments).into_response(),
+        )
+}
+
+pub async fn list_corporate_actions(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<SymbolQuery>,
+) -> Response {
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    api.get_corporate_actions(query.symbol.as_deref())
+        .await
+        .map_or_else(
+            |_| StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            |actions| Json(actions).into_response(),
+        )
+}
+
+pub async fn list_positions(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_positions(user.id, query.account).await {
+        Ok(Some(positions)) => Json(positions).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn update_position(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Path(symbol): Path<String>,
+    Query(query): Query<AccountQuery>,
+    Json(settings): Json<PositionSettings>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api
+        .update_position_settings(user.id, query.account, &symbol, &settings)
+        .await
+    {
+        Ok(Some(position)) => Json(position).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn get_margin(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_margin(user.id, query.account).await {
+        Ok(Some(margin)) => Json(margin).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_cash_balances(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_cash_balances(user.id, query.account).await {
+        Ok(Some(balances)) => Json(balances).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// The body of a currency conversion
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConvertCurrency {
+    /// The account's uuid
+    pub account: Option<Uuid>,
+    /// The conversion itself
+    #[serde(flatten)]
+    pub conversion: NewConversion,
+}
+
+pub async fn convert_currency(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Json(body): Json<ConvertCurrency>,
+) -> Response {
+    use ConvertAction::*;
+
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api
+        .convert_currency(user.id, body.account, body.conversion)
+        .await
+    {
+        Ok(Converted(conversion)) => (StatusCode::CREATED, Json(conversion)).into_response(),
+        Ok(NoAccount) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Ok(InvalidConversion) => (StatusCode::BAD_REQUEST, "invalid conversion").into_response(),
+        Ok(NoRate) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "no exchange rate for currencies",
+        )
+            .into_response(),
+        Ok(InsufficientCash) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, "insufficient cash").into_response()
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_conversions(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_conversions(user.id, query.account).await {
+        Ok(Some(conversions)) => Json(conversions).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn list_interest_accruals(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Query(query): Query<AccountQuery>,
+) -> Response {
+    let Some(user) = auth.user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    match api.get_interest_accruals(user.id, query.account).await {
+        Ok(Some(accruals)) => Json(accruals).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "account not found").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn get_option_chain(
+    auth: AuthSession,
+    State(api): State<Api>,
+    Path(symbol): Path<String>,
+    Query(query): Query<ChainQuery>,
+) -> Response {
+    use OptionChainAction::*;
+
+    if auth.user.is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Ok(expiry) = query
+        .expiry
+        .map(|expiry| Date::parse(&expiry, &Iso8601::DATE))
+        .transpose()
+    else {
+        return (StatusCode::BAD_REQUEST, "invalid expiry").into_response();
+    };
+    match api.get_option_chain(&symbol, expiry).await {
+        Ok(Chain(chain)) => Json(chain).into_response(),
+        Ok(UnknownSymbol) => (StatusCode::NOT_FOUND, "unknown symbol").into_response(),
+        Ok(Delisted) => (StatusCode::UNPROCESSABLE_ENTITY, "symbol is delisted").into_response(),
+        Ok(NoQuote) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "no market data for symbol",
+        )
+            .into_response(),
+        Ok(InvalidExpiry) => (StatusCode::BAD_REQUEST, "invalid expiry").into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
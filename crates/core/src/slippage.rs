@@ -0,0 +1,147 @@
+use std::{fmt::Debug, sync::Arc};
+
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+use crate::{
+    market::Quote,
+    models::{OrderSide, SlippageKind},
+};
+
+/// The basis points [`Fixed`] slips by, unless configured
+pub const DEFAULT_FIXED_BPS: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+/// The basis points [`Volume`] slips by at full participation, unless
+/// configured
+pub const DEFAULT_VOLUME_BPS: Decimal = Decimal::from_parts(100, 0, 0, false, 0);
+
+/// The market an order executes against
+#[derive(Debug, Clone, Copy)]
+pub struct Execution<'a> {
+    /// The symbol's latest quote
+    pub quote: &'a Quote,
+    /// The symbol's volume over the last day, if known
+    pub volume: Option<Decimal>,
+}
+
+/// How far fills slip from the market price
+pub trait SlippageModel: Send + Sync + Debug {
+    /// The price an order for `quantity` executes at, before it's rounded to
+    /// the symbol's tick
+    fn price(&self, side: OrderSide, quantity: Decimal, execution: &Execution) -> Decimal;
+
+    /// Whether the model needs the market's volume to price an execution
+    fn uses_volume(&self) -> bool {
+        false
+    }
+}
+
+/// Fills at the last price, without slipping
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Last;
+
+impl SlippageModel for Last {
+    fn price(&self, _side: OrderSide, _quantity: Decimal, execution: &Execution) -> Decimal {
+        execution.quote.last
+    }
+}
+
+/// Fills a fixed number of basis points worse than the last price
+#[derive(Debug, Clone, Copy)]
+pub struct Fixed {
+    /// How far fills slip, in basis points
+    pub bps: Decimal,
+}
+
+impl Default for Fixed {
+    fn default() -> Self {
+        Self {
+            bps: DEFAULT_FIXED_BPS,
+        }
+    }
+}
+
+impl SlippageModel for Fixed {
+    fn price(&self, side: OrderSide, _quantity: Decimal, execution: &Execution) -> Decimal {
+        slip(side, execution.quote.last, self.bps)
+    }
+}
+
+/// Buys at the ask & sells at the bid
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Spread;
+
+impl SlippageModel for Spread {
+    fn price(&self, side: OrderSide, _quantity: Decimal, execution: &Execution) -> Decimal {
+        match side {
+            OrderSide::Buy => execution.quote.ask,
+            OrderSide::Sell => execution.quote.bid,
+        }
+    }
+}
+
+/// Slips by the square root of the order's participation in the market's
+/// volume, from the far side of the spread
+///
+/// An order for the whole day's volume slips by `bps`, one for a hundredth of
+/// it by a tenth of that. Without volume, orders fill at the far side of the
+/// spread.
+#[derive(Debug, Clone, Copy)]
+pub struct Volume {
+    /// How far an order for the whole day's volume slips, in basis points
+    pub bps: Decimal,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            bps: DEFAULT_VOLUME_BPS,
+        }
+    }
+}
+
+impl SlippageModel for Volume {
+    fn price(&self, side: OrderSide, quantity: Decimal, execution: &Execution) -> Decimal {
+        let price = Spread.price(side, quantity, execution);
+        let participation = execution
+            .volume
+            .filter(|volume| volume.is_sign_positive() && !volume.is_zero())
+            .and_then(|volume| (quantity / volume).to_f64())
+            .unwrap_or_default();
+        let impact = Decimal::from_f64(participation.sqrt()).unwrap_or_default();
+        slip(side, price, self.bps * impact)
+    }
+
+    fn uses_volume(&self) -> bool {
+        true
+    }
+}
+
+/// The slippage model an account's settings pick
+///
+/// Returns `None` for [`SlippageKind::Router`], leaving the choice to the
+/// router's model.
+#[must_use]
+pub fn model(kind: SlippageKind, bps: Option<Decimal>) -> Option<Arc<dyn SlippageModel>> {
+    Some(match kind {
+        SlippageKind::Router => return None,
+        SlippageKind::Last => Arc::new(Last),
+        SlippageKind::Fixed => Arc::new(Fixed {
+            bps: bps.unwrap_or(DEFAULT_FIXED_BPS),
+        }),
+        SlippageKind::Spread => Arc::new(Spread),
+        SlippageKind::Volume => Arc::new(Volume {
+            bps: bps.unwrap_or(DEFAULT_VOLUME_BPS),
+        }),
+    })
+}
+
+/// Moves a price `bps` basis points against the trader, never below zero
+fn slip(side: OrderSide, price: Decimal, bps: Decimal) -> Decimal {
+    let offset = price * bps / Decimal::from(10_000);
+    match side {
+        OrderSide::Buy => price + offset,
+        OrderSide::Sell => (price - offset).max(Decimal::ZERO),
+    }
+}
@@ -1,14 +1,45 @@
 #![allow(clippy::single_match_else)]
 
+use std::sync::Arc;
+
 use derivative::Derivative;
 use password_auth::generate_hash;
 use sqlx::PgPool;
 
-use crate::models::User;
+use crate::{calendar::Calendar, market::MarketData, models::User, numeric::Money};
 use persist::{error::ConnectionError, Database};
 
+/// Accounts & their cash
+mod accounts;
+/// Cash in other currencies, converting it, & the interest it earns
+mod conversions;
+/// Placing & cancelling groups of orders
+mod groups;
+/// Option chains
+mod options;
+/// Placing & cancelling orders
+mod orders;
 /// Handles persist
 pub mod persist;
+/// Valuing positions
+mod positions;
+
+pub use accounts::UpdateAccountAction;
+pub use conversions::{CashBalanceReport, ConvertAction};
+pub use groups::{CancelOrderGroupAction, OrderGroupReport, PlaceOrderGroupAction};
+pub use options::{OptionChain, OptionChainAction, OptionQuote};
+pub use orders::{CancelOrderAction, PlaceOrderAction};
+pub use positions::{PositionReport, INSTRUMENT_SEARCH_LIMIT};
+
+/// The name of the account opened for every new user
+pub const DEFAULT_ACCOUNT_NAME: &str = "Default";
+/// The cash a new account opens with
+pub const DEFAULT_STARTING_CASH: Money = Money::new(100_000, 0);
+/// The fees a new account pays, commission free with regulatory fees
+pub const DEFAULT_FEE_SCHEDULE: &str = "retail";
+/// The currency accounts are kept in, and that symbols which aren't listed
+/// trade in
+pub const DEFAULT_CURRENCY: &str = "USD";
 
 /// The central state
 #[derive(Derivative)]
@@ -16,6 +47,8 @@ pub mod persist;
 pub struct Context {
     database: Database,
     rules: ValidationRules,
+    market: Arc<dyn MarketData>,
+    calendar: Arc<Calendar>,
 }
 
 // TODO: eventually and email sign up using https://docs.rs/lettre/latest/lettre/
@@ -26,14 +59,24 @@ impl Context {
     /// # Errors
     ///
     /// Fails when [`Database::new`] does.
-    pub async fn new(pool: PgPool, rules: ValidationRules) -> Result<Self, ConnectionError> {
+    pub async fn new(
+        pool: PgPool,
+        rules: ValidationRules,
+        market: Arc<dyn MarketData>,
+        calendar: Arc<Calendar>,
+    ) -> Result<Self, ConnectionError> {
         Ok(Self {
             database: Database::new(pool).await?,
             rules,
+            market,
+            calendar,
         })
     }
     /// Try signing up a user
     ///
+    /// A [`DEFAULT_ACCOUNT_NAME`] account is opened for them, so they can
+    /// start trading right away.
+    ///
     /// # Errors
     ///
     /// See [`sqlx`]
@@ -45,7 +88,13 @@ impl Context {
         match self.rules.validate(username, password) {
             Validated::Valid if self.database.get_user(username).await?.is_none() => self
                 .database
-                .add_user(username, &generate_hash(password))
+                .add_user(
+                    username,
+                    &generate_hash(password),
+                    DEFAULT_ACCOUNT_NAME,
+                    DEFAULT_STARTING_CASH,
+                    DEFAULT_FEE_SCHEDULE,
+                )
                 .await
                 .map(AddUserAction::Added),
             Validated::Valid | Validated::InvalidName => Ok(AddUserAction::InvalidName),
@@ -0,0 +1,240 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::{Context, DEFAULT_FEE_SCHEDULE, DEFAULT_STARTING_CASH};
+use crate::{
+    margin::{self, MarginReport},
+    models::{is_currency, Account, AccountSettings, AccountType, CashEntry, FeeSchedule},
+    numeric::{Money, Quantity},
+};
+
+impl Context {
+    /// Open a new account for a user, holding [`DEFAULT_STARTING_CASH`] and
+    /// paying the [`DEFAULT_FEE_SCHEDULE`]
+    ///
+    /// Returns `None` if the user already has an account with this name.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn open_account(
+        &self,
+        user_id: i32,
+        name: &str,
+    ) -> Result<Option<Account>, sqlx::Error> {
+        if self
+            .database
+            .get_accounts(user_id)
+            .await?
+            .iter()
+            .any(|account| account.name == name)
+        {
+            return Ok(None);
+        }
+        self.database
+            .add_account(user_id, name, DEFAULT_STARTING_CASH, DEFAULT_FEE_SCHEDULE)
+            .await
+            .map(Some)
+    }
+
+    /// Get all of a user's accounts, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_accounts(&self, user_id: i32) -> Result<Vec<Account>, sqlx::Error> {
+        self.database.get_accounts(user_id).await
+    }
+
+    /// Get one of a user's accounts
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_account(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<Account>, sqlx::Error> {
+        self.database.get_account(user_id, uuid).await
+    }
+
+    /// Get one of a user's accounts, or their default account if `uuid` is
+    /// `None`
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_account_or_default(
+        &self,
+        user_id: i32,
+        uuid: Option<Uuid>,
+    ) -> Result<Option<Account>, sqlx::Error> {
+        match uuid {
+            Some(uuid) => self.database.get_account(user_id, uuid).await,
+            None => Ok(self
+                .database
+                .get_accounts(user_id)
+                .await?
+                .into_iter()
+                .next()),
+        }
+    }
+
+    /// Get the cash history of one of a user's accounts
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_cash_entries(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<Vec<CashEntry>>, sqlx::Error> {
+        match self.database.get_account(user_id, uuid).await? {
+            Some(account) => self.database.get_cash_entries(account.id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Change the settings of one of a user's accounts
+    ///
+    /// Margins must be percentages, the maintenance margin no more than the
+    /// initial. Accounts can only become cash accounts once they've repaid
+    /// what they've borrowed, in cash & shares, and only take a base currency
+    /// the market prices against their current one.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn update_account_settings(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+        settings: &AccountSettings,
+    ) -> Result<UpdateAccountAction, sqlx::Error> {
+        let Some(account) = self.database.get_account(user_id, uuid).await? else {
+            return Ok(UpdateAccountAction::NotFound);
+        };
+        if let Some(name) = &settings.fee_schedule {
+            if self.database.get_fee_schedule_named(name).await?.is_none() {
+                return Ok(UpdateAccountAction::UnknownFeeSchedule);
+            }
+        }
+        let initial = settings.initial_margin.unwrap_or(account.initial_margin);
+        let maintenance = settings
+            .maintenance_margin
+            .unwrap_or(account.maintenance_margin);
+        if initial > Decimal::ONE_HUNDRED || maintenance <= Decimal::ZERO || maintenance > initial {
+            return Ok(UpdateAccountAction::InvalidMargin);
+        }
+        let mut settings = settings.clone();
+        if let Some(currency) = &mut settings.base_currency {
+            *currency = currency.trim().to_ascii_uppercase();
+            if !is_currency(currency)
+                || margin::fx_rate(self.market.as_ref(), currency, &account.base_currency)
+                    .await
+                    .is_none()
+            {
+                return Ok(UpdateAccountAction::UnknownCurrency);
+            }
+        }
+        if settings
+            .fx_spread_bps
+            .is_some_and(|bps| bps < Decimal::ZERO || bps >= Decimal::from(10_000))
+        {
+            return Ok(UpdateAccountAction::InvalidSpread);
+        }
+        if settings.account_type == Some(AccountType::Cash)
+            && account.account_type == AccountType::Margin
+        {
+            let short = self
+                .database
+                .get_positions(account.id)
+                .await?
+                .iter()
+                .any(|position| position.quantity < Quantity::ZERO);
+            if short || account.cash < Money::ZERO {
+                return Ok(UpdateAccountAction::MarginInUse);
+            }
+        }
+        self.database
+            .update_account_settings(account.id, &settings)
+            .await
+            .map(|account| UpdateAccountAction::Updated(Box::new(account)))
+    }
+
+    /// Get every fee schedule an account can pay
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_fee_schedules(&self) -> Result<Vec<FeeSchedule>, sqlx::Error> {
+        self.database.get_fee_schedules().await
+    }
+
+    /// Get the margin of one of a user's accounts, valued at the market
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_margin(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<MarginReport>, sqlx::Error> {
+        match self.get_account_or_default(user_id, account).await? {
+            Some(account) => self.margin(&account).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// An account's margin, valued at the market
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn margin(&self, account: &Account) -> Result<MarginReport, sqlx::Error> {
+        let market = self.market.as_ref();
+        let positions = margin::value_positions(&self.database, market, account).await?;
+        let cash = margin::value_cash(&self.database, market, account).await?;
+        let reserved = self.database.get_open_buy_notional(account.id).await?;
+        Ok(MarginReport::new(account, cash, &positions, reserved))
+    }
+
+    /// Reset one of a user's accounts back to its starting cash
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn reset_account(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<Account>, sqlx::Error> {
+        match self.database.get_account(user_id, uuid).await? {
+            Some(account) => self.database.reset_account(account.id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The result of changing an account's settings
+pub enum UpdateAccountAction {
+    /// Settings changed
+    Updated(Box<Account>),
+    /// The account doesn't exist
+    NotFound,
+    /// The fee schedule doesn't exist
+    UnknownFeeSchedule,
+    /// The margins aren't valid percentages
+    InvalidMargin,
+    /// The account can't become a cash account while it's borrowing
+    MarginInUse,
+    /// The base currency isn't one the market prices
+    UnknownCurrency,
+    /// The exchange spread isn't a fraction of the rate, in basis points
+    InvalidSpread,
+}
@@ -0,0 +1,185 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Context;
+use crate::{
+    margin,
+    models::{Conversion, InterestAccrual, InterestRate, NewConversion},
+    numeric::Money,
+};
+
+impl Context {
+    /// Get the cash one of a user's accounts holds in every currency, its
+    /// base currency's first, each valued in the base currency
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_cash_balances(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<CashBalanceReport>>, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(None);
+        };
+        let mut reports = vec![CashBalanceReport::new(
+            account.base_currency.clone(),
+            *account.cash,
+            Some(Decimal::ONE),
+            &account.base_currency,
+        )];
+        for balance in self.database.get_cash_balances(account.id).await? {
+            let rate = margin::fx_rate(
+                self.market.as_ref(),
+                &balance.currency,
+                &account.base_currency,
+            )
+            .await;
+            reports.push(CashBalanceReport::new(
+                balance.currency,
+                *balance.amount,
+                rate,
+                &account.base_currency,
+            ));
+        }
+        Ok(Some(reports))
+    }
+
+    /// Get the conversions of one of a user's accounts, newest first
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_conversions(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<Conversion>>, sqlx::Error> {
+        match self.get_account_or_default(user_id, account).await? {
+            Some(account) => self.database.get_conversions(account.id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Get every interest rate cash earns or pays, by currency, latest to
+    /// take effect first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_interest_rates(&self) -> Result<Vec<InterestRate>, sqlx::Error> {
+        self.database.get_interest_rates().await
+    }
+
+    /// Get the interest one of a user's accounts has accrued each day,
+    /// newest first
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_interest_accruals(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<InterestAccrual>>, sqlx::Error> {
+        match self.get_account_or_default(user_id, account).await? {
+            Some(account) => self
+                .database
+                .get_interest_accruals(account.id)
+                .await
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Try converting the cash of one of a user's accounts to another
+    /// currency, at the market's rate less the account's spread
+    ///
+    /// Uses the user's default account if `account` is `None`. Accounts can
+    /// only convert cash they hold.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn convert_currency(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+        mut conversion: NewConversion,
+    ) -> Result<ConvertAction, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(ConvertAction::NoAccount);
+        };
+        conversion.from = conversion.from.trim().to_ascii_uppercase();
+        conversion.to = conversion.to.trim().to_ascii_uppercase();
+        if !conversion.is_valid() {
+            return Ok(ConvertAction::InvalidConversion);
+        }
+        let Some(rate) =
+            margin::fx_rate(self.market.as_ref(), &conversion.from, &conversion.to).await
+        else {
+            return Ok(ConvertAction::NoRate);
+        };
+        Ok(self
+            .database
+            .convert_currency(account.id, &conversion, rate)
+            .await?
+            .map_or(ConvertAction::InsufficientCash, |conversion| {
+                ConvertAction::Converted(Box::new(conversion))
+            }))
+    }
+}
+
+/// Cash held in a currency, valued in its account's base currency
+#[derive(Debug, Clone, Serialize)]
+pub struct CashBalanceReport {
+    /// The cash's currency
+    pub currency: String,
+    /// The cash held, negative when owed
+    pub amount: Decimal,
+    /// The market's units of the base currency a unit of the cash's, if it's
+    /// quoted
+    pub fx_rate: Option<Decimal>,
+    /// The cash held, in the base currency
+    pub base_value: Option<Decimal>,
+}
+
+impl CashBalanceReport {
+    /// Values cash at the given exchange rate to `base_currency`
+    #[must_use]
+    pub fn new(
+        currency: String,
+        amount: Decimal,
+        fx_rate: Option<Decimal>,
+        base_currency: &str,
+    ) -> Self {
+        Self {
+            currency,
+            amount,
+            fx_rate,
+            base_value: fx_rate.map(|rate| *Money::round_minor(amount * rate, base_currency)),
+        }
+    }
+}
+
+/// The result of converting cash
+pub enum ConvertAction {
+    /// Cash converted
+    Converted(Box<Conversion>),
+    /// The account doesn't exist
+    NoAccount,
+    /// The conversion is malformed
+    InvalidConversion,
+    /// The market doesn't price the currencies against each other
+    NoRate,
+    /// The account doesn't hold enough of the currency to convert
+    InsufficientCash,
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{orders::CheckedOrder, Context, PlaceOrderAction};
+use crate::{
+    margin::{requirement, Holding, Requirement},
+    models::{
+        Account, AccountType, NewLeg, NewOrder, NewOrderGroup, Order, OrderGroup, OrderGroupKind,
+        OrderSide, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Context {
+    /// Try placing a group of orders for one of a user's accounts
+    ///
+    /// Uses the user's default account if `account` is `None`. A bracket's
+    /// entry, and each one-cancels-other order, is checked as if placed on its
+    /// own. A bracket's exits wait for its entry to fill. A multi-leg group's
+    /// legs are checked together, see [`Self::prepare_spread`].
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn place_order_group(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+        mut group: NewOrderGroup,
+    ) -> Result<PlaceOrderGroupAction, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(PlaceOrderGroupAction::Refused(PlaceOrderAction::NoAccount));
+        };
+        if !group.is_valid() {
+            return Ok(PlaceOrderGroupAction::Refused(
+                PlaceOrderAction::InvalidOrder,
+            ));
+        }
+        let mut spread = None;
+        let (parent, children) = match &mut group {
+            NewOrderGroup::Bracket {
+                entry,
+                take_profit,
+                stop_loss,
+            } => {
+                let trail_reference = match self.prepare_order(&account, entry).await? {
+                    Ok(trail_reference) => trail_reference,
+                    Err(action) => return Ok(PlaceOrderGroupAction::Refused(action)),
+                };
+                let exits = entry.exits(*take_profit, *stop_loss);
+                let instrument = self
+                    .database
+                    .get_instrument_or_unlisted(&entry.symbol)
+                    .await?;
+                if !exits.iter().all(|exit| instrument.allows(exit)) {
+                    return Ok(PlaceOrderGroupAction::Refused(
+                        PlaceOrderAction::OffIncrement,
+                    ));
+                }
+                (
+                    Some((entry.clone(), trail_reference)),
+                    exits.map(|exit| (exit, None)).to_vec(),
+                )
+            }
+            NewOrderGroup::Oco { orders } => {
+                let mut legs = Vec::with_capacity(orders.len());
+                for order in orders.iter_mut() {
+                    match self.prepare_order(&account, order).await? {
+                        Ok(trail_reference) => legs.push((order.clone(), trail_reference)),
+                        Err(action) => return Ok(PlaceOrderGroupAction::Refused(action)),
+                    }
+                }
+                (None, legs)
+            }
+            NewOrderGroup::MultiLeg {
+                legs,
+                quantity,
+                limit_price,
+                time_in_force,
+                expires_at,
+            } => {
+                spread = Some((*quantity, *limit_price));
+                match self
+                    .prepare_spread(
+                        &account,
+                        legs,
+                        (*quantity, *limit_price),
+                        *time_in_force,
+                        *expires_at,
+                    )
+                    .await?
+                {
+                    Ok(legs) => (None, legs),
+                    Err(action) => return Ok(PlaceOrderGroupAction::Refused(action)),
+                }
+            }
+        };
+        let parent = parent
+            .as_ref()
+            .map(|(order, trail_reference)| (order, *trail_reference));
+        let (group, orders) = self
+            .database
+            .add_order_group(account.id, group.kind(), spread, parent, &children)
+            .await?;
+        Ok(PlaceOrderGroupAction::Placed(Box::new(
+            OrderGroupReport::new(group, orders),
+        )))
+    }
+
+    /// Validates & prices a multi-leg group's legs, trading `quantity` units
+    /// of the spread for at worst `limit_price`, before it's placed
+    ///
+    /// Each leg is checked as an order placed on its own would be, short of
+    /// what it costs, and all must trade in the same currency. The group must
+    /// then be affordable as a whole, see [`Self::check_spread_affordable`].
+    ///
+    /// Returns the legs' orders, or why the group can't be placed.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    async fn prepare_spread(
+        &self,
+        account: &Account,
+        legs: &[NewLeg],
+        (quantity, limit_price): (Quantity, Money),
+        time_in_force: TimeInForce,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<Result<Vec<(NewOrder, Option<Money>)>, PlaceOrderAction>, sqlx::Error> {
+        let mut orders = Vec::with_capacity(legs.len());
+        let mut checked = Vec::with_capacity(legs.len());
+        for leg in legs {
+            let mut order = leg.order(quantity, time_in_force, expires_at);
+            match self.check_order(account, &mut order).await? {
+                Ok(leg) => checked.push(leg),
+                Err(action) => return Ok(Err(action)),
+            }
+            orders.push(order);
+        }
+        if checked
+            .iter()
+            .any(|leg| leg.instrument.currency != checked[0].instrument.currency)
+        {
+            return Ok(Err(PlaceOrderAction::InvalidOrder));
+        }
+        let unit = checked
+            .iter()
+            .map(|leg| leg.instrument.multiplier)
+            .max()
+            .unwrap_or(Quantity::ONE);
+        let Some(cost) = quantity.at(limit_price) else {
+            return Ok(Err(PlaceOrderAction::InvalidOrder));
+        };
+        let cost = cost * *unit;
+        if let Err(action) = self
+            .check_spread_affordable(account, &orders, &checked, cost)
+            .await?
+        {
+            return Ok(Err(action));
+        }
+        Ok(Ok(orders.into_iter().map(|order| (order, None)).collect()))
+    }
+
+    /// Checks an account can afford a multi-leg group's legs, paying at most
+    /// `cost` for them, or raising at least its opposite, in their currency
+    ///
+    /// Legs are estimated at the current ask or bid, with their fees, and are
+    /// checked again when they fill. Cash accounts must be able to pay the
+    /// spread's net debit, and only sell what they hold. Margin accounts must
+    /// have enough equity past their initial requirement to cover the change
+    /// the spread makes to it, margining its legs together, see
+    /// [`requirement`], and only sell short what can be borrowed. Futures are
+    /// only traded on margin, paying nothing for what they hold.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    async fn check_spread_affordable(
+        &self,
+        account: &Account,
+        orders: &[NewOrder],
+        checked: &[CheckedOrder],
+        cost: Decimal,
+    ) -> Result<Result<(), PlaceOrderAction>, sqlx::Error> {
+        let schedule = self
+            .database
+            .get_fee_schedule(account.fee_schedule_id)
+            .await?;
+        let rate = checked.first().map_or(Decimal::ONE, |leg| leg.rate);
+        let mut fees = Decimal::ZERO;
+        let (mut before, mut after) = (Vec::new(), Vec::new());
+        for (order, leg) in orders.iter().zip(checked) {
+            let instrument = &leg.instrument;
+            let price = match order.side {
+                OrderSide::Buy => leg.quote.ask,
+                OrderSide::Sell => leg.quote.bid,
+            } * *instrument.multiplier;
+            fees += schedule.fee(order.side, *order.quantity, price);
+            let held = self
+                .database
+                .get_position(account.id, &order.symbol)
+                .await?
+                .map_or(Decimal::ZERO, |position| *position.quantity);
+            let traded = match order.side {
+                OrderSide::Buy => *order.quantity,
+                OrderSide::Sell => -*order.quantity,
+            };
+            let short = held + traded < Decimal::ZERO && (held + traded).abs() > held.abs();
+            let future = instrument.is_future();
+            let allowed = match account.account_type {
+                AccountType::Cash if future => false,
+                AccountType::Cash => {
+                    order.side == OrderSide::Buy
+                        || self.sellable(account, &order.symbol).await? >= *order.quantity
+                }
+                AccountType::Margin => {
+                    !short
+                        || instrument.is_option()
+                        || future
+                        || self
+                            .database
+                            .get_borrow_rate(&order.symbol)
+                            .await?
+                            .is_some_and(|borrow| borrow.easy_to_borrow && instrument.shortable)
+                }
+            };
+            if !allowed {
+                return Ok(Err(PlaceOrderAction::InsufficientHoldings));
+            }
+            let value = if future {
+                Decimal::ZERO
+            } else {
+                leg.quote.last * *instrument.multiplier * rate
+            };
+            let holding = |quantity: Decimal| Holding {
+                instrument,
+                quantity,
+                value: quantity * value,
+                scale: Some(*instrument.multiplier * rate),
+            };
+            before.push(holding(held));
+            after.push(holding(held + traded));
+        }
+        let paid = (cost + fees) * rate;
+        let buying_power = self.buying_power(account).await?;
+        let affordable = match account.account_type {
+            AccountType::Cash => paid <= buying_power,
+            AccountType::Margin => {
+                let initial = Requirement::Initial;
+                let value = |holdings: &[Holding<'_>]| -> Decimal {
+                    holdings.iter().map(|holding| holding.value).sum()
+                };
+                let needed = paid - (value(&after) - value(&before))
+                    + requirement(&after, account, initial)
+                    - requirement(&before, account, initial);
+                needed <= buying_power * account.initial_margin / Decimal::ONE_HUNDRED
+            }
+        };
+        Ok(if affordable {
+            Ok(())
+        } else {
+            Err(PlaceOrderAction::InsufficientBuyingPower)
+        })
+    }
+
+    /// Get all the order groups of one of a user's accounts, newest first
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_order_groups(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<OrderGroupReport>>, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(None);
+        };
+        let mut orders: HashMap<_, Vec<_>> = HashMap::new();
+        for order in self.database.get_grouped_orders(account.id).await? {
+            orders.entry(order.group_id).or_default().push(order);
+        }
+        let groups = self.database.get_order_groups(account.id).await?;
+        Ok(Some(
+            groups
+                .into_iter()
+                .map(|group| {
+                    let orders = orders.remove(&Some(group.id)).unwrap_or_default();
+                    OrderGroupReport::new(group, orders)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Try cancelling every open or pending order of one of a user's groups
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn cancel_order_group(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<CancelOrderGroupAction, sqlx::Error> {
+        let Some(group) = self.database.get_order_group(user_id, uuid).await? else {
+            return Ok(CancelOrderGroupAction::NotFound);
+        };
+        let orders = self.database.cancel_order_group(&group).await?;
+        Ok(if orders.is_empty() {
+            CancelOrderGroupAction::NotOpen
+        } else {
+            CancelOrderGroupAction::Cancelled(orders)
+        })
+    }
+}
+
+/// An order group, with its orders
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderGroupReport {
+    /// The group itself
+    #[serde(flatten)]
+    pub group: OrderGroup,
+    /// The order whose fill opens the others, for brackets
+    pub parent: Option<Order>,
+    /// The group's other orders, which cancel one another, or a multi-leg
+    /// group's legs
+    pub children: Vec<Order>,
+}
+
+impl OrderGroupReport {
+    /// Splits a group's orders into its parent & children
+    #[must_use]
+    pub fn new(group: OrderGroup, orders: Vec<Order>) -> Self {
+        let (parents, children) = orders.into_iter().partition::<Vec<_>, _>(|order| {
+            group.kind == OrderGroupKind::Bracket && order.parent_id.is_none()
+        });
+        Self {
+            group,
+            parent: parents.into_iter().next(),
+            children,
+        }
+    }
+}
+
+/// The result of placing an order group
+pub enum PlaceOrderGroupAction {
+    /// Group placed
+    Placed(Box<OrderGroupReport>),
+    /// The group, or one of its orders, can't be placed
+    Refused(PlaceOrderAction),
+}
+
+/// The result of cancelling an order group
+pub enum CancelOrderGroupAction {
+    /// The group's open & pending orders were cancelled
+    Cancelled(Vec<Order>),
+    /// The group doesn't exist
+    NotFound,
+    /// None of the group's orders are open or pending
+    NotOpen,
+}
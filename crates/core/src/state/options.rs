@@ -0,0 +1,196 @@
+use rust_decimal::Decimal;
+use serde::{Serialize, Serializer};
+use time::{Date, OffsetDateTime};
+use tracing::warn;
+
+use super::Context;
+use crate::{
+    models::{Instrument, OptionType},
+    options::{self, ContractTerms},
+};
+
+impl Context {
+    /// Get a symbol's option chain for one of its expiries, priced at the
+    /// market
+    ///
+    /// Lists the chain's expiries, see [`options::expiries`], and the calls &
+    /// puts at each of its strikes, see [`options::strikes`], for the soonest
+    /// expiry unless one is given. Contracts are listed in the registry as
+    /// their chain is asked for, so they can be traded.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_option_chain(
+        &self,
+        underlying: &str,
+        expiry: Option<Date>,
+    ) -> Result<OptionChainAction, sqlx::Error> {
+        let underlying = underlying.trim().to_ascii_uppercase();
+        let Some(instrument) = self
+            .database
+            .get_instrument(&underlying)
+            .await?
+            .filter(|instrument| !instrument.is_option())
+        else {
+            return Ok(OptionChainAction::UnknownSymbol);
+        };
+        if !instrument.active {
+            return Ok(OptionChainAction::Delisted);
+        }
+        let price = match self.market.quote(&underlying).await {
+            Ok(Some(quote)) => quote.last,
+            Ok(None) => return Ok(OptionChainAction::NoQuote),
+            Err(e) => {
+                warn!(symbol = underlying, "failed to quote: {e}");
+                return Ok(OptionChainAction::NoQuote);
+            }
+        };
+        let expiries = options::expiries(OffsetDateTime::now_utc().date());
+        let Some(expires_on) = expiry.or_else(|| expiries.first().copied()) else {
+            return Ok(OptionChainAction::InvalidExpiry);
+        };
+        if !expiries.contains(&expires_on) {
+            return Ok(OptionChainAction::InvalidExpiry);
+        }
+        let contracts: Vec<_> = options::strikes(price)
+            .into_iter()
+            .flat_map(|strike| {
+                [OptionType::Call, OptionType::Put].map(|option_type| {
+                    ContractTerms {
+                        underlying: underlying.clone(),
+                        expires_on,
+                        option_type,
+                        strike,
+                    }
+                    .listing(&instrument)
+                })
+            })
+            .collect();
+        self.database.list_instruments(&contracts).await?;
+        let mut chain = OptionChain {
+            underlying,
+            price,
+            expires_on,
+            expiries,
+            calls: Vec::new(),
+            puts: Vec::new(),
+        };
+        for contract in contracts {
+            let quote = match self.market.quote(&contract.symbol).await {
+                Ok(quote) => quote,
+                Err(e) => {
+                    warn!(symbol = contract.symbol, "failed to quote: {e}");
+                    None
+                }
+            };
+            let quote = OptionQuote {
+                bid: quote.as_ref().map(|quote| quote.bid),
+                ask: quote.as_ref().map(|quote| quote.ask),
+                last: quote.map(|quote| quote.last),
+                intrinsic_value: contract.intrinsic_value(price),
+                strike: *contract.strike.unwrap_or_default(),
+                multiplier: *contract.multiplier,
+                symbol: contract.symbol,
+            };
+            match contract.option_type {
+                Some(OptionType::Call) => chain.calls.push(quote),
+                Some(OptionType::Put) | None => chain.puts.push(quote),
+            }
+        }
+        Ok(OptionChainAction::Chain(Box::new(chain)))
+    }
+
+    /// Get a symbol from the registry, listing it first if it's an option
+    /// contract on a listed symbol, for one of the expiries its chain lists
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub(super) async fn find_instrument(
+        &self,
+        symbol: &str,
+    ) -> Result<Option<Instrument>, sqlx::Error> {
+        if let Some(instrument) = self.database.get_instrument(symbol).await? {
+            return Ok(Some(instrument));
+        }
+        let expiries = options::expiries(OffsetDateTime::now_utc().date());
+        let Some(terms) =
+            ContractTerms::parse(symbol).filter(|terms| expiries.contains(&terms.expires_on))
+        else {
+            return Ok(None);
+        };
+        let Some(underlying) = self
+            .database
+            .get_instrument(&terms.underlying)
+            .await?
+            .filter(|underlying| underlying.active && !underlying.is_option())
+        else {
+            return Ok(None);
+        };
+        let contract = terms.listing(&underlying);
+        self.database
+            .list_instruments(std::slice::from_ref(&contract))
+            .await?;
+        Ok(Some(contract))
+    }
+}
+
+time::serde::format_description!(iso_date, Date, "[year]-[month]-[day]");
+
+/// Serializes dates as `YYYY-MM-DD`
+fn iso_dates<S: Serializer>(dates: &[Date], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(dates.iter().map(ToString::to_string))
+}
+
+/// The calls & puts listed on a symbol for one expiry, priced at the market
+#[derive(Debug, Clone, Serialize)]
+pub struct OptionChain {
+    /// The symbol the contracts are on
+    pub underlying: String,
+    /// The underlying's last price
+    pub price: Decimal,
+    /// The date the contracts expire, at its end in UTC
+    #[serde(with = "iso_date")]
+    pub expires_on: Date,
+    /// Every expiry the symbol's chain lists, soonest first
+    #[serde(serialize_with = "iso_dates")]
+    pub expiries: Vec<Date>,
+    /// The calls, by strike
+    pub calls: Vec<OptionQuote>,
+    /// The puts, by strike
+    pub puts: Vec<OptionQuote>,
+}
+
+/// An option contract in a chain, with its quote if it's priced
+#[derive(Debug, Clone, Serialize)]
+pub struct OptionQuote {
+    /// The contract's OCC symbol
+    pub symbol: String,
+    /// The contract's strike
+    pub strike: Decimal,
+    /// The shares of the underlying a contract is for
+    pub multiplier: Decimal,
+    /// The best price a seller gets, per share
+    pub bid: Option<Decimal>,
+    /// The best price a buyer gets, per share
+    pub ask: Option<Decimal>,
+    /// The theoretical price, per share
+    pub last: Option<Decimal>,
+    /// What exercising the contract is worth now, per share
+    pub intrinsic_value: Decimal,
+}
+
+/// The result of getting an option chain
+pub enum OptionChainAction {
+    /// The chain
+    Chain(Box<OptionChain>),
+    /// The symbol isn't in the registry, or is itself a contract
+    UnknownSymbol,
+    /// The symbol has been delisted
+    Delisted,
+    /// The symbol has no market data
+    NoQuote,
+    /// The chain doesn't list the expiry
+    InvalidExpiry,
+}
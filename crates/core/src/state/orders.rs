@@ -0,0 +1,455 @@
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::Context;
+use crate::{
+    engine::trail_stop,
+    margin,
+    market::Quote,
+    models::{
+        Account, AccountType, Instrument, NewOrder, Order, OrderSide, OrderStatus, OrderType,
+        TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Context {
+    /// Try placing an order for one of a user's accounts
+    ///
+    /// Uses the user's default account if `account` is `None`. Symbols must
+    /// be listed in the registry, & still active, and quantities & prices in
+    /// their increments. Option contracts on listed symbols are listed as
+    /// they're ordered, & can't be ordered once they expire. Buys are checked
+    /// against the account's buying power, in its base currency, and sells
+    /// against what the account holds, before being accepted. Symbols in
+    /// another currency must have their exchange rate quoted. Sells of more
+    /// than is held go short, if the account trades on margin and the symbol
+    /// is an option contract, which is written, or is shortable & easy to
+    /// borrow, and the account's buying power covers the rest. Day orders
+    /// expire at the close of the current, or next, session of the symbol's
+    /// exchange, or of the UTC day for crypto, which trades around the
+    /// clock. Orders placed while the market is closed wait for it to
+    /// open, unless they must fill at once, and good 'til date orders must
+    /// expire in the future.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn place_order(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+        mut order: NewOrder,
+    ) -> Result<PlaceOrderAction, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(PlaceOrderAction::NoAccount);
+        };
+        let trail_reference = match self.prepare_order(&account, &mut order).await? {
+            Ok(trail_reference) => trail_reference,
+            Err(action) => return Ok(action),
+        };
+        self.database
+            .add_order(account.id, &order, trail_reference)
+            .await
+            .map(|order| PlaceOrderAction::Placed(Box::new(order)))
+    }
+
+    /// Validates & prices an order before it's placed for an account
+    ///
+    /// Returns the reference a trailing stop starts trailing, or why the
+    /// order can't be placed.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub(super) async fn prepare_order(
+        &self,
+        account: &Account,
+        order: &mut NewOrder,
+    ) -> Result<Result<Option<Money>, PlaceOrderAction>, sqlx::Error> {
+        let checked = match self.check_order(account, order).await? {
+            Ok(checked) => checked,
+            Err(action) => return Ok(Err(action)),
+        };
+        if let Err(action) = self
+            .check_affordable(
+                account,
+                order,
+                &checked.quote,
+                &checked.instrument,
+                checked.rate,
+            )
+            .await?
+        {
+            return Ok(Err(action));
+        }
+        Ok(Ok(checked.trail_reference))
+    }
+
+    /// Validates & prices an order before it's placed for an account, short
+    /// of checking it can afford it
+    ///
+    /// Returns the order's symbol, quote & exchange rate, or why the order
+    /// can't be placed.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub(super) async fn check_order(
+        &self,
+        account: &Account,
+        order: &mut NewOrder,
+    ) -> Result<Result<CheckedOrder, PlaceOrderAction>, sqlx::Error> {
+        order.symbol = order.symbol.trim().to_ascii_uppercase();
+        let now = OffsetDateTime::now_utc();
+        if !order.is_valid() || order.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Ok(Err(PlaceOrderAction::InvalidOrder));
+        }
+        let Some(instrument) = self.find_instrument(&order.symbol).await? else {
+            return Ok(Err(PlaceOrderAction::UnknownSymbol));
+        };
+        if !instrument.active || instrument.expires_on.is_some_and(|date| date < now.date()) {
+            return Ok(Err(PlaceOrderAction::Delisted));
+        }
+        if !instrument.allows(order) {
+            return Ok(Err(PlaceOrderAction::OffIncrement));
+        }
+        let Some(session) = self
+            .calendar
+            .trading_session(&instrument, now, order.extended_hours)
+        else {
+            return Ok(Err(PlaceOrderAction::MarketClosed));
+        };
+        // orders that must fill at once can't wait for the market to open,
+        // while others are queued until it does
+        let killed = matches!(order.time_in_force, TimeInForce::Ioc | TimeInForce::Fok);
+        if killed && session.open > now {
+            return Ok(Err(PlaceOrderAction::MarketClosed));
+        }
+        if order.time_in_force == TimeInForce::Day {
+            order.expires_at = Some(session.close);
+        }
+        let quote = match self.market.quote(&order.symbol).await {
+            Ok(Some(quote)) => quote,
+            Ok(None) => return Ok(Err(PlaceOrderAction::NoQuote)),
+            Err(e) => {
+                warn!(symbol = order.symbol, "failed to quote: {e}");
+                return Ok(Err(PlaceOrderAction::NoQuote));
+            }
+        };
+        // market orders reserve what they were quoted at until they fill
+        if order.order_type == OrderType::Market {
+            order.quoted_price = Some(Money::round(match order.side {
+                OrderSide::Buy => quote.ask,
+                OrderSide::Sell => quote.bid,
+            }));
+        }
+        // trailing stops start trailing the last price
+        let trail_reference =
+            (order.order_type == OrderType::TrailingStop).then(|| Money::round(quote.last));
+        if let Some(reference) = trail_reference {
+            order.stop_price = trail_stop(
+                order.side,
+                *reference,
+                order.trail_amount,
+                order.trail_percent,
+                &instrument,
+            );
+        }
+        let Some(rate) = margin::fx_rate(
+            self.market.as_ref(),
+            &instrument.currency,
+            &account.base_currency,
+        )
+        .await
+        else {
+            return Ok(Err(PlaceOrderAction::NoQuote));
+        };
+        Ok(Ok(CheckedOrder {
+            instrument,
+            quote,
+            rate,
+            trail_reference,
+        }))
+    }
+
+    /// Checks an account can afford an order, in its base currency at the
+    /// market's exchange `rate`
+    ///
+    /// Orders are estimated at the worst price they may fill at, or the
+    /// current ask or bid for market orders, scaled by the symbol's
+    /// multiplier, with their fees, and are checked again when they fill.
+    /// Cash accounts that don't auto-convert must hold what a buy costs in
+    /// the symbol's currency. Futures are checked against their margin
+    /// instead, see [`Context::check_future_affordable`].
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    async fn check_affordable(
+        &self,
+        account: &Account,
+        order: &NewOrder,
+        quote: &Quote,
+        instrument: &Instrument,
+        rate: Decimal,
+    ) -> Result<Result<(), PlaceOrderAction>, sqlx::Error> {
+        if instrument.is_future() {
+            return self
+                .check_future_affordable(account, order, quote, instrument, rate)
+                .await;
+        }
+        let schedule = self
+            .database
+            .get_fee_schedule(account.fee_schedule_id)
+            .await?;
+        if order.side == OrderSide::Buy {
+            let price = order
+                .limit_price
+                .or(order.stop_price)
+                .map_or(quote.ask, Money::get)
+                * *instrument.multiplier;
+            let fee = schedule.fee(order.side, *order.quantity, price);
+            // covering a short frees what it held of the buying power, the
+            // proceeds of its sale in a cash account, or its value on margin
+            let freed = self
+                .database
+                .get_position(account.id, &order.symbol)
+                .await?
+                .filter(|position| position.quantity < Quantity::ZERO)
+                .map_or(Decimal::ZERO, |position| {
+                    let covered = *order.quantity.min(-position.quantity);
+                    match account.account_type {
+                        AccountType::Cash => *position.cost_basis / *position.quantity * covered,
+                        AccountType::Margin => price * covered,
+                    }
+                });
+            let cost = price * *order.quantity + fee;
+            if cost * rate > self.buying_power(account).await? + freed * rate {
+                return Ok(Err(PlaceOrderAction::InsufficientBuyingPower));
+            }
+            if account.account_type == AccountType::Cash
+                && !account.auto_convert
+                && instrument.currency != account.base_currency
+            {
+                let held = self
+                    .database
+                    .get_cash_balances(account.id)
+                    .await?
+                    .into_iter()
+                    .find(|balance| balance.currency == instrument.currency)
+                    .map_or(Decimal::ZERO, |balance| *balance.amount);
+                if cost > held {
+                    return Ok(Err(PlaceOrderAction::InsufficientBuyingPower));
+                }
+            }
+        } else {
+            // whatever's sold past what's held is borrowed, on margin, and
+            // held against buying power
+            let sellable = self.sellable(account, &order.symbol).await?;
+            let short = *order.quantity - sellable.max(Decimal::ZERO);
+            if short > Decimal::ZERO {
+                // option contracts are written, not borrowed
+                let borrowable = account.account_type == AccountType::Margin
+                    && (instrument.is_option()
+                        || self
+                            .database
+                            .get_borrow_rate(&order.symbol)
+                            .await?
+                            .is_some_and(|borrow| borrow.easy_to_borrow && instrument.shortable));
+                if !borrowable {
+                    return Ok(Err(PlaceOrderAction::InsufficientHoldings));
+                }
+                let price = order
+                    .limit_price
+                    .or(order.stop_price)
+                    .map_or(quote.bid, Money::get)
+                    * *instrument.multiplier;
+                let fee = schedule.fee(order.side, *order.quantity, price);
+                if (price * short + fee) * rate > self.buying_power(account).await? {
+                    return Ok(Err(PlaceOrderAction::InsufficientBuyingPower));
+                }
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// Checks a margin account can afford a futures order, in its base
+    /// currency at the market's exchange `rate`
+    ///
+    /// Futures are held without paying for them, long or short, so only the
+    /// initial margin of the contracts the order adds to the position, and
+    /// its fee, are held against buying power. Cash accounts can't trade
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    async fn check_future_affordable(
+        &self,
+        account: &Account,
+        order: &NewOrder,
+        quote: &Quote,
+        instrument: &Instrument,
+        rate: Decimal,
+    ) -> Result<Result<(), PlaceOrderAction>, sqlx::Error> {
+        if account.account_type == AccountType::Cash {
+            return Ok(Err(PlaceOrderAction::InsufficientBuyingPower));
+        }
+        let held = self
+            .database
+            .get_position(account.id, &order.symbol)
+            .await?
+            .map_or(Quantity::ZERO, |position| position.quantity);
+        let after = match order.side {
+            OrderSide::Buy => held + order.quantity,
+            OrderSide::Sell => held - order.quantity,
+        };
+        let added = after.abs() - held.abs();
+        if added <= Quantity::ZERO {
+            return Ok(Ok(()));
+        }
+        let schedule = self
+            .database
+            .get_fee_schedule(account.fee_schedule_id)
+            .await?;
+        let price = match order.side {
+            OrderSide::Buy => quote.ask,
+            OrderSide::Sell => quote.bid,
+        };
+        let price = order
+            .limit_price
+            .or(order.stop_price)
+            .map_or(price, Money::get)
+            * *instrument.multiplier;
+        let fee = schedule.fee(order.side, *order.quantity, price);
+        let margin = instrument.initial_margin.unwrap_or_default();
+        let Some(margin) = added.at(margin) else {
+            return Ok(Err(PlaceOrderAction::InvalidOrder));
+        };
+        let needed = (margin + fee) * rate;
+        Ok(if needed <= self.buying_power(account).await? {
+            Ok(())
+        } else {
+            Err(PlaceOrderAction::InsufficientBuyingPower)
+        })
+    }
+
+    /// Get all the orders of one of a user's accounts, newest first
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_orders(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<Order>>, sqlx::Error> {
+        match self.get_account_or_default(user_id, account).await? {
+            Some(account) => self.database.get_orders(account.id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Try cancelling one of a user's orders
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn cancel_order(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<CancelOrderAction, sqlx::Error> {
+        if let Some(order) = self.database.cancel_order(user_id, uuid).await? {
+            return Ok(CancelOrderAction::Cancelled(Box::new(order)));
+        }
+        Ok(match self.database.get_order(user_id, uuid).await? {
+            Some(order) => CancelOrderAction::NotOpen(order.status),
+            None => CancelOrderAction::NotFound,
+        })
+    }
+
+    /// The value an account can still commit to new positions
+    ///
+    /// See [`MarginReport::new`](crate::margin::MarginReport::new).
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn buying_power(&self, account: &Account) -> Result<Decimal, sqlx::Error> {
+        self.margin(account).await.map(|margin| margin.buying_power)
+    }
+
+    /// The quantity of a symbol an account can still commit to new sells
+    ///
+    /// This is its position, less what its open sells have committed, and is
+    /// negative once the account is short.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn sellable(&self, account: &Account, symbol: &str) -> Result<Decimal, sqlx::Error> {
+        let held = self
+            .database
+            .get_position(account.id, symbol)
+            .await?
+            .map_or(Decimal::ZERO, |position| *position.quantity);
+        let committed = self
+            .database
+            .get_open_sell_quantity(account.id, symbol)
+            .await?;
+        Ok(held - committed)
+    }
+}
+
+/// An order checked before it's placed, with what it was checked against
+pub(super) struct CheckedOrder {
+    /// The order's symbol
+    pub instrument: Instrument,
+    /// The symbol's quote
+    pub quote: Quote,
+    /// The market's units of the account's base currency a unit of the
+    /// symbol's currency
+    pub rate: Decimal,
+    /// The reference a trailing stop starts trailing
+    pub trail_reference: Option<Money>,
+}
+
+/// The result of placing an order
+pub enum PlaceOrderAction {
+    /// Order placed
+    Placed(Box<Order>),
+    /// The account doesn't exist
+    NoAccount,
+    /// The order is malformed
+    InvalidOrder,
+    /// The symbol isn't in the registry
+    UnknownSymbol,
+    /// The symbol has been delisted
+    Delisted,
+    /// The order's quantity or prices aren't in its symbol's increments
+    OffIncrement,
+    /// The symbol has no market data
+    NoQuote,
+    /// The account can't afford the order
+    InsufficientBuyingPower,
+    /// The account doesn't hold enough to sell, and can't borrow the rest
+    InsufficientHoldings,
+    /// The market isn't open, and the order can't wait for it
+    MarketClosed,
+}
+
+/// The result of cancelling an order
+pub enum CancelOrderAction {
+    /// Order cancelled
+    Cancelled(Box<Order>),
+    /// The order doesn't exist
+    NotFound,
+    /// The order has already left the book
+    NotOpen(OrderStatus),
+}
@@ -1,14 +1,46 @@
-use crate::models::User;
+use crate::{models::User, numeric::Money};
 use derivative::Derivative;
 use error::ConnectionError;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Accounts & their cash
+mod accounts;
+/// Corporate actions & the dividends they pay
+mod actions;
+/// Historical market data
+mod bars;
+/// Borrowing symbols to sell them short
+mod borrows;
+/// Cash held in other currencies, & conversions between them
+mod conversions;
 /// Errors
 pub mod error;
+/// Commission & regulatory fees
+mod fees;
+/// Fills & the positions they change
+mod fills;
+/// Futures settling daily, & at expiry
+mod futures;
+/// Order groups, like brackets
+mod groups;
+/// The increments symbols trade in
+mod instruments;
+/// Interest accruing daily on cash, & posted monthly
+mod interest;
+/// The double-entry journal every balance is posted through
+mod ledger;
+/// Margin calls & liquidations
+mod margin;
+/// Option contracts expiring, & being exercised or assigned
+mod options;
+/// Orders
+mod orders;
+
+pub use fills::{FillAction, Leg, Settlement, SpreadFillAction};
 
 /// The overarching database system
-#[derive(Derivative)]
+#[derive(Derivative, Clone)]
 #[derivative(Debug)]
 pub struct Database {
     /// A pool of database conenctions
@@ -29,6 +61,9 @@ impl Database {
     }
     /// Add a user to the database, returning the user's info
     ///
+    /// The user's default account is opened alongside them, named `account`,
+    /// holding `starting_cash` and paying the fees named `fee_schedule`.
+    ///
     /// Note: password must be hashed
     ///
     /// # Errors
@@ -38,16 +73,27 @@ impl Database {
     /// # Panics
     ///
     /// May be possible due to sqlx
-    pub async fn add_user(&self, username: &str, password: &str) -> Result<User, sqlx::Error> {
-        sqlx::query_file_as!(
+    pub async fn add_user(
+        &self,
+        username: &str,
+        password: &str,
+        account: &str,
+        starting_cash: Money,
+        fee_schedule: &str,
+    ) -> Result<User, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let user = sqlx::query_file_as!(
             User,
             "queries/insert_user.sql",
             Uuid::new_v4(),
             username,
             password
         )
-        .fetch_one(&self.pool)
-        .await
+        .fetch_one(&mut *tx)
+        .await?;
+        accounts::insert(&mut tx, user.id, account, starting_cash, fee_schedule).await?;
+        tx.commit().await?;
+        Ok(user)
     }
 
     /// Get user by username
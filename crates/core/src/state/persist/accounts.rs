@@ -0,0 +1,256 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{
+    ledger::{self, Line},
+    Database,
+};
+use crate::{
+    models::{
+        Account, AccountSettings, AccountType, CashEntry, CashEntryKind, LotMethod, SlippageKind,
+    },
+    numeric::Money,
+};
+
+impl Database {
+    /// Open a new account for the given user, depositing `starting_cash`, that
+    /// pays the fees named `fee_schedule`
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_account(
+        &self,
+        user_id: i32,
+        name: &str,
+        starting_cash: Money,
+        fee_schedule: &str,
+    ) -> Result<Account, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let account = insert(&mut tx, user_id, name, starting_cash, fee_schedule).await?;
+        tx.commit().await?;
+        Ok(account)
+    }
+
+    /// Get all of a user's accounts, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_accounts(&self, user_id: i32) -> Result<Vec<Account>, sqlx::Error> {
+        sqlx::query_file_as!(Account, "queries/select_accounts.sql", user_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get one of a user's accounts by its uuid
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_account(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<Account>, sqlx::Error> {
+        sqlx::query_file_as!(Account, "queries/select_account_uuid.sql", user_id, uuid)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Get an account by its id
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_account_by_id(&self, id: i32) -> Result<Account, sqlx::Error> {
+        sqlx::query_file_as!(Account, "queries/select_account_id.sql", id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Get an account's cash history, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_cash_entries(&self, account_id: i32) -> Result<Vec<CashEntry>, sqlx::Error> {
+        sqlx::query_file_as!(CashEntry, "queries/select_cash_entries.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Change an account's settings, leaving those that are `None` as is
+    ///
+    /// Unknown fee schedules are left as is too. Changing the base currency
+    /// sets the account's cash in the old one aside with its other
+    /// currencies', and takes whatever it holds of the new one as its cash,
+    /// each as the journal has it.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn update_account_settings(
+        &self,
+        account_id: i32,
+        settings: &AccountSettings,
+    ) -> Result<Account, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let account = sqlx::query_file_as!(Account, "queries/select_account_lock.sql", account_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if let Some(currency) = settings
+            .base_currency
+            .as_deref()
+            .filter(|currency| *currency != account.base_currency)
+        {
+            ledger::rebase(&mut tx, &account, currency).await?;
+        }
+        let account = sqlx::query_file_as!(
+            Account,
+            "queries/update_account_settings.sql",
+            account_id,
+            settings.lot_method as Option<LotMethod>,
+            settings.fee_schedule,
+            settings.slippage_model as Option<SlippageKind>,
+            settings.slippage_bps,
+            settings.account_type as Option<AccountType>,
+            settings.initial_margin,
+            settings.maintenance_margin,
+            settings.auto_liquidate,
+            settings.drip,
+            settings.base_currency,
+            settings.auto_convert,
+            settings.fx_spread_bps
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(account)
+    }
+
+    /// Restore an account's cash to its starting cash
+    ///
+    /// Open orders are cancelled and positions closed out, and interest
+    /// accrued but not yet posted is forgone, as are dividends declared but
+    /// not yet paid. Every ledger but the account's
+    /// capital is closed into it, and the difference in cash is recorded as
+    /// a [`CashEntryKind::Reset`] entry.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn reset_account(&self, account_id: i32) -> Result<Account, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let account = sqlx::query_file_as!(Account, "queries/select_account_lock.sql", account_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        sqlx::query_file!("queries/update_orders_cancel_account.sql", account_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query_file!("queries/delete_positions_account.sql", account_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query_file!("queries/delete_lots_account.sql", account_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query_file!("queries/delete_interest_accruals_unposted.sql", account_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query_file!("queries/delete_dividends_unpaid.sql", account_id)
+            .execute(&mut *tx)
+            .await?;
+        ledger::close(
+            &mut tx,
+            &account,
+            CashEntryKind::Reset,
+            *account.starting_cash,
+        )
+        .await?;
+        let account = sqlx::query_file_as!(Account, "queries/select_account_id.sql", account_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(account)
+    }
+}
+
+/// Insert an account & its opening deposit
+pub(super) async fn insert(
+    conn: &mut PgConnection,
+    user_id: i32,
+    name: &str,
+    starting_cash: Money,
+    fee_schedule: &str,
+) -> Result<Account, sqlx::Error> {
+    let account = sqlx::query_file_as!(
+        Account,
+        "queries/insert_account.sql",
+        Uuid::new_v4(),
+        user_id,
+        name,
+        starting_cash as Money,
+        fee_schedule
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let lines = [Line::cash(*starting_cash), Line::capital(-*starting_cash)];
+    let kind = CashEntryKind::Deposit;
+    ledger::post(conn, account.id, kind, None, &account.base_currency, &lines).await?;
+    Ok(Account {
+        cash: starting_cash,
+        ..account
+    })
+}
+
+/// Record a movement of an account's cash in a currency, as posted to its
+/// journal
+pub(super) async fn insert_cash_entry(
+    conn: &mut PgConnection,
+    account_id: i32,
+    kind: CashEntryKind,
+    currency: &str,
+    amount: Decimal,
+    balance: Decimal,
+) -> Result<CashEntry, sqlx::Error> {
+    sqlx::query_file_as!(
+        CashEntry,
+        "queries/insert_cash_entry.sql",
+        account_id,
+        kind as CashEntryKind,
+        currency,
+        amount,
+        balance
+    )
+    .fetch_one(conn)
+    .await
+}
@@ -0,0 +1,417 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use time::Date;
+
+use super::{
+    ledger::{self, Line},
+    orders, Database,
+};
+use crate::{
+    models::{
+        CashEntryKind, CorporateAction, CorporateActionKind, Dividend, Instrument, LedgerAccount,
+        NewCorporateAction, NewOrder, OrderOrigin, OrderSide, OrderStatus, OrderType, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Store corporate actions, replacing any not yet applied for the same
+    /// symbol, kind & ex-date
+    ///
+    /// Returns the number of actions written.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_corporate_actions(
+        &self,
+        actions: &[NewCorporateAction],
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+        for action in actions {
+            written += sqlx::query_file!(
+                "queries/upsert_corporate_action.sql",
+                action.symbol,
+                action.kind as CorporateActionKind,
+                action.ex_date,
+                action.pay_date,
+                action.ratio as Option<Money>,
+                action.amount as Option<Money>
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(written)
+    }
+
+    /// Get every corporate action, or a symbol's, by ex-date
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_corporate_actions(
+        &self,
+        symbol: Option<&str>,
+    ) -> Result<Vec<CorporateAction>, sqlx::Error> {
+        sqlx::query_file_as!(
+            CorporateAction,
+            "queries/select_corporate_actions.sql",
+            symbol
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Get a symbol's splits, applied or not, by ex-date
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_splits(&self, symbol: &str) -> Result<Vec<CorporateAction>, sqlx::Error> {
+        sqlx::query_file_as!(CorporateAction, "queries/select_splits.sql", symbol)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get every corporate action gone ex by `date` that's yet to be applied,
+    /// oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_due_actions(&self, date: Date) -> Result<Vec<CorporateAction>, sqlx::Error> {
+        sqlx::query_file_as!(
+            CorporateAction,
+            "queries/select_corporate_actions_due.sql",
+            date
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Apply a split to every position, lot & open order in its symbol held
+    /// or placed before its ex-date
+    ///
+    /// Each lot's quantity is multiplied by the split's ratio & its price
+    /// divided by it, leaving its cost as is. Symbols that don't trade in
+    /// fractions keep each lot to whole lots, and the shares left over are
+    /// paid in cash at `in_lieu_at`, or at their cost if it's `None`, realizing
+    /// the difference. Open & pending orders are adjusted the same way, with
+    /// their prices rounded to the symbol's tick & kept to at least one, as
+    /// are their quantities to whole lots. Each position's new shares, & any
+    /// cash paid for them, are posted to its account's journal as of the
+    /// ex-date. Everything is written in a single transaction.
+    ///
+    /// Returns the number of positions split, or `None` if the split was
+    /// already applied.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn apply_split(
+        &self,
+        action: &CorporateAction,
+        in_lieu_at: Option<Decimal>,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(action) = sqlx::query_file_as!(
+            CorporateAction,
+            "queries/select_corporate_action_lock.sql",
+            action.id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+        let ratio = action.ratio.unwrap_or(Money::ONE);
+        let instrument = self.get_instrument_or_unlisted(&action.symbol).await?;
+        let effective_at = action.effective_at();
+        let lot_size = (!instrument.fractional).then_some(instrument.lot_size);
+        let held = sqlx::query_file!(
+            "queries/select_lots_split.sql",
+            action.symbol,
+            effective_at,
+            ratio as Money,
+            lot_size as Option<Quantity>
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query_file!(
+            "queries/update_lots_split.sql",
+            action.symbol,
+            effective_at,
+            ratio as Money,
+            lot_size as Option<Quantity>
+        )
+        .execute(&mut *tx)
+        .await?;
+        for lots in &held {
+            let added = lots.added;
+            let cost = *lots.in_lieu_cost;
+            let cash = in_lieu_at.map_or(cost, |price| *Money::round(lots.in_lieu * price));
+            let realized = cash - cost;
+            sqlx::query_file!(
+                "queries/update_position_split.sql",
+                lots.account_id,
+                action.symbol,
+                added,
+                cost,
+                realized
+            )
+            .execute(&mut *tx)
+            .await?;
+            let lines = [
+                Line::symbol(LedgerAccount::Securities, &action.symbol, -cost, added),
+                Line::symbol(
+                    LedgerAccount::RealizedPnl,
+                    &action.symbol,
+                    -realized,
+                    Decimal::ZERO,
+                ),
+                Line::cash(cash),
+            ];
+            // only shares paid in cash move any, so splits otherwise record
+            // no cash entry
+            let lines = if lots.in_lieu.is_zero() {
+                &lines[..1]
+            } else {
+                &lines[..]
+            };
+            let kind = CashEntryKind::Split;
+            let currency = &instrument.currency;
+            ledger::post_effective(
+                &mut tx,
+                lots.account_id,
+                kind,
+                effective_at,
+                currency,
+                lines,
+            )
+            .await?;
+        }
+        sqlx::query_file!(
+            "queries/update_orders_split.sql",
+            action.symbol,
+            effective_at,
+            ratio as Money,
+            instrument.tick_size as Money,
+            lot_size as Option<Quantity>
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query_file!("queries/update_corporate_action_applied.sql", action.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(held.len() as u64))
+    }
+
+    /// Record what each account is owed by a dividend, from what it held as
+    /// the symbol went ex-dividend
+    ///
+    /// Holdings are taken from the journal as of the start of the ex-date, so
+    /// trades since don't count. Shorts owe the dividend instead. Amounts are
+    /// rounded to cents.
+    ///
+    /// Returns the number of accounts owed, or `None` if the dividend was
+    /// already recorded.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn declare_dividend(
+        &self,
+        action: &CorporateAction,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(action) = sqlx::query_file_as!(
+            CorporateAction,
+            "queries/select_corporate_action_lock.sql",
+            action.id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+        let owed = sqlx::query_file!(
+            "queries/insert_dividends.sql",
+            action.id,
+            action.symbol,
+            action.amount.unwrap_or_default() as Money,
+            action.pay_date.unwrap_or(action.ex_date),
+            action.effective_at()
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query_file!("queries/update_corporate_action_applied.sql", action.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(owed))
+    }
+
+    /// Get every dividend due by `date` that's yet to be paid, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_payable_dividends(&self, date: Date) -> Result<Vec<Dividend>, sqlx::Error> {
+        sqlx::query_file_as!(Dividend, "queries/select_dividends_payable.sql", date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Whether an account reinvests a symbol's dividends, by its position's
+    /// setting or else the account's
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn is_reinvested(&self, account_id: i32, symbol: &str) -> Result<bool, sqlx::Error> {
+        Ok(
+            sqlx::query_file_scalar!("queries/select_dividend_drip.sql", account_id, symbol)
+                .fetch_optional(&self.pool)
+                .await?
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Pay a dividend into its account's cash, or take it from a short's
+    ///
+    /// The payment, in the symbol's currency, is posted to the account's
+    /// journal, & added to its position's dividends, in a single transaction.
+    /// Given a price, what's paid is reinvested too, by a fractional market
+    /// buy of as much of the symbol as it pays for at that price, to
+    /// [`Instrument::FRACTIONAL_DP`] decimal places, even if the symbol
+    /// otherwise only trades in whole lots.
+    ///
+    /// Returns the dividend paid, with its reinvestment order if any, or
+    /// `None` if it already was.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn pay_dividend(
+        &self,
+        dividend: &Dividend,
+        reinvest_at: Option<Decimal>,
+    ) -> Result<Option<Dividend>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(mut dividend) =
+            sqlx::query_file_as!(Dividend, "queries/update_dividend_paid.sql", dividend.id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+        if !dividend.amount.is_zero() {
+            let currency = self
+                .get_instrument_or_unlisted(&dividend.symbol)
+                .await?
+                .currency;
+            sqlx::query_file!(
+                "queries/upsert_position_dividends.sql",
+                dividend.account_id,
+                dividend.symbol,
+                dividend.amount as Money
+            )
+            .execute(&mut *tx)
+            .await?;
+            let lines = [
+                Line::cash(*dividend.amount),
+                Line::symbol(
+                    LedgerAccount::Dividends,
+                    &dividend.symbol,
+                    -*dividend.amount,
+                    Decimal::ZERO,
+                ),
+            ];
+            let kind = CashEntryKind::Dividend;
+            ledger::post(&mut tx, dividend.account_id, kind, None, &currency, &lines).await?;
+        }
+        let quantity = reinvest_at
+            .filter(|price| *price > Decimal::ZERO && dividend.amount > Money::ZERO)
+            .map(|price| {
+                let quantity = (*dividend.amount / price)
+                    .round_dp_with_strategy(Instrument::FRACTIONAL_DP, RoundingStrategy::ToZero);
+                Quantity::round(quantity.normalize())
+            })
+            .filter(|quantity| *quantity > Quantity::ZERO);
+        if let Some(quantity) = quantity {
+            let order = NewOrder {
+                symbol: dividend.symbol.clone(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Market,
+                quantity,
+                limit_price: None,
+                stop_price: None,
+                trail_amount: None,
+                trail_percent: None,
+                time_in_force: TimeInForce::Gtc,
+                expires_at: None,
+                extended_hours: false,
+                origin: OrderOrigin::Reinvestment,
+                quoted_price: reinvest_at.map(Money::round),
+            };
+            let order = orders::insert(
+                &mut tx,
+                dividend.account_id,
+                &order,
+                None,
+                None,
+                OrderStatus::Open,
+            )
+            .await?;
+            sqlx::query_file!("queries/update_dividend_order.sql", dividend.id, order.id)
+                .execute(&mut *tx)
+                .await?;
+            dividend.order_id = Some(order.id);
+        }
+        tx.commit().await?;
+        Ok(Some(dividend))
+    }
+}
@@ -0,0 +1,78 @@
+use time::OffsetDateTime;
+
+use super::Database;
+use crate::market::Bar;
+
+impl Database {
+    /// Store a symbol's bars, replacing any already stored at the same times
+    ///
+    /// Returns the number of bars written.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_bars(&self, symbol: &str, bars: &[Bar]) -> Result<u64, sqlx::Error> {
+        let time: Vec<_> = bars.iter().map(|bar| bar.time).collect();
+        let open: Vec<_> = bars.iter().map(|bar| bar.open).collect();
+        let high: Vec<_> = bars.iter().map(|bar| bar.high).collect();
+        let low: Vec<_> = bars.iter().map(|bar| bar.low).collect();
+        let close: Vec<_> = bars.iter().map(|bar| bar.close).collect();
+        let volume: Vec<_> = bars.iter().map(|bar| bar.volume).collect();
+        sqlx::query_file!(
+            "queries/upsert_bars.sql",
+            symbol,
+            &time,
+            &open,
+            &high,
+            &low,
+            &close,
+            &volume
+        )
+        .execute(&self.pool)
+        .await
+        .map(|result| result.rows_affected())
+    }
+
+    /// Get a symbol's stored bars from `start` up to (not including) `end`
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_bars(
+        &self,
+        symbol: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Bar>, sqlx::Error> {
+        sqlx::query_file_as!(Bar, "queries/select_bars.sql", symbol, start, end)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get a symbol's latest stored bar at or before `at`
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_last_bar(
+        &self,
+        symbol: &str,
+        at: OffsetDateTime,
+    ) -> Result<Option<Bar>, sqlx::Error> {
+        sqlx::query_file_as!(Bar, "queries/select_bar_last.sql", symbol, at)
+            .fetch_optional(&self.pool)
+            .await
+    }
+}
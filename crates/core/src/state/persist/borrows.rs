@@ -0,0 +1,152 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use time::Date;
+
+use super::{
+    ledger::{self, Line},
+    Database,
+};
+use crate::{
+    models::{BorrowRate, CashEntryKind, LedgerAccount, Position},
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Get every symbol's borrow rate, by symbol
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_borrow_rates(&self) -> Result<Vec<BorrowRate>, sqlx::Error> {
+        sqlx::query_file_as!(BorrowRate, "queries/select_borrow_rates.sql")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get a symbol's borrow rate, if it's listed
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_borrow_rate(&self, symbol: &str) -> Result<Option<BorrowRate>, sqlx::Error> {
+        sqlx::query_file_as!(BorrowRate, "queries/select_borrow_rate.sql", symbol)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Get every short position that hasn't yet paid its borrow fee for a
+    /// date
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_unbilled_shorts(&self, date: Date) -> Result<Vec<Position>, sqlx::Error> {
+        sqlx::query_file_as!(
+            Position,
+            "queries/select_positions_short_unbilled.sql",
+            date
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Charge a short position a day's borrow fee, at `price`
+    ///
+    /// The fee is charged in the symbol's currency. It, its journal entry &
+    /// the position's running total are written in a single transaction.
+    /// Each position is charged at most once a date.
+    ///
+    /// Returns the fee, or `None` if the position is no longer short or was
+    /// already charged.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn charge_borrow_fee(
+        &self,
+        position: &Position,
+        rate: &BorrowRate,
+        date: Date,
+        price: Decimal,
+    ) -> Result<Option<Decimal>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(position) = sqlx::query_file_as!(
+            Position,
+            "queries/select_position_lock.sql",
+            position.account_id,
+            position.symbol
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .filter(|position| position.quantity < Quantity::ZERO) else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+        let fee = rate.daily_fee(*position.quantity, price);
+        let charged = sqlx::query_file_scalar!(
+            "queries/insert_borrow_fee.sql",
+            position.account_id,
+            position.symbol,
+            date,
+            -*position.quantity,
+            price,
+            rate.annual_rate,
+            fee
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if charged.is_none() {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        if !fee.is_zero() {
+            let currency = self
+                .get_instrument_or_unlisted(&position.symbol)
+                .await?
+                .currency;
+            sqlx::query_file!("queries/update_position_borrow_fees.sql", position.id, fee)
+                .execute(&mut *tx)
+                .await?;
+            let lines = [
+                Line::symbol(
+                    LedgerAccount::BorrowFees,
+                    &position.symbol,
+                    fee,
+                    Decimal::ZERO,
+                ),
+                Line::cash(-fee),
+            ];
+            let kind = CashEntryKind::BorrowFee;
+            ledger::post(&mut tx, position.account_id, kind, None, &currency, &lines).await?;
+        }
+        tx.commit().await?;
+        Ok(Some(fee))
+    }
+}
+
+/// Whether a symbol may be sold short & is easy to borrow, or is an option
+/// contract, which is written rather than borrowed
+pub(super) async fn is_borrowable(
+    conn: &mut PgConnection,
+    symbol: &str,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_file_scalar!("queries/select_borrowable.sql", symbol)
+        .fetch_one(conn)
+        .await
+}
@@ -0,0 +1,148 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{
+    ledger::{self, Line},
+    Database,
+};
+use crate::{
+    models::{
+        Account, AccountType, CashBalance, CashEntryKind, Conversion, LedgerAccount, LotMethod,
+        NewConversion, SlippageKind,
+    },
+    numeric::Money,
+};
+
+impl Database {
+    /// Get the cash an account holds in currencies other than its base
+    /// currency, by currency
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_cash_balances(
+        &self,
+        account_id: i32,
+    ) -> Result<Vec<CashBalance>, sqlx::Error> {
+        sqlx::query_file_as!(CashBalance, "queries/select_cash_balances.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get an account's conversions, newest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_conversions(&self, account_id: i32) -> Result<Vec<Conversion>, sqlx::Error> {
+        sqlx::query_file_as!(Conversion, "queries/select_conversions.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Convert an account's cash from one currency to another, at the
+    /// market's `rate` less the account's spread
+    ///
+    /// Returns `None` if the account doesn't hold enough of the currency
+    /// converted from.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn convert_currency(
+        &self,
+        account_id: i32,
+        conversion: &NewConversion,
+        rate: Decimal,
+    ) -> Result<Option<Conversion>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let account = sqlx::query_file_as!(Account, "queries/select_account_lock.sql", account_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if cash_balance(&mut tx, &account, &conversion.from).await? < *conversion.amount {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        let conversion = exchange(
+            &mut tx,
+            account.id,
+            None,
+            &conversion.from,
+            &conversion.to,
+            *conversion.amount,
+            account.fx_rate(rate),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(Some(conversion))
+    }
+}
+
+/// The cash an account holds in a currency
+pub(super) async fn cash_balance(
+    conn: &mut PgConnection,
+    account: &Account,
+    currency: &str,
+) -> Result<Decimal, sqlx::Error> {
+    if currency == account.base_currency {
+        return Ok(*account.cash);
+    }
+    Ok(
+        sqlx::query_file_scalar!("queries/select_cash_balance.sql", account.id, currency)
+            .fetch_optional(conn)
+            .await?
+            .unwrap_or_default(),
+    )
+}
+
+/// Exchange `amount` of an account's cash in one currency for another's, at
+/// `rate` units of the other a unit, rounded down to its minor unit
+///
+/// Each currency's side is posted to the account's journal as an entry of its
+/// own, against what's been exchanged in that currency.
+pub(super) async fn exchange(
+    conn: &mut PgConnection,
+    account_id: i32,
+    fill_id: Option<i32>,
+    from: &str,
+    to: &str,
+    amount: Decimal,
+    rate: Decimal,
+) -> Result<Conversion, sqlx::Error> {
+    let converted = *Money::round_minor_with(amount * rate, to, RoundingStrategy::ToZero);
+    let kind = CashEntryKind::Conversion;
+    for (currency, cash) in [(from, -amount), (to, converted)] {
+        let lines = [
+            Line::cash(cash),
+            Line::currency(LedgerAccount::Exchange, -cash),
+        ];
+        ledger::post(conn, account_id, kind, fill_id, currency, &lines).await?;
+    }
+    sqlx::query_file_as!(
+        Conversion,
+        "queries/insert_conversion.sql",
+        Uuid::new_v4(),
+        account_id,
+        fill_id,
+        from,
+        to,
+        amount,
+        rate,
+        converted
+    )
+    .fetch_one(conn)
+    .await
+}
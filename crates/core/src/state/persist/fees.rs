@@ -0,0 +1,52 @@
+use super::Database;
+use crate::models::FeeSchedule;
+
+impl Database {
+    /// Get every fee schedule, in the order they were added
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_fee_schedules(&self) -> Result<Vec<FeeSchedule>, sqlx::Error> {
+        sqlx::query_file_as!(FeeSchedule, "queries/select_fee_schedules.sql")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get a fee schedule by its id
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_fee_schedule(&self, id: i32) -> Result<FeeSchedule, sqlx::Error> {
+        sqlx::query_file_as!(FeeSchedule, "queries/select_fee_schedule.sql", id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Get a fee schedule by its name
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_fee_schedule_named(
+        &self,
+        name: &str,
+    ) -> Result<Option<FeeSchedule>, sqlx::Error> {
+        sqlx::query_file_as!(FeeSchedule, "queries/select_fee_schedule_name.sql", name)
+            .fetch_optional(&self.pool)
+            .await
+    }
+}
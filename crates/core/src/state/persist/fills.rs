@@ -0,0 +1,909 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{
+    borrows, conversions,
+    ledger::{self, Line},
+    margin, orders, Database,
+};
+use crate::{
+    lots,
+    margin::{requirement, Holding, Requirement},
+    models::{
+        Account, AccountType, CashEntryKind, FeeSchedule, Fill, Instrument, LedgerAccount, Lot,
+        LotMethod, Order, OrderGroup, OrderOrigin, OrderSide, OrderStatus, OrderType, Position,
+        PositionSettings, SlippageKind, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Get every open order, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_open_orders(&self) -> Result<Vec<Order>, sqlx::Error> {
+        sqlx::query_file_as!(Order, "queries/select_orders_open.sql")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get all of an account's positions, by symbol
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_positions(&self, account_id: i32) -> Result<Vec<Position>, sqlx::Error> {
+        sqlx::query_file_as!(Position, "queries/select_positions.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get an account's open lots in a symbol, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_open_lots(
+        &self,
+        account_id: i32,
+        symbol: &str,
+    ) -> Result<Vec<Lot>, sqlx::Error> {
+        sqlx::query_file_as!(Lot, "queries/select_lots_open.sql", account_id, symbol)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get an account's position in a symbol
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_position(
+        &self,
+        account_id: i32,
+        symbol: &str,
+    ) -> Result<Option<Position>, sqlx::Error> {
+        sqlx::query_file_as!(Position, "queries/select_position.sql", account_id, symbol)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Change an account's settings for a symbol, whether or not it holds it
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn update_position_settings(
+        &self,
+        account_id: i32,
+        symbol: &str,
+        settings: &PositionSettings,
+    ) -> Result<Position, sqlx::Error> {
+        sqlx::query_file_as!(
+            Position,
+            "queries/upsert_position_settings.sql",
+            account_id,
+            symbol,
+            settings.drip
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// The quantity of a symbol an account's open sells have committed
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_open_sell_quantity(
+        &self,
+        account_id: i32,
+        symbol: &str,
+    ) -> Result<Decimal, sqlx::Error> {
+        sqlx::query_file_scalar!("queries/select_open_sell_quantity.sql", account_id, symbol)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Fill `quantity` of an open order at `price`
+    ///
+    /// The fill, the position & lot changes and the journal entries, for the
+    /// trade and for the account's fees, are written in a single transaction,
+    /// as are the opening of a filled order's pending children and the
+    /// cancelling of its one-cancels-other siblings. Orders a cash account can
+    /// no longer afford, fees included, or sells of more than it holds, are
+    /// rejected instead. So are orders growing a margin account's position
+    /// past its initial margin, or short of a symbol that can't be borrowed.
+    ///
+    /// Trades, and their fees, are paid in the symbol's currency. Accounts that
+    /// auto-convert, trading in another currency than their base currency,
+    /// convert whatever they lack of it to pay, or whatever they raise of it,
+    /// from or to their base currency at the settlement's rate less their
+    /// spread, and may pay with their base currency's cash. Prices are
+    /// given for a unit of the settlement's multiplier, which what's paid,
+    /// fees included, is scaled by.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn fill_order(
+        &self,
+        order: &Order,
+        quantity: Quantity,
+        price: Money,
+        settlement: Settlement<'_>,
+    ) -> Result<FillAction, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let account =
+            sqlx::query_file_as!(Account, "queries/select_account_lock.sql", order.account_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let schedule = sqlx::query_file_as!(
+            FeeSchedule,
+            "queries/select_fee_schedule.sql",
+            account.fee_schedule_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let fee = schedule.fee(order.side, *quantity, *price * settlement.multiplier);
+        if !is_affordable(&mut tx, &account, order, quantity, price, fee, settlement).await? {
+            tx.rollback().await?;
+            return Ok(self
+                .close_order(order, OrderStatus::Rejected)
+                .await?
+                .map_or(FillAction::Stale, FillAction::Rejected));
+        }
+
+        let Some((order, fill)) =
+            execute(&mut tx, &account, order, quantity, price, fee, settlement).await?
+        else {
+            tx.rollback().await?;
+            return Ok(FillAction::Stale);
+        };
+        if account.auto_convert && settlement.currency != account.base_currency {
+            auto_convert(&mut tx, &account, &fill, settlement).await?;
+        }
+
+        tx.commit().await?;
+        Ok(FillAction::Filled(order, fill))
+    }
+
+    /// Fill every leg of a multi-leg group in full, at once, or none of them
+    ///
+    /// The legs are filled in a single transaction, each as
+    /// [`Database::fill_order`] fills an order, all settling in the first
+    /// leg's currency at `rate`. The whole group is rejected instead if its
+    /// account can't afford it, see [`is_spread_affordable`], and nothing's
+    /// filled if any leg is no longer open.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn fill_spread(
+        &self,
+        group: &OrderGroup,
+        legs: &[Leg<'_>],
+        rate: Decimal,
+    ) -> Result<SpreadFillAction, sqlx::Error> {
+        let Some(currency) = legs.first().map(|leg| leg.instrument.currency.as_str()) else {
+            return Ok(SpreadFillAction::Stale);
+        };
+        let mut tx = self.pool.begin().await?;
+        let account =
+            sqlx::query_file_as!(Account, "queries/select_account_lock.sql", group.account_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let schedule = sqlx::query_file_as!(
+            FeeSchedule,
+            "queries/select_fee_schedule.sql",
+            account.fee_schedule_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let fees: Vec<_> = legs
+            .iter()
+            .map(|leg| {
+                let price = *leg.price * *leg.instrument.multiplier;
+                schedule.fee(leg.order.side, *leg.order.remaining(), price)
+            })
+            .collect();
+        if !is_spread_affordable(&mut tx, &account, legs, &fees, rate).await? {
+            tx.rollback().await?;
+            let orders = self.close_order_group(group, OrderStatus::Rejected).await?;
+            return Ok(if orders.is_empty() {
+                SpreadFillAction::Stale
+            } else {
+                SpreadFillAction::Rejected(orders)
+            });
+        }
+
+        let mut filled = Vec::with_capacity(legs.len());
+        for (leg, fee) in legs.iter().zip(fees) {
+            let settlement = Settlement {
+                currency,
+                rate,
+                multiplier: *leg.instrument.multiplier,
+                margin: leg.instrument.initial_margin.map(Money::get),
+            };
+            let quantity = leg.order.remaining();
+            let Some((order, fill)) = execute(
+                &mut tx, &account, leg.order, quantity, leg.price, fee, settlement,
+            )
+            .await?
+            else {
+                tx.rollback().await?;
+                return Ok(SpreadFillAction::Stale);
+            };
+            if account.auto_convert && currency != account.base_currency {
+                auto_convert(&mut tx, &account, &fill, settlement).await?;
+            }
+            filled.push((order, fill));
+        }
+
+        tx.commit().await?;
+        Ok(SpreadFillAction::Filled(filled))
+    }
+
+    /// Take an open order off the book with the given status
+    ///
+    /// Any children waiting on the order are cancelled with it. Returns `None` if the order is no longer open.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn close_order(
+        &self,
+        order: &Order,
+        status: OrderStatus,
+    ) -> Result<Option<Order>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(order) = sqlx::query_file_as!(
+            Order,
+            "queries/update_order_close.sql",
+            order.id,
+            status as OrderStatus
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        orders::cancel_children(&mut tx, &[order.id]).await?;
+        tx.commit().await?;
+        Ok(Some(order))
+    }
+}
+
+/// The currency a fill settles in, what it's worth in its account's base
+/// currency, and the cash each unit of its price is worth
+#[derive(Debug, Clone, Copy)]
+pub struct Settlement<'a> {
+    /// The traded symbol's currency
+    pub currency: &'a str,
+    /// The market's units of the account's base currency a unit of it
+    pub rate: Decimal,
+    /// The traded symbol's multiplier, see
+    /// [`Instrument::multiplier`](crate::models::Instrument::multiplier)
+    pub multiplier: Decimal,
+    /// The initial margin each contract requires, in its currency, if the
+    /// traded symbol is marked to market as futures are, so its trades only
+    /// pay the profit they realize
+    pub margin: Option<Decimal>,
+}
+
+/// A leg of a multi-leg group, filling in full
+#[derive(Debug, Clone, Copy)]
+pub struct Leg<'a> {
+    /// The leg's order
+    pub order: &'a Order,
+    /// The price the leg fills at
+    pub price: Money,
+    /// The leg's symbol
+    pub instrument: &'a Instrument,
+}
+
+/// Fill `quantity` of an open order at `price`, paying `fee`, whether or not
+/// its account can afford it
+///
+/// The fill, its position, lot & journal changes, and the fee's, are written
+/// to `conn`, as are the opening of a filled order's pending children and
+/// the cancelling of its one-cancels-other siblings. Returns `None` if the
+/// order is no longer open.
+pub(super) async fn execute(
+    conn: &mut PgConnection,
+    account: &Account,
+    order: &Order,
+    quantity: Quantity,
+    price: Money,
+    fee: Decimal,
+    settlement: Settlement<'_>,
+) -> Result<Option<(Order, Fill)>, sqlx::Error> {
+    let Some(order) = sqlx::query_file_as!(
+        Order,
+        "queries/update_order_fill.sql",
+        order.id,
+        quantity as Quantity
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let fill = sqlx::query_file_as!(
+        Fill,
+        "queries/insert_fill.sql",
+        Uuid::new_v4(),
+        order.id,
+        order.account_id,
+        order.symbol,
+        order.side as OrderSide,
+        *quantity,
+        price as Money,
+        fee
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    trade_position(conn, account, &fill, settlement).await?;
+    if order.status == OrderStatus::Filled {
+        sqlx::query_file_as!(Order, "queries/update_orders_activate.sql", order.id)
+            .fetch_all(&mut *conn)
+            .await?;
+        orders::cancel_siblings(conn, &order).await?;
+    }
+
+    // fees are posted apart from the trade, so profit is reported both
+    // before & after them
+    if !fee.is_zero() {
+        let lines = [
+            Line::symbol(LedgerAccount::Fees, &fill.symbol, fee, Decimal::ZERO),
+            Line::cash(-fee),
+        ];
+        let kind = CashEntryKind::Fee;
+        let currency = settlement.currency;
+        ledger::post(conn, account.id, kind, Some(fill.id), currency, &lines).await?;
+    }
+    Ok(Some((order, fill)))
+}
+
+/// Whether an account can afford to fill `quantity` of an order at `price`,
+/// paying `fee`
+///
+/// Prices are for a single unit of the symbol's multiplier.
+///
+/// Cash accounts must pay in full, from the currency they trade in or, if
+/// they auto-convert, their base currency's cash too, and only sell what they
+/// hold. Margin accounts must still meet their initial margin, valuing the
+/// trade in their base currency, and only sell short what can be borrowed,
+/// unless the fill only shrinks a position. Symbols marked to market are
+/// only traded on margin, and require their margin per contract instead,
+/// shorts included.
+async fn is_affordable(
+    conn: &mut PgConnection,
+    account: &Account,
+    order: &Order,
+    quantity: Quantity,
+    price: Money,
+    fee: Decimal,
+    settlement: Settlement<'_>,
+) -> Result<bool, sqlx::Error> {
+    let (held, basis) = sqlx::query_file_as!(
+        Position,
+        "queries/select_position_lock.sql",
+        order.account_id,
+        order.symbol
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .map_or((Decimal::ZERO, Decimal::ZERO), |position| {
+        (*position.quantity, *position.cost_basis)
+    });
+    let traded = match order.side {
+        OrderSide::Buy => *quantity,
+        OrderSide::Sell => -*quantity,
+    };
+    let after = held + traded;
+    let price = *price * settlement.multiplier;
+    Ok(match account.account_type {
+        AccountType::Cash if settlement.margin.is_some() => false,
+        AccountType::Cash => match order.side {
+            OrderSide::Buy => {
+                let mut cash =
+                    conversions::cash_balance(conn, account, settlement.currency).await?;
+                if account.auto_convert && settlement.currency != account.base_currency {
+                    cash += *account.cash * account.fx_rate(Decimal::ONE / settlement.rate);
+                }
+                cash >= *quantity * price + fee
+            }
+            OrderSide::Sell => after >= Decimal::ZERO,
+        },
+        // fills that only shrink a position are always allowed, so
+        // liquidations are never refused
+        AccountType::Margin if shrinks(held, after) => true,
+        AccountType::Margin if settlement.margin.is_some() => {
+            let (net, gross, futures) = margin::position_totals(conn, account.id).await?;
+            let added = (after.abs() - held.abs()) * settlement.margin.unwrap_or_default();
+            let equity = *account.cash - fee * settlement.rate + net;
+            let required = gross * account.initial_margin / Decimal::ONE_HUNDRED
+                + futures
+                + added * settlement.rate;
+            equity >= required
+        }
+        AccountType::Margin => {
+            let rate = settlement.rate;
+            let cash = *account.cash - (traded * price + fee) * rate;
+            (after >= Decimal::ZERO || borrows::is_borrowable(conn, &order.symbol).await?)
+                && meets_initial_margin(conn, account, basis * rate, after, cash, price * rate)
+                    .await?
+        }
+    })
+}
+
+/// Whether a fill taking a position from `held` to `after` only closes some or
+/// all of it
+///
+/// Fills crossing zero close the position & open the other way, so don't.
+fn shrinks(held: Decimal, after: Decimal) -> bool {
+    after.is_zero()
+        || after.is_sign_negative() == held.is_sign_negative() && after.abs() <= held.abs()
+}
+
+/// Whether an account still meets its initial margin after a fill, leaving
+/// it `cash` & holding `after` of the traded symbol
+///
+/// The traded symbol is valued at the fill's price, and the account's other
+/// positions at cost, both in the account's base currency.
+async fn meets_initial_margin(
+    conn: &mut PgConnection,
+    account: &Account,
+    basis: Decimal,
+    after: Decimal,
+    cash: Decimal,
+    price: Decimal,
+) -> Result<bool, sqlx::Error> {
+    let (net, gross, futures) = margin::position_totals(conn, account.id).await?;
+    let equity = cash + net - basis + after * price;
+    let exposure = gross - basis.abs() + after.abs() * price;
+    Ok(equity >= exposure * account.initial_margin / Decimal::ONE_HUNDRED + futures)
+}
+
+/// Whether an account can afford to fill every leg of a multi-leg group,
+/// each paying its fee, settling in the first leg's currency at `rate`
+///
+/// Cash accounts must pay the group's net debit in full, fees included, as
+/// [`is_affordable`] has them pay an order, and only sell what they hold.
+/// Margin accounts must still meet their initial margin, as the legs are
+/// margined together, see [`requirement`], with their other positions at
+/// cost, and only sell short what can be borrowed, unless every leg only
+/// shrinks a position. Futures legs are only traded on margin, and pay
+/// nothing for what they hold.
+async fn is_spread_affordable(
+    conn: &mut PgConnection,
+    account: &Account,
+    legs: &[Leg<'_>],
+    fees: &[Decimal],
+    rate: Decimal,
+) -> Result<bool, sqlx::Error> {
+    let (mut net, mut gross, mut futures) = margin::position_totals(conn, account.id).await?;
+    let mut cost = Decimal::ZERO;
+    let mut shrinking = true;
+    let mut holdings = Vec::with_capacity(legs.len());
+    for (leg, fee) in legs.iter().zip(fees) {
+        let (held, basis) = sqlx::query_file_as!(
+            Position,
+            "queries/select_position_lock.sql",
+            account.id,
+            leg.order.symbol
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .map_or((Decimal::ZERO, Decimal::ZERO), |position| {
+            (*position.quantity, *position.cost_basis)
+        });
+        let traded = match leg.order.side {
+            OrderSide::Buy => *leg.order.remaining(),
+            OrderSide::Sell => -*leg.order.remaining(),
+        };
+        let after = held + traded;
+        let short = after < Decimal::ZERO && !shrinks(held, after);
+        let future = leg.instrument.is_future();
+        let allowed = match account.account_type {
+            AccountType::Cash if future => false,
+            AccountType::Cash => after >= Decimal::ZERO || traded > Decimal::ZERO,
+            AccountType::Margin => {
+                !short || borrows::is_borrowable(conn, &leg.order.symbol).await?
+            }
+        };
+        if !allowed {
+            return Ok(false);
+        }
+        shrinking &= shrinks(held, after);
+        let price = *leg.price * *leg.instrument.multiplier;
+        cost += fee;
+        if future {
+            let margin = *leg.instrument.initial_margin.unwrap_or_default();
+            futures -= held.abs() * margin;
+        } else {
+            cost += traded * price;
+            net -= basis * rate;
+            gross -= (basis * rate).abs();
+        }
+        holdings.push(Holding {
+            instrument: leg.instrument,
+            quantity: after,
+            // futures are worth only what they've made since they filled
+            value: if future {
+                Decimal::ZERO
+            } else {
+                after * price * rate
+            },
+            scale: Some(*leg.instrument.multiplier * rate),
+        });
+    }
+    Ok(match account.account_type {
+        AccountType::Cash => {
+            let currency = &legs[0].instrument.currency;
+            let mut cash = conversions::cash_balance(conn, account, currency).await?;
+            if account.auto_convert && *currency != account.base_currency {
+                cash += *account.cash * account.fx_rate(Decimal::ONE / rate);
+            }
+            cash >= cost
+        }
+        AccountType::Margin if shrinking => true,
+        AccountType::Margin => {
+            let value: Decimal = holdings.iter().map(|holding| holding.value).sum();
+            let equity = *account.cash - cost * rate + net + value;
+            let required = gross * account.initial_margin / Decimal::ONE_HUNDRED
+                + futures
+                + requirement(&holdings, account, Requirement::Initial);
+            equity >= required
+        }
+    })
+}
+
+/// Apply a fill to its account's position & lots
+///
+/// A fill first closes any position on the other side, relieving its lots by
+/// the account's [`LotMethod`] & realizing the difference between the fill's
+/// price & theirs. Whatever's left opens a new lot, long for buys & short for
+/// sells. The fill's fee is tracked apart from the realized profit.
+///
+/// Lots are priced as the fill is, while cost & profit are in cash, so are
+/// scaled by the settlement's multiplier. The trade is posted to the
+/// account's journal in the settlement's currency, see [`post_trade`].
+async fn trade_position(
+    conn: &mut PgConnection,
+    account: &Account,
+    fill: &Fill,
+    settlement: Settlement<'_>,
+) -> Result<Position, sqlx::Error> {
+    let multiplier = settlement.multiplier;
+    let position = sqlx::query_file_as!(
+        Position,
+        "queries/select_position_lock.sql",
+        account.id,
+        fill.symbol
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let (held, basis) = position.map_or((Decimal::ZERO, Decimal::ZERO), |p| {
+        (*p.quantity, *p.cost_basis)
+    });
+    let mut cost_basis = basis;
+    let traded = match fill.side {
+        OrderSide::Buy => *fill.quantity,
+        OrderSide::Sell => -*fill.quantity,
+    };
+
+    let closing = if held * traded < Decimal::ZERO {
+        held.abs().min(*fill.quantity)
+    } else {
+        Decimal::ZERO
+    };
+    let mut realized = Decimal::ZERO;
+    if !closing.is_zero() {
+        let lots = sqlx::query_file_as!(
+            Lot,
+            "queries/select_lots_open_lock.sql",
+            account.id,
+            fill.symbol
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for relief in lots::relieve(lots, account.lot_method, closing) {
+            sqlx::query_file!(
+                "queries/update_lot_relieve.sql",
+                relief.lot_id,
+                relief.quantity
+            )
+            .execute(&mut *conn)
+            .await?;
+            cost_basis -= relief.quantity * relief.price * multiplier;
+            realized += relief.quantity * (*fill.price - relief.price) * multiplier;
+        }
+    }
+    let opening = match fill.side {
+        OrderSide::Buy => *fill.quantity - closing,
+        OrderSide::Sell => closing - *fill.quantity,
+    };
+    if !opening.is_zero() {
+        sqlx::query_file_as!(
+            Lot,
+            "queries/insert_lot.sql",
+            account.id,
+            fill.symbol,
+            fill.id,
+            opening,
+            fill.price as Money,
+            fill.created_at
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        cost_basis += opening * *fill.price * multiplier;
+    }
+
+    // realized profit is added to what the position has already realized
+    let position = sqlx::query_file_as!(
+        Position,
+        "queries/upsert_position.sql",
+        account.id,
+        fill.symbol,
+        held + traded,
+        cost_basis,
+        realized,
+        fill.fee as Money
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    post_trade(
+        conn,
+        account,
+        fill,
+        settlement,
+        cost_basis - basis,
+        realized,
+    )
+    .await?;
+    Ok(position)
+}
+
+/// Post a trade to its account's journal, moving `cost` of what's held & the
+/// `realized` profit against the cash paid or received
+///
+/// Symbols marked to market are held without paying for them, so their cost
+/// moves against the [`LedgerAccount::Notional`] ledger instead, & only the
+/// profit realized is paid.
+async fn post_trade(
+    conn: &mut PgConnection,
+    account: &Account,
+    fill: &Fill,
+    settlement: Settlement<'_>,
+    cost: Decimal,
+    realized: Decimal,
+) -> Result<(), sqlx::Error> {
+    let traded = match fill.side {
+        OrderSide::Buy => *fill.quantity,
+        OrderSide::Sell => -*fill.quantity,
+    };
+    let (paid, notional) = if settlement.margin.is_some() {
+        (realized, -cost)
+    } else {
+        (-traded * *fill.price * settlement.multiplier, Decimal::ZERO)
+    };
+    let lines = [
+        Line::symbol(LedgerAccount::Securities, &fill.symbol, cost, traded),
+        Line::symbol(
+            LedgerAccount::RealizedPnl,
+            &fill.symbol,
+            -realized,
+            Decimal::ZERO,
+        ),
+        Line::symbol(
+            LedgerAccount::Notional,
+            &fill.symbol,
+            notional,
+            Decimal::ZERO,
+        ),
+        Line::cash(paid),
+    ];
+    let kind = CashEntryKind::Trade;
+    let currency = settlement.currency;
+    ledger::post(conn, account.id, kind, Some(fill.id), currency, &lines).await
+}
+
+/// Convert between an account's base currency & the currency a fill settled
+/// in, as the account auto-converts
+///
+/// Buys convert as much of the base currency as covers what the account is
+/// left short of the settlement currency, to its minor unit. Sells convert
+/// their proceeds, net of fees, back to the base currency, as far as the
+/// account still holds them. Trades in symbols marked to market only convert what
+/// the account is left short, either way.
+async fn auto_convert(
+    conn: &mut PgConnection,
+    account: &Account,
+    fill: &Fill,
+    settlement: Settlement<'_>,
+) -> Result<(), sqlx::Error> {
+    let held = conversions::cash_balance(conn, account, settlement.currency).await?;
+    let base = account.base_currency.as_str();
+    // trades marked to market raise no proceeds, only convert what they owe
+    let side = if settlement.margin.is_some() {
+        OrderSide::Buy
+    } else {
+        fill.side
+    };
+    match side {
+        OrderSide::Buy if held < Decimal::ZERO => {
+            let rate = account.fx_rate(Decimal::ONE / settlement.rate);
+            let away = RoundingStrategy::AwayFromZero;
+            let needed = *Money::round_minor_with(-held, settlement.currency, away);
+            let amount = *Money::round_minor_with(needed / rate, base, away);
+            conversions::exchange(
+                conn,
+                account.id,
+                Some(fill.id),
+                base,
+                settlement.currency,
+                amount,
+                rate,
+            )
+            .await?;
+        }
+        OrderSide::Sell => {
+            let amount =
+                (*fill.quantity * *fill.price * settlement.multiplier - *fill.fee).min(held);
+            if amount > Decimal::ZERO {
+                conversions::exchange(
+                    conn,
+                    account.id,
+                    Some(fill.id),
+                    settlement.currency,
+                    base,
+                    amount,
+                    account.fx_rate(settlement.rate),
+                )
+                .await?;
+            }
+        }
+        OrderSide::Buy => {}
+    }
+    Ok(())
+}
+
+/// The result of filling a multi-leg group
+#[derive(Debug)]
+pub enum SpreadFillAction {
+    /// Every leg traded, returning the updated orders
+    Filled(Vec<(Order, Fill)>),
+    /// The group was rejected, returning its legs
+    Rejected(Vec<Order>),
+    /// A leg was no longer open
+    Stale,
+}
+
+/// The result of filling an order
+#[derive(Debug)]
+pub enum FillAction {
+    /// The order traded, returning the updated order
+    Filled(Order, Fill),
+    /// The order was rejected
+    Rejected(Order),
+    /// The order was no longer open
+    Stale,
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::{AccountSettings, NewOrder};
+
+    const USD: Settlement = Settlement {
+        currency: "USD",
+        rate: Decimal::ONE,
+        multiplier: Decimal::ONE,
+        margin: None,
+    };
+
+    /// Places & fills a market order for GME, which is hard to borrow, in
+    /// full at 150
+    async fn trade(
+        database: &Database,
+        account: &Account,
+        side: OrderSide,
+        quantity: u32,
+    ) -> FillAction {
+        let order = NewOrder {
+            symbol: "GME".to_owned(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Quantity::from(quantity),
+            limit_price: None,
+            stop_price: None,
+            trail_amount: None,
+            trail_percent: None,
+            time_in_force: TimeInForce::default(),
+            expires_at: None,
+            extended_hours: false,
+            origin: OrderOrigin::default(),
+            quoted_price: Some(Money::from(150)),
+        };
+        let order = database.add_order(account.id, &order, None).await.unwrap();
+        database
+            .fill_order(&order, order.quantity, Money::from(150), USD)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn selling_through_a_long_borrows_what_it_shorts(pool: PgPool) {
+        let database = Database::new(pool).await.unwrap();
+        let user = database
+            .add_user(
+                "trader",
+                "hashed",
+                "Default",
+                Money::from(100_000),
+                "retail",
+            )
+            .await
+            .unwrap();
+        let account = database.get_accounts(user.id).await.unwrap().remove(0);
+        let settings = AccountSettings {
+            account_type: Some(AccountType::Margin),
+            ..AccountSettings::default()
+        };
+        database
+            .update_account_settings(account.id, &settings)
+            .await
+            .unwrap();
+        let bought = trade(&database, &account, OrderSide::Buy, 10).await;
+        assert!(matches!(bought, FillAction::Filled(..)));
+
+        let flipped = trade(&database, &account, OrderSide::Sell, 15).await;
+        let closed = trade(&database, &account, OrderSide::Sell, 10).await;
+
+        assert!(matches!(flipped, FillAction::Rejected(..)));
+        assert!(matches!(closed, FillAction::Filled(..)));
+    }
+}
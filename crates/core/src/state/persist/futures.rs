@@ -0,0 +1,155 @@
+use rust_decimal::Decimal;
+use time::Date;
+
+use super::{
+    fills::Settlement,
+    ledger::{self, Line},
+    options::{settle, settlement_order},
+    Database,
+};
+use crate::{
+    models::{
+        Account, AccountType, AssetClass, CashEntryKind, Instrument, LedgerAccount, LotMethod,
+        OptionType, OrderOrigin, OrderSide, Position, SlippageKind,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Get every listed future yet to settle on `date`, soonest to expire
+    /// first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_unsettled_futures(&self, date: Date) -> Result<Vec<Instrument>, sqlx::Error> {
+        sqlx::query_file_as!(Instrument, "queries/select_futures_unsettled.sql", date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Settle every position in a future at `price`, as of `date`
+    ///
+    /// Each position is marked to the price, paying or taking its variation
+    /// margin, what it's made or lost since it last settled, in cash. This is
+    /// realized, & its open lots repriced to the settlement price. Futures
+    /// that expired before `date` settle for the last time, so are delisted
+    /// & each position closed by a settlement order filled at the price.
+    /// Settlements pay no fees, and aren't checked against what their
+    /// accounts can afford. Everything's written in a single transaction.
+    ///
+    /// Returns the number of positions settled, or `None` if the future
+    /// already was on `date`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn settle_future(
+        &self,
+        future: &Instrument,
+        price: Decimal,
+        date: Date,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if sqlx::query_file_scalar!("queries/update_instrument_settled.sql", future.symbol, date)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_none()
+        {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        let positions = sqlx::query_file_as!(
+            Position,
+            "queries/select_positions_held_lock.sql",
+            future.symbol
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let expired = future
+            .expires_on
+            .is_some_and(|expires_on| expires_on < date);
+        for position in &positions {
+            let variation = *position.quantity * price * *future.multiplier - *position.cost_basis;
+            sqlx::query_file!(
+                "queries/update_position_mark.sql",
+                position.account_id,
+                future.symbol,
+                variation
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query_file!(
+                "queries/update_lots_mark.sql",
+                position.account_id,
+                future.symbol,
+                price
+            )
+            .execute(&mut *tx)
+            .await?;
+            if !variation.is_zero() {
+                let lines = [
+                    Line::cash(variation),
+                    Line::symbol(
+                        LedgerAccount::RealizedPnl,
+                        &future.symbol,
+                        -variation,
+                        Decimal::ZERO,
+                    ),
+                    Line::symbol(
+                        LedgerAccount::Securities,
+                        &future.symbol,
+                        variation,
+                        Decimal::ZERO,
+                    ),
+                    Line::symbol(
+                        LedgerAccount::Notional,
+                        &future.symbol,
+                        -variation,
+                        Decimal::ZERO,
+                    ),
+                ];
+                let kind = CashEntryKind::VariationMargin;
+                let currency = &future.currency;
+                ledger::post(&mut tx, position.account_id, kind, None, currency, &lines).await?;
+            }
+            if !expired {
+                continue;
+            }
+            let account = sqlx::query_file_as!(
+                Account,
+                "queries/select_account_lock.sql",
+                position.account_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let closing = settlement_order(
+                future.symbol.clone(),
+                if position.quantity > Quantity::ZERO {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                },
+                position.quantity.abs(),
+                OrderOrigin::Settlement,
+            );
+            let settlement = Settlement {
+                currency: &future.currency,
+                rate: Decimal::ONE,
+                multiplier: *future.multiplier,
+                margin: future.initial_margin.map(Money::get),
+            };
+            settle(&mut tx, &account, &closing, Money::round(price), settlement).await?;
+        }
+        tx.commit().await?;
+        Ok(Some(positions.len() as u64))
+    }
+}
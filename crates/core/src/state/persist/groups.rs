@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use super::{orders, Database};
+use crate::{
+    models::{
+        NewOrder, Order, OrderGroup, OrderGroupKind, OrderOrigin, OrderSide, OrderStatus,
+        OrderType, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Place a group of orders for the given account
+    ///
+    /// The group & its orders are written in a single transaction. With a
+    /// `parent`, the other orders wait, pending, for it to fill. Each order is
+    /// given with the reference it trails, for trailing stops. Multi-leg
+    /// groups are given with their quantity & net limit price.
+    ///
+    /// Returns the group & its orders, the parent first.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_order_group(
+        &self,
+        account_id: i32,
+        kind: OrderGroupKind,
+        spread: Option<(Quantity, Money)>,
+        parent: Option<(&NewOrder, Option<Money>)>,
+        children: &[(NewOrder, Option<Money>)],
+    ) -> Result<(OrderGroup, Vec<Order>), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let group = sqlx::query_file_as!(
+            OrderGroup,
+            "queries/insert_order_group.sql",
+            Uuid::new_v4(),
+            account_id,
+            kind as OrderGroupKind,
+            spread.map(|(quantity, _)| quantity) as Option<Quantity>,
+            spread.map(|(_, limit_price)| limit_price) as Option<Money>
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut placed = Vec::with_capacity(children.len() + 1);
+        let mut parent_id = None;
+        if let Some((order, trail_reference)) = parent {
+            let order = orders::insert(
+                &mut tx,
+                account_id,
+                order,
+                trail_reference,
+                Some((group.id, None)),
+                OrderStatus::Open,
+            )
+            .await?;
+            parent_id = Some(order.id);
+            placed.push(order);
+        }
+        let status = parent_id.map_or(OrderStatus::Open, |_| OrderStatus::Pending);
+        for (order, trail_reference) in children {
+            let order = orders::insert(
+                &mut tx,
+                account_id,
+                order,
+                *trail_reference,
+                Some((group.id, parent_id)),
+                status,
+            )
+            .await?;
+            placed.push(order);
+        }
+
+        tx.commit().await?;
+        Ok((group, placed))
+    }
+
+    /// Get all of an account's order groups, newest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_order_groups(&self, account_id: i32) -> Result<Vec<OrderGroup>, sqlx::Error> {
+        sqlx::query_file_as!(OrderGroup, "queries/select_order_groups.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get one of a user's order groups by its uuid
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_order_group(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<OrderGroup>, sqlx::Error> {
+        sqlx::query_file_as!(
+            OrderGroup,
+            "queries/select_order_group_uuid.sql",
+            uuid,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Get all of an account's orders that belong to a group, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_grouped_orders(&self, account_id: i32) -> Result<Vec<Order>, sqlx::Error> {
+        sqlx::query_file_as!(Order, "queries/select_orders_grouped.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Cancel every open or pending order of a group, all at once
+    ///
+    /// Returns the cancelled orders.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn cancel_order_group(&self, group: &OrderGroup) -> Result<Vec<Order>, sqlx::Error> {
+        sqlx::query_file_as!(Order, "queries/update_orders_cancel_group.sql", group.id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get every multi-leg group with open legs, oldest first, each with its
+    /// open legs
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_open_spreads(&self) -> Result<Vec<(OrderGroup, Vec<Order>)>, sqlx::Error> {
+        let mut legs: HashMap<_, Vec<_>> = HashMap::new();
+        for order in sqlx::query_file_as!(Order, "queries/select_orders_open_legs.sql")
+            .fetch_all(&self.pool)
+            .await?
+        {
+            legs.entry(order.group_id).or_default().push(order);
+        }
+        let groups =
+            sqlx::query_file_as!(OrderGroup, "queries/select_order_groups_open_multi_leg.sql")
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(groups
+            .into_iter()
+            .filter_map(|group| {
+                let legs = legs.remove(&Some(group.id))?;
+                Some((group, legs))
+            })
+            .collect())
+    }
+
+    /// Take every open order of a group off the book with the given status,
+    /// all at once
+    ///
+    /// Returns the closed orders.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn close_order_group(
+        &self,
+        group: &OrderGroup,
+        status: OrderStatus,
+    ) -> Result<Vec<Order>, sqlx::Error> {
+        sqlx::query_file_as!(
+            Order,
+            "queries/update_orders_close_group.sql",
+            group.id,
+            status as OrderStatus
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,158 @@
+use super::Database;
+use crate::{
+    models::{AssetClass, Instrument, OptionType},
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Get a symbol from the registry, if it's listed
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_instrument(&self, symbol: &str) -> Result<Option<Instrument>, sqlx::Error> {
+        sqlx::query_file_as!(Instrument, "queries/select_instrument.sql", symbol)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Get a symbol from the registry, or how it's held if it isn't listed
+    ///
+    /// See [`Instrument::unlisted`].
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_instrument_or_unlisted(
+        &self,
+        symbol: &str,
+    ) -> Result<Instrument, sqlx::Error> {
+        Ok(self
+            .get_instrument(symbol)
+            .await?
+            .unwrap_or_else(|| Instrument::unlisted(symbol.to_owned())))
+    }
+
+    /// Search the registry for up to `limit` symbols starting with `query`,
+    /// or named with it, exact symbols first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn search_instruments(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<Instrument>, sqlx::Error> {
+        // the query is matched literally, not as a pattern
+        let query = query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        sqlx::query_file_as!(
+            Instrument,
+            "queries/select_instruments_search.sql",
+            query,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Store symbols in the registry, replacing those already listed
+    ///
+    /// Returns the number of symbols written.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_instruments(&self, instruments: &[Instrument]) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+        for instrument in instruments {
+            written += sqlx::query_file!(
+                "queries/upsert_instrument.sql",
+                instrument.symbol,
+                instrument.name,
+                instrument.exchange,
+                instrument.asset_class as AssetClass,
+                instrument.currency,
+                instrument.tick_size as Money,
+                instrument.lot_size as Quantity,
+                instrument.fractional,
+                instrument.shortable,
+                instrument.active,
+                instrument.multiplier as Quantity,
+                instrument.underlying,
+                instrument.option_type as Option<OptionType>,
+                instrument.strike as Option<Money>,
+                instrument.expires_on,
+                instrument.initial_margin as Option<Money>,
+                instrument.maintenance_margin as Option<Money>,
+                instrument.roll_on
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(written)
+    }
+
+    /// List symbols in the registry, leaving those already listed as they
+    /// are
+    ///
+    /// Returns the number of symbols newly listed.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn list_instruments(&self, instruments: &[Instrument]) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+        for instrument in instruments {
+            written += sqlx::query_file!(
+                "queries/insert_instrument.sql",
+                instrument.symbol,
+                instrument.name,
+                instrument.exchange,
+                instrument.asset_class as AssetClass,
+                instrument.currency,
+                instrument.tick_size as Money,
+                instrument.lot_size as Quantity,
+                instrument.fractional,
+                instrument.shortable,
+                instrument.active,
+                instrument.multiplier as Quantity,
+                instrument.underlying,
+                instrument.option_type as Option<OptionType>,
+                instrument.strike as Option<Money>,
+                instrument.expires_on,
+                instrument.initial_margin as Option<Money>,
+                instrument.maintenance_margin as Option<Money>,
+                instrument.roll_on
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(written)
+    }
+}
@@ -0,0 +1,259 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use time::Date;
+
+use super::{
+    ledger::{self, Line},
+    Database,
+};
+use crate::{
+    models::{
+        Account, AccountType, CashBalance, CashEntryKind, InterestAccrual, InterestKind,
+        InterestRate, LedgerAccount, LotMethod, SlippageKind,
+    },
+    numeric::Money,
+};
+
+impl Database {
+    /// Get every interest rate, by currency & kind, latest to take effect
+    /// first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_interest_rates(&self) -> Result<Vec<InterestRate>, sqlx::Error> {
+        sqlx::query_file_as!(InterestRate, "queries/select_interest_rates.sql")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Set the interest on cash in a currency from `effective_on`, replacing
+    /// any rate already set to take effect then
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn set_interest_rate(
+        &self,
+        currency: &str,
+        kind: InterestKind,
+        effective_on: Date,
+        annual_rate: Decimal,
+    ) -> Result<InterestRate, sqlx::Error> {
+        sqlx::query_file_as!(
+            InterestRate,
+            "queries/upsert_interest_rate.sql",
+            currency,
+            kind as InterestKind,
+            effective_on,
+            annual_rate
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Get an account's interest accruals, newest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_interest_accruals(
+        &self,
+        account_id: i32,
+    ) -> Result<Vec<InterestAccrual>, sqlx::Error> {
+        sqlx::query_file_as!(
+            InterestAccrual,
+            "queries/select_interest_accruals.sql",
+            account_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Get the cash every account held in each currency, its base
+    /// currency's included, at the close of each day, in UTC, it hasn't yet
+    /// accrued interest for, through `through`
+    ///
+    /// Balances are rebuilt from the journal, from the day after each last
+    /// accrued, or from its first entry if it never has, oldest day first.
+    /// Days it held nothing are skipped.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_unaccrued_balances(
+        &self,
+        through: Date,
+    ) -> Result<Vec<(Date, CashBalance)>, sqlx::Error> {
+        let balances = sqlx::query_file!("queries/select_cash_balances_unaccrued.sql", through)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|balance| {
+                let cash = CashBalance {
+                    account_id: balance.account_id,
+                    currency: balance.currency,
+                    amount: Money::round(balance.amount),
+                };
+                (balance.date, cash)
+            })
+            .collect();
+        Ok(balances)
+    }
+
+    /// Accrue a day's interest on the cash an account held in a currency at
+    /// the close of `date`
+    ///
+    /// Positive balances earn the currency's credit rate, and negative ones,
+    /// borrowed on margin, pay its debit rate, each the rate in effect on
+    /// `date`. Currencies without one accrue nothing. The accrual is only
+    /// recorded, to be posted with the rest of its month's by
+    /// [`Self::post_interest`]. Each balance accrues at most once a date.
+    ///
+    /// Returns the interest, or `None` if the balance is zero or already
+    /// accrued.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn accrue_interest(
+        &self,
+        balance: &CashBalance,
+        date: Date,
+    ) -> Result<Option<Decimal>, sqlx::Error> {
+        let amount = *balance.amount;
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        let kind = if amount > Decimal::ZERO {
+            InterestKind::Credit
+        } else {
+            InterestKind::Debit
+        };
+        let rate = sqlx::query_file_as!(
+            InterestRate,
+            "queries/select_interest_rate_effective.sql",
+            balance.currency,
+            kind as InterestKind,
+            date
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let annual_rate = rate.as_ref().map_or(Decimal::ZERO, |rate| rate.annual_rate);
+        let interest = rate.map_or(Decimal::ZERO, |rate| rate.daily_interest(amount));
+        let accrued = sqlx::query_file_scalar!(
+            "queries/insert_interest_accrual.sql",
+            balance.account_id,
+            balance.currency,
+            date,
+            kind as InterestKind,
+            amount,
+            annual_rate,
+            interest
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(accrued.map(|_| interest))
+    }
+
+    /// Get the interest every account has accrued in each currency before a
+    /// date, yet to be posted
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_unposted_interest(
+        &self,
+        before: Date,
+    ) -> Result<Vec<CashBalance>, sqlx::Error> {
+        sqlx::query_file_as!(
+            CashBalance,
+            "queries/select_interest_accruals_unposted.sql",
+            before
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Post the interest an account accrued in a currency before `before`,
+    /// the first day of a month
+    ///
+    /// The accruals are summed & rounded to the currency's minor unit, then
+    /// posted as a single [`CashEntryKind::Interest`] entry taking effect at
+    /// the start of `before`, moving the account's cash in the currency. The
+    /// entry & the accruals being marked posted are written in a single
+    /// transaction.
+    ///
+    /// Returns the interest posted, or `None` if there was none left to post.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn post_interest(
+        &self,
+        account_id: i32,
+        currency: &str,
+        before: Date,
+    ) -> Result<Option<Decimal>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query_file_as!(Account, "queries/select_account_lock.sql", account_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let accruals = sqlx::query_file_scalar!(
+            "queries/update_interest_accruals_posted.sql",
+            account_id,
+            currency,
+            before
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if accruals.is_empty() {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        let interest = *Money::round_minor_with(
+            accruals.into_iter().sum(),
+            currency,
+            RoundingStrategy::MidpointAwayFromZero,
+        );
+        if !interest.is_zero() {
+            let lines = [
+                Line::cash(interest),
+                Line::currency(LedgerAccount::Interest, -interest),
+            ];
+            let kind = CashEntryKind::Interest;
+            let effective_at = before.midnight().assume_utc();
+            ledger::post_effective(&mut tx, account_id, kind, effective_at, currency, &lines)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(Some(interest))
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+
+use super::{accounts::insert_cash_entry, Database};
+use crate::{
+    models::{
+        Account, AccountType, Balance, CashBalance, CashEntryKind, Drift, LedgerAccount, LotMethod,
+        Position, SlippageKind,
+    },
+    numeric::{Money, Quantity},
+};
+
+/// An account's balances by ledger, symbol & currency, as the journal has them
+type Balances = HashMap<(i32, LedgerAccount, Option<String>, String), (Decimal, Decimal)>;
+/// An account's balances by ledger & symbol, or by currency for its cash
+type Reconciled = HashMap<(i32, LedgerAccount, Option<String>), (Decimal, Decimal)>;
+
+impl Database {
+    /// Rebuild every account's balances from the journal, reporting those
+    /// recorded elsewhere that disagree with it
+    ///
+    /// Cash is checked against each account, in each currency it holds, and
+    /// quantity, cost basis, realized profit, fees & dividends against each
+    /// position, as are the position's open lots. Journal balances with
+    /// nothing recorded for them, and journal entries that don't balance, are
+    /// reported too. Everything is read from a single snapshot, so trades
+    /// posted meanwhile don't show up as drift.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn reconcile(&self) -> Result<Vec<Drift>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("Set Transaction Isolation Level Repeatable Read, Read Only")
+            .execute(&mut *tx)
+            .await?;
+        // cash is kept by currency, and everything else by symbol
+        let mut journal = Reconciled::new();
+        for ((account_id, ledger, symbol, currency), (amount, quantity)) in
+            balances(&mut tx, None).await?
+        {
+            let symbol = match ledger {
+                LedgerAccount::Cash => Some(currency),
+                _ => symbol,
+            };
+            let balance = journal.entry((account_id, ledger, symbol)).or_default();
+            balance.0 += amount;
+            balance.1 += quantity;
+        }
+        let mut drift = Vec::new();
+        check_cash(&mut tx, &mut drift, &mut journal).await?;
+        let mut lots: HashMap<_, _> = sqlx::query_file!("queries/select_lot_totals.sql")
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|lot| ((lot.account_id, lot.symbol), lot.quantity))
+            .collect();
+        for position in sqlx::query_file_as!(Position, "queries/select_positions_all.sql")
+            .fetch_all(&mut *tx)
+            .await?
+        {
+            let open = lots
+                .remove(&(position.account_id, position.symbol.clone()))
+                .unwrap_or_default();
+            check_position(&mut drift, &mut journal, &position, open);
+        }
+        for ((account_id, ledger, symbol), (amount, quantity)) in journal {
+            let recorded = match ledger {
+                LedgerAccount::Capital
+                | LedgerAccount::Exchange
+                | LedgerAccount::Notional
+                | LedgerAccount::Interest => continue,
+                LedgerAccount::Cash => Balance::Cash,
+                LedgerAccount::Securities => {
+                    let symbol = symbol.clone();
+                    check(
+                        &mut drift,
+                        account_id,
+                        Balance::Quantity,
+                        symbol,
+                        quantity,
+                        Decimal::ZERO,
+                    );
+                    Balance::CostBasis
+                }
+                LedgerAccount::RealizedPnl => Balance::RealizedPnl,
+                LedgerAccount::Fees => Balance::Fees,
+                LedgerAccount::BorrowFees => Balance::BorrowFees,
+                LedgerAccount::Dividends => Balance::Dividends,
+            };
+            check(
+                &mut drift,
+                account_id,
+                recorded,
+                symbol,
+                amount,
+                Decimal::ZERO,
+            );
+        }
+        for ((account_id, symbol), quantity) in lots {
+            check(
+                &mut drift,
+                account_id,
+                Balance::Lots,
+                Some(symbol),
+                Decimal::ZERO,
+                quantity,
+            );
+        }
+        for entry in sqlx::query_file!("queries/select_journal_unbalanced.sql")
+            .fetch_all(&mut *tx)
+            .await?
+        {
+            let balance = Balance::Entry(entry.id);
+            check(
+                &mut drift,
+                entry.account_id,
+                balance,
+                None,
+                entry.amount,
+                Decimal::ZERO,
+            );
+        }
+        tx.commit().await?;
+        drift.sort_by(|a, b| (a.account_id, &a.symbol).cmp(&(b.account_id, &b.symbol)));
+        Ok(drift)
+    }
+}
+
+/// One side of a journal entry, debiting its ledger when positive & crediting
+/// it when negative
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Line<'a> {
+    /// The ledger posted to
+    ledger: LedgerAccount,
+    /// The symbol the ledger is kept for, if any
+    symbol: Option<&'a str>,
+    /// The amount debited
+    amount: Decimal,
+    /// The quantity of the symbol moved
+    quantity: Decimal,
+}
+
+impl<'a> Line<'a> {
+    /// A movement of an account's cash
+    pub(super) const fn cash(amount: Decimal) -> Self {
+        Self {
+            ledger: LedgerAccount::Cash,
+            symbol: None,
+            amount,
+            quantity: Decimal::ZERO,
+        }
+    }
+
+    /// A movement of what's been deposited into an account
+    pub(super) const fn capital(amount: Decimal) -> Self {
+        Self {
+            ledger: LedgerAccount::Capital,
+            symbol: None,
+            amount,
+            quantity: Decimal::ZERO,
+        }
+    }
+
+    /// A movement of a ledger kept for the entry's currency as a whole
+    pub(super) const fn currency(ledger: LedgerAccount, amount: Decimal) -> Self {
+        Self {
+            ledger,
+            symbol: None,
+            amount,
+            quantity: Decimal::ZERO,
+        }
+    }
+
+    /// A movement of a ledger kept for a symbol, moving `quantity` of it for
+    /// [`LedgerAccount::Securities`]
+    pub(super) const fn symbol(
+        ledger: LedgerAccount,
+        symbol: &'a str,
+        amount: Decimal,
+        quantity: Decimal,
+    ) -> Self {
+        Self {
+            ledger,
+            symbol: Some(symbol),
+            amount,
+            quantity,
+        }
+    }
+}
+
+/// Post a journal entry for an account
+///
+/// The entry's lines must balance, or the transaction fails as it commits.
+/// Lines moving nothing are left out. Any cash lines move the account's cash,
+/// & are recorded together as a single cash entry of the same kind.
+pub(super) async fn post(
+    conn: &mut PgConnection,
+    account_id: i32,
+    kind: CashEntryKind,
+    fill_id: Option<i32>,
+    currency: &str,
+    lines: &[Line<'_>],
+) -> Result<(), sqlx::Error> {
+    insert_entry(conn, account_id, kind, fill_id, None, currency, lines).await
+}
+
+/// Post a journal entry for an account that took effect at `effective_at`,
+/// before it's posted
+///
+/// See [`post`]
+pub(super) async fn post_effective(
+    conn: &mut PgConnection,
+    account_id: i32,
+    kind: CashEntryKind,
+    effective_at: OffsetDateTime,
+    currency: &str,
+    lines: &[Line<'_>],
+) -> Result<(), sqlx::Error> {
+    insert_entry(
+        conn,
+        account_id,
+        kind,
+        None,
+        Some(effective_at),
+        currency,
+        lines,
+    )
+    .await
+}
+
+/// Insert a journal entry & its lines, moving the account's cash in the
+/// entry's currency by its cash lines
+async fn insert_entry(
+    conn: &mut PgConnection,
+    account_id: i32,
+    kind: CashEntryKind,
+    fill_id: Option<i32>,
+    effective_at: Option<OffsetDateTime>,
+    currency: &str,
+    lines: &[Line<'_>],
+) -> Result<(), sqlx::Error> {
+    let entry_id = sqlx::query_file_scalar!(
+        "queries/insert_journal_entry.sql",
+        account_id,
+        kind as CashEntryKind,
+        fill_id,
+        effective_at,
+        currency
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let mut cash = None;
+    for line in lines {
+        if line.ledger == LedgerAccount::Cash {
+            *cash.get_or_insert(Decimal::ZERO) += line.amount;
+        }
+        if line.amount.is_zero() && line.quantity.is_zero() {
+            continue;
+        }
+        sqlx::query_file!(
+            "queries/insert_journal_line.sql",
+            entry_id,
+            line.ledger as LedgerAccount,
+            line.symbol,
+            line.amount,
+            line.quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    if let Some(cash) = cash {
+        // cash in the account's base currency is held by the account itself,
+        // and in any other apart from it
+        let account = sqlx::query_file_as!(
+            Account,
+            "queries/update_account_cash.sql",
+            account_id,
+            cash,
+            currency
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        let balance = match account {
+            Some(account) => *account.cash,
+            None => {
+                sqlx::query_file_scalar!(
+                    "queries/upsert_cash_balance.sql",
+                    account_id,
+                    currency,
+                    cash
+                )
+                .fetch_one(&mut *conn)
+                .await?
+            }
+        };
+        insert_cash_entry(conn, account_id, kind, currency, cash, balance).await?;
+    }
+    Ok(())
+}
+
+/// Post journal entries closing every one of an account's ledgers kept for a
+/// symbol, & its cash, leaving it `cash` in its base currency & none in any
+/// other
+///
+/// Whatever the account has made or lost is closed into its capital, in each
+/// currency it was made or lost in.
+pub(super) async fn close(
+    conn: &mut PgConnection,
+    account: &Account,
+    kind: CashEntryKind,
+    cash: Decimal,
+) -> Result<(), sqlx::Error> {
+    let balances = balances(&mut *conn, Some(account.id)).await?;
+    let held = cash_held(&balances, account.id, &account.base_currency);
+    let mut entries: HashMap<&str, Vec<_>> = HashMap::new();
+    entries.insert(&account.base_currency, vec![Line::cash(cash - held)]);
+    for ((_, ledger, symbol, currency), (amount, quantity)) in &balances {
+        let line = match (ledger, symbol) {
+            (LedgerAccount::Cash, _) if *currency == account.base_currency => continue,
+            (LedgerAccount::Cash, _) => Line::cash(-amount),
+            (LedgerAccount::Capital, _) | (_, None) => continue,
+            (_, Some(symbol)) => Line::symbol(*ledger, symbol, -amount, -quantity),
+        };
+        entries.entry(currency).or_default().push(line);
+    }
+    for (currency, mut lines) in entries {
+        let closed = lines.iter().map(|line| line.amount).sum::<Decimal>();
+        lines.push(Line::capital(-closed));
+        post(conn, account.id, kind, None, currency, &lines).await?;
+    }
+    Ok(())
+}
+
+/// Move an account's cash to a new base currency
+///
+/// What it holds in the old one is set aside with its other currencies', and
+/// what it holds in the new one is taken as its cash, each as the journal has
+/// it.
+pub(super) async fn rebase(
+    conn: &mut PgConnection,
+    account: &Account,
+    currency: &str,
+) -> Result<(), sqlx::Error> {
+    let balances = balances(&mut *conn, Some(account.id)).await?;
+    let base = &account.base_currency;
+    sqlx::query_file_scalar!(
+        "queries/upsert_cash_balance.sql",
+        account.id,
+        base,
+        cash_held(&balances, account.id, base)
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query_file_scalar!("queries/delete_cash_balance.sql", account.id, currency)
+        .fetch_optional(&mut *conn)
+        .await?;
+    sqlx::query_file!(
+        "queries/update_account_base_currency.sql",
+        account.id,
+        currency,
+        cash_held(&balances, account.id, currency)
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// The cash an account holds in a currency, as the journal has it
+fn cash_held(balances: &Balances, account_id: i32, currency: &str) -> Decimal {
+    balances
+        .get(&(account_id, LedgerAccount::Cash, None, currency.to_owned()))
+        .map_or(Decimal::ZERO, |(amount, _)| *amount)
+}
+
+/// The journal's balances for an account, or for every account
+async fn balances(
+    conn: &mut PgConnection,
+    account_id: Option<i32>,
+) -> Result<Balances, sqlx::Error> {
+    Ok(
+        sqlx::query_file!("queries/select_ledger_balances.sql", account_id)
+            .fetch_all(conn)
+            .await?
+            .into_iter()
+            .map(|balance| {
+                (
+                    (
+                        balance.account_id,
+                        balance.ledger,
+                        balance.symbol,
+                        balance.currency,
+                    ),
+                    (balance.amount, balance.quantity),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Checks every account's cash, in each currency it holds, against the
+/// journal's, taking it out of the journal's balances
+async fn check_cash(
+    conn: &mut PgConnection,
+    drift: &mut Vec<Drift>,
+    journal: &mut Reconciled,
+) -> Result<(), sqlx::Error> {
+    let accounts = sqlx::query_file!("queries/select_account_balances.sql")
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|account| (account.id, account.base_currency, account.cash));
+    let balances = sqlx::query_file_as!(CashBalance, "queries/select_cash_balances_all.sql")
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|balance| (balance.account_id, balance.currency, *balance.amount));
+    for (account_id, currency, recorded) in accounts.chain(balances) {
+        let currency = Some(currency);
+        let (cash, _) = journal
+            .remove(&(account_id, LedgerAccount::Cash, currency.clone()))
+            .unwrap_or_default();
+        check(drift, account_id, Balance::Cash, currency, cash, recorded);
+    }
+    Ok(())
+}
+
+/// Checks a position's balances against the journal's, taking them out of it
+fn check_position(
+    drift: &mut Vec<Drift>,
+    journal: &mut Reconciled,
+    position: &Position,
+    lots: Decimal,
+) {
+    let mut take = |ledger| {
+        journal
+            .remove(&(position.account_id, ledger, Some(position.symbol.clone())))
+            .unwrap_or_default()
+    };
+    let (cost_basis, quantity) = take(LedgerAccount::Securities);
+    let (realized, _) = take(LedgerAccount::RealizedPnl);
+    let (fees, _) = take(LedgerAccount::Fees);
+    let (borrow_fees, _) = take(LedgerAccount::BorrowFees);
+    let (dividends, _) = take(LedgerAccount::Dividends);
+    for (balance, journal, recorded) in [
+        (Balance::Quantity, quantity, *position.quantity),
+        (Balance::CostBasis, cost_basis, *position.cost_basis),
+        (Balance::RealizedPnl, -realized, *position.realized_pnl),
+        (Balance::Fees, fees, *position.fees),
+        (Balance::BorrowFees, borrow_fees, *position.borrow_fees),
+        (Balance::Dividends, -dividends, *position.dividends),
+        (Balance::Lots, quantity, lots),
+    ] {
+        let symbol = Some(position.symbol.clone());
+        check(
+            drift,
+            position.account_id,
+            balance,
+            symbol,
+            journal,
+            recorded,
+        );
+    }
+}
+
+/// Reports a balance if the journal disagrees with what's recorded
+fn check(
+    drift: &mut Vec<Drift>,
+    account_id: i32,
+    balance: Balance,
+    symbol: Option<String>,
+    journal: Decimal,
+    recorded: Decimal,
+) {
+    if journal != recorded {
+        drift.push(Drift {
+            account_id,
+            balance,
+            symbol,
+            journal,
+            recorded,
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+
+use super::Database;
+use crate::{
+    models::{Account, AccountType, LotMethod, SlippageKind},
+    numeric::Money,
+};
+
+impl Database {
+    /// Get every margin account holding positions, or flagged for a margin
+    /// call
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_margin_accounts(&self) -> Result<Vec<Account>, sqlx::Error> {
+        sqlx::query_file_as!(Account, "queries/select_accounts_margin.sql")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Flag an account for a margin call from `at`, or clear its flag
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn set_margin_call(
+        &self,
+        account_id: i32,
+        at: Option<OffsetDateTime>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query_file!("queries/update_account_margin_call.sql", account_id, at)
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Whether an account has liquidation orders still open
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn is_liquidating(&self, account_id: i32) -> Result<bool, sqlx::Error> {
+        sqlx::query_file_scalar!("queries/select_orders_liquidating.sql", account_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+}
+
+/// The net & gross cost basis of all an account's positions but its futures,
+/// and the initial margin its futures require
+pub(super) async fn position_totals(
+    conn: &mut PgConnection,
+    account_id: i32,
+) -> Result<(Decimal, Decimal, Decimal), sqlx::Error> {
+    let totals = sqlx::query_file!("queries/select_position_totals.sql", account_id)
+        .fetch_one(conn)
+        .await?;
+    Ok((totals.net, totals.gross, totals.futures))
+}
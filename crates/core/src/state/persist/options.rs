@@ -0,0 +1,186 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use time::Date;
+
+use super::{
+    fills::{self, Settlement},
+    orders, Database,
+};
+use crate::{
+    models::{
+        Account, AccountType, AssetClass, Instrument, LotMethod, NewOrder, OptionType, OrderOrigin,
+        OrderSide, OrderStatus, OrderType, Position, SlippageKind, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Get every option contract that expired before `date` & is yet to be
+    /// settled, oldest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_expired_contracts(&self, date: Date) -> Result<Vec<Instrument>, sqlx::Error> {
+        sqlx::query_file_as!(Instrument, "queries/select_contracts_expired.sql", date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Settle every position in an expired option contract, its underlying
+    /// having last traded at `price`
+    ///
+    /// Each position is closed by an expiration order filled at no price. If
+    /// the contract is in the money, long positions are then exercised &
+    /// short positions assigned, by an order for the underlying filled at the
+    /// strike, buying for long calls & short puts and selling otherwise. The
+    /// rest expire worthless. Settlements pay no fees, and aren't checked
+    /// against what their accounts can afford. The contract is delisted, so
+    /// its open orders are cancelled, and everything's written in a single
+    /// transaction.
+    ///
+    /// Returns the number of positions settled, or `None` if the contract
+    /// already was.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn expire_contract(
+        &self,
+        contract: &Instrument,
+        price: Decimal,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if sqlx::query_file_scalar!("queries/update_instrument_expire.sql", contract.symbol)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_none()
+        {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        let positions = sqlx::query_file_as!(
+            Position,
+            "queries/select_positions_held_lock.sql",
+            contract.symbol
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let exercised = contract
+            .underlying
+            .as_ref()
+            .zip(contract.option_type)
+            .zip(contract.strike)
+            .filter(|_| contract.intrinsic_value(price) > Decimal::ZERO);
+        for position in &positions {
+            let account = sqlx::query_file_as!(
+                Account,
+                "queries/select_account_lock.sql",
+                position.account_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let long = position.quantity > Quantity::ZERO;
+            let closing = settlement_order(
+                contract.symbol.clone(),
+                if long {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                },
+                position.quantity.abs(),
+                OrderOrigin::Expiration,
+            );
+            let settlement = Settlement {
+                currency: &contract.currency,
+                rate: Decimal::ONE,
+                multiplier: *contract.multiplier,
+                margin: None,
+            };
+            settle(&mut tx, &account, &closing, Money::ZERO, settlement).await?;
+            let Some(((underlying, option_type), strike)) = exercised else {
+                continue;
+            };
+            // calls buy the underlying & puts sell it, for their holders
+            let buys = (option_type == OptionType::Call) == long;
+            let delivery = settlement_order(
+                underlying.clone(),
+                if buys {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                },
+                Quantity::round(*position.quantity.abs() * *contract.multiplier),
+                if long {
+                    OrderOrigin::Exercise
+                } else {
+                    OrderOrigin::Assignment
+                },
+            );
+            let settlement = Settlement {
+                multiplier: Decimal::ONE,
+                ..settlement
+            };
+            settle(&mut tx, &account, &delivery, strike, settlement).await?;
+        }
+        tx.commit().await?;
+        Ok(Some(positions.len() as u64))
+    }
+}
+
+/// Place an order settling an expired contract or future for an account, and
+/// fill it at `price` with no fee
+pub(super) async fn settle(
+    conn: &mut PgConnection,
+    account: &Account,
+    order: &NewOrder,
+    price: Money,
+    settlement: Settlement<'_>,
+) -> Result<(), sqlx::Error> {
+    let order = orders::insert(conn, account.id, order, None, None, OrderStatus::Open).await?;
+    let quantity = order.quantity;
+    fills::execute(
+        conn,
+        account,
+        &order,
+        quantity,
+        price,
+        Decimal::ZERO,
+        settlement,
+    )
+    .await?;
+    Ok(())
+}
+
+/// A market order settling an expired contract or future, filled as it's
+/// placed
+pub(super) const fn settlement_order(
+    symbol: String,
+    side: OrderSide,
+    quantity: Quantity,
+    origin: OrderOrigin,
+) -> NewOrder {
+    NewOrder {
+        symbol,
+        side,
+        order_type: OrderType::Market,
+        quantity,
+        limit_price: None,
+        stop_price: None,
+        trail_amount: None,
+        trail_percent: None,
+        time_in_force: TimeInForce::Day,
+        expires_at: None,
+        extended_hours: false,
+        origin,
+        quoted_price: None,
+    }
+}
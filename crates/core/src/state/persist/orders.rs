@@ -0,0 +1,245 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::Database;
+use crate::{
+    models::{NewOrder, Order, OrderOrigin, OrderSide, OrderStatus, OrderType, TimeInForce},
+    numeric::{Money, Quantity},
+};
+
+impl Database {
+    /// Place an order for the given account
+    ///
+    /// Trailing stops start trailing `trail_reference`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn add_order(
+        &self,
+        account_id: i32,
+        order: &NewOrder,
+        trail_reference: Option<Money>,
+    ) -> Result<Order, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        insert(
+            &mut conn,
+            account_id,
+            order,
+            trail_reference,
+            None,
+            OrderStatus::Open,
+        )
+        .await
+    }
+
+    /// Get all of an account's orders, newest first
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_orders(&self, account_id: i32) -> Result<Vec<Order>, sqlx::Error> {
+        sqlx::query_file_as!(Order, "queries/select_orders.sql", account_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Get one of a user's orders by its uuid
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_order(&self, user_id: i32, uuid: Uuid) -> Result<Option<Order>, sqlx::Error> {
+        sqlx::query_file_as!(Order, "queries/select_order_uuid.sql", uuid, user_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Cancel one of a user's open or pending orders
+    ///
+    /// Any children waiting on the order, and any siblings it would have
+    /// cancelled by filling, are cancelled with it, as are the other legs of
+    /// a multi-leg group. Returns `None` if no such order is open or pending.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn cancel_order(
+        &self,
+        user_id: i32,
+        uuid: Uuid,
+    ) -> Result<Option<Order>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(order) =
+            sqlx::query_file_as!(Order, "queries/update_order_cancel.sql", uuid, user_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+        cancel_children(&mut tx, &[order.id]).await?;
+        cancel_siblings(&mut tx, &order).await?;
+        if let Some(group_id) = order.group_id {
+            sqlx::query_file_as!(Order, "queries/update_orders_cancel_legs.sql", group_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(Some(order))
+    }
+
+    /// Move an open stop order's stop price & trailing reference, or mark it
+    /// triggered
+    ///
+    /// Returns `None` if the order is no longer open.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn update_order_stop(
+        &self,
+        order: &Order,
+        stop_price: Option<Money>,
+        trail_reference: Option<Money>,
+        triggered_at: Option<OffsetDateTime>,
+    ) -> Result<Option<Order>, sqlx::Error> {
+        sqlx::query_file_as!(
+            Order,
+            "queries/update_order_stop.sql",
+            order.id,
+            stop_price as Option<Money>,
+            trail_reference as Option<Money>,
+            triggered_at
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// The cash reserved by an account's open buys, at their limit or stop
+    /// price, or what market buys were quoted at as they were placed
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn get_open_buy_notional(&self, account_id: i32) -> Result<Decimal, sqlx::Error> {
+        sqlx::query_file_scalar!("queries/select_open_buy_notional.sql", account_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Expire every open order whose expiry is at or before `now`
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    ///
+    /// # Panics
+    ///
+    /// May be possible due to sqlx
+    pub async fn expire_orders(&self, now: OffsetDateTime) -> Result<Vec<Order>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let orders = sqlx::query_file_as!(Order, "queries/update_orders_expire.sql", now)
+            .fetch_all(&mut *tx)
+            .await?;
+        let ids: Vec<_> = orders.iter().map(|order| order.id).collect();
+        cancel_children(&mut tx, &ids).await?;
+        tx.commit().await?;
+        Ok(orders)
+    }
+}
+
+/// Insert an order, into a group & under a parent if given
+pub(super) async fn insert(
+    conn: &mut PgConnection,
+    account_id: i32,
+    order: &NewOrder,
+    trail_reference: Option<Money>,
+    group: Option<(i32, Option<i32>)>,
+    status: OrderStatus,
+) -> Result<Order, sqlx::Error> {
+    let (group_id, parent_id) = group.map_or((None, None), |(group, parent)| (Some(group), parent));
+    sqlx::query_file_as!(
+        Order,
+        "queries/insert_order.sql",
+        Uuid::new_v4(),
+        account_id,
+        order.symbol,
+        order.side as OrderSide,
+        order.order_type as OrderType,
+        order.quantity as Quantity,
+        order.limit_price as Option<Money>,
+        order.stop_price as Option<Money>,
+        order.trail_amount as Option<Money>,
+        order.trail_percent,
+        trail_reference as Option<Money>,
+        order.time_in_force as TimeInForce,
+        order.expires_at,
+        group_id,
+        parent_id,
+        status as OrderStatus,
+        order.extended_hours,
+        order.origin as OrderOrigin,
+        order.quoted_price as Option<Money>
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Cancel the pending children of the given orders
+pub(super) async fn cancel_children(
+    conn: &mut PgConnection,
+    parent_ids: &[i32],
+) -> Result<Vec<Order>, sqlx::Error> {
+    sqlx::query_file_as!(
+        Order,
+        "queries/update_orders_cancel_children.sql",
+        parent_ids
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// Cancel the orders that one-cancels-other with the given order
+pub(super) async fn cancel_siblings(
+    conn: &mut PgConnection,
+    order: &Order,
+) -> Result<Vec<Order>, sqlx::Error> {
+    let Some(group_id) = order.group_id else {
+        return Ok(Vec::new());
+    };
+    sqlx::query_file_as!(
+        Order,
+        "queries/update_orders_cancel_siblings.sql",
+        group_id,
+        order.id,
+        order.parent_id
+    )
+    .fetch_all(conn)
+    .await
+}
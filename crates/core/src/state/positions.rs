@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::Context;
+use crate::{
+    margin,
+    models::{BorrowRate, CorporateAction, Instrument, Lot, Position, PositionSettings},
+    numeric::{Money, Quantity},
+};
+
+/// The most symbols a registry search returns
+pub const INSTRUMENT_SEARCH_LIMIT: i64 = 50;
+
+impl Context {
+    /// Get the positions of one of a user's accounts, valued at the market
+    ///
+    /// Uses the user's default account if `account` is `None`. Closed
+    /// positions are included, for their realized profit. Positions are
+    /// valued in their symbol's currency, and in the account's base currency.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_positions(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+    ) -> Result<Option<Vec<PositionReport>>, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(None);
+        };
+        let mut rates = HashMap::new();
+        let mut reports = Vec::new();
+        for position in self.database.get_positions(account.id).await? {
+            let lots = self
+                .database
+                .get_open_lots(account.id, &position.symbol)
+                .await?;
+            let price = match self.market.quote(&position.symbol).await {
+                Ok(quote) => quote.map(|quote| quote.last),
+                Err(e) => {
+                    warn!(symbol = position.symbol, "failed to quote: {e}");
+                    None
+                }
+            };
+            let instrument = self
+                .database
+                .get_instrument_or_unlisted(&position.symbol)
+                .await?;
+            let currency = instrument.currency;
+            if !rates.contains_key(&currency) {
+                let market = self.market.as_ref();
+                let rate = margin::fx_rate(market, &currency, &account.base_currency).await;
+                rates.insert(currency.clone(), rate);
+            }
+            let rate = rates[&currency];
+            let multiplier = instrument.multiplier;
+            reports.push(PositionReport::new(
+                position,
+                lots,
+                price,
+                currency,
+                rate,
+                &account.base_currency,
+                multiplier,
+            ));
+        }
+        Ok(Some(reports))
+    }
+
+    /// Change the settings of one of a user's accounts for a symbol, whether
+    /// or not it's held
+    ///
+    /// Uses the user's default account if `account` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn update_position_settings(
+        &self,
+        user_id: i32,
+        account: Option<Uuid>,
+        symbol: &str,
+        settings: &PositionSettings,
+    ) -> Result<Option<Position>, sqlx::Error> {
+        let Some(account) = self.get_account_or_default(user_id, account).await? else {
+            return Ok(None);
+        };
+        let symbol = symbol.to_ascii_uppercase();
+        self.database
+            .update_position_settings(account.id, &symbol, settings)
+            .await
+            .map(Some)
+    }
+
+    /// Get every symbol's borrow rate, and whether it can be shorted
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_borrow_rates(&self) -> Result<Vec<BorrowRate>, sqlx::Error> {
+        self.database.get_borrow_rates().await
+    }
+
+    /// Search the symbol registry by symbol or name, delisted symbols
+    /// included
+    ///
+    /// Symbols starting with the query come first, the exact symbol before
+    /// any other, then those whose name contains it. Returns at most
+    /// [`INSTRUMENT_SEARCH_LIMIT`] symbols, the first listed if there's no
+    /// query.
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn search_instruments(
+        &self,
+        query: Option<&str>,
+    ) -> Result<Vec<Instrument>, sqlx::Error> {
+        self.database
+            .search_instruments(query.unwrap_or_default().trim(), INSTRUMENT_SEARCH_LIMIT)
+            .await
+    }
+
+    /// Get every corporate action, or a symbol's, by ex-date
+    ///
+    /// # Errors
+    ///
+    /// See [`sqlx`]
+    pub async fn get_corporate_actions(
+        &self,
+        symbol: Option<&str>,
+    ) -> Result<Vec<CorporateAction>, sqlx::Error> {
+        let symbol = symbol.map(str::to_ascii_uppercase);
+        self.database.get_corporate_actions(symbol.as_deref()).await
+    }
+}
+
+/// A position, valued at the market
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionReport {
+    /// The held symbol
+    pub symbol: String,
+    /// The currency the symbol trades in, and the report's amounts are in
+    pub currency: String,
+    /// The quantity held, negative when short
+    pub quantity: Quantity,
+    /// The total cost of the quantity held, negative when short
+    pub cost_basis: Money,
+    /// The cost per unit held, of the symbol's multiplier, so it compares
+    /// with the price
+    pub average_cost: Option<Decimal>,
+    /// The symbol's last price, if it's quoted
+    pub price: Option<Decimal>,
+    /// The quantity held, at the last price
+    pub market_value: Option<Decimal>,
+    /// The profit or loss of everything closed so far, before fees
+    pub realized_pnl: Money,
+    /// The profit or loss of the quantity held, at the last price
+    pub unrealized_pnl: Option<Decimal>,
+    /// The fees paid trading the symbol so far
+    pub fees: Money,
+    /// The fees paid borrowing the symbol to short it so far
+    pub borrow_fees: Money,
+    /// The dividends received holding the symbol so far, less those paid in
+    /// lieu while short
+    pub dividends: Money,
+    /// Whether dividends are reinvested in the symbol, following the
+    /// account's setting if `None`
+    pub drip: Option<bool>,
+    /// The realized & unrealized profit or loss, before fees
+    pub gross_pnl: Option<Decimal>,
+    /// The realized & unrealized profit or loss, after trading & borrow fees
+    /// and with dividends
+    pub net_pnl: Option<Decimal>,
+    /// The market's units of the account's base currency a unit of the
+    /// symbol's, if it's quoted
+    pub fx_rate: Option<Decimal>,
+    /// The market value, in the account's base currency
+    pub base_market_value: Option<Decimal>,
+    /// The net profit or loss, in the account's base currency
+    pub base_net_pnl: Option<Decimal>,
+    /// The open lots making up the position
+    pub lots: Vec<Lot>,
+}
+
+impl PositionReport {
+    /// Values a position at the given price, in its symbol's `currency`, and
+    /// at the given exchange rate to its account's `base_currency`
+    ///
+    /// Each unit of the position is worth `multiplier` times its price, as
+    /// is its average cost.
+    #[must_use]
+    pub fn new(
+        position: Position,
+        lots: Vec<Lot>,
+        price: Option<Decimal>,
+        currency: String,
+        fx_rate: Option<Decimal>,
+        base_currency: &str,
+        multiplier: Quantity,
+    ) -> Self {
+        let market_value = price.map(|price| price * *position.quantity * *multiplier);
+        let unrealized_pnl = market_value.map(|value| value - *position.cost_basis);
+        let gross_pnl = unrealized_pnl.map(|unrealized| *position.realized_pnl + unrealized);
+        let net_pnl = gross_pnl
+            .map(|gross| gross - *position.fees - *position.borrow_fees + *position.dividends);
+        let in_base =
+            |amount: Option<Decimal>| Some(*Money::round_minor(amount? * fx_rate?, base_currency));
+        Self {
+            currency,
+            fx_rate,
+            base_market_value: in_base(market_value),
+            base_net_pnl: in_base(net_pnl),
+            average_cost: (!position.quantity.is_zero())
+                .then(|| *position.cost_basis / *position.quantity / *multiplier),
+            unrealized_pnl,
+            fees: position.fees,
+            borrow_fees: position.borrow_fees,
+            dividends: position.dividends,
+            drip: position.drip,
+            gross_pnl,
+            net_pnl,
+            symbol: position.symbol,
+            quantity: position.quantity,
+            cost_basis: position.cost_basis,
+            price,
+            market_value,
+            realized_pnl: position.realized_pnl,
+            lots,
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Posting through the journal keeps every balance recorded elsewhere in step
+//! with it
+
+use core_server::{
+    models::{
+        Account, CashBalance, CorporateActionKind, NewCorporateAction, NewOrder, OrderOrigin,
+        OrderSide, OrderType, TimeInForce,
+    },
+    numeric::{Money, Quantity},
+    rust_decimal::Decimal,
+    sqlx::{self, PgPool},
+    state::persist::{Database, FillAction, Settlement},
+    time::{Duration, OffsetDateTime},
+};
+
+const USD: Settlement = Settlement {
+    currency: "USD",
+    rate: Decimal::ONE,
+    multiplier: Decimal::ONE,
+    margin: None,
+};
+
+/// Opens an account depositing 100,000, paying the retail fee schedule
+async fn open(database: &Database) -> Account {
+    let user = database
+        .add_user(
+            "trader",
+            "hashed",
+            "Default",
+            Money::from(100_000),
+            "retail",
+        )
+        .await
+        .unwrap();
+    database.get_accounts(user.id).await.unwrap().remove(0)
+}
+
+/// Places & fills a market order for `symbol` in full at `price`
+async fn trade(
+    database: &Database,
+    account: &Account,
+    symbol: &str,
+    side: OrderSide,
+    quantity: u32,
+    price: u32,
+) {
+    let order = NewOrder {
+        symbol: symbol.to_owned(),
+        side,
+        order_type: OrderType::Market,
+        quantity: Quantity::from(quantity),
+        limit_price: None,
+        stop_price: None,
+        trail_amount: None,
+        trail_percent: None,
+        time_in_force: TimeInForce::default(),
+        expires_at: None,
+        extended_hours: false,
+        origin: OrderOrigin::default(),
+        quoted_price: Some(Money::from(price)),
+    };
+    let order = database.add_order(account.id, &order, None).await.unwrap();
+    let filled = database
+        .fill_order(&order, order.quantity, Money::from(price), USD)
+        .await
+        .unwrap();
+    assert!(matches!(filled, FillAction::Filled(..)));
+}
+
+#[sqlx::test]
+async fn deposit_reconciles(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+
+    assert_eq!(account.cash, Money::from(100_000));
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn fills_and_fees_reconcile(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "AAPL", OrderSide::Buy, 10, 150).await;
+    trade(&database, &account, "AAPL", OrderSide::Sell, 4, 160).await;
+
+    let account = database.get_account_by_id(account.id).await.unwrap();
+    let position = database
+        .get_position(account.id, "AAPL")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(position.quantity, Quantity::from(6));
+    assert!(position.fees > Money::ZERO);
+    assert_eq!(
+        account.cash,
+        Money::from(100_000 - 1500 + 640) - position.fees
+    );
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn dividends_reconcile(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "AAPL", OrderSide::Buy, 10, 150).await;
+    let today = OffsetDateTime::now_utc().date();
+    let action = NewCorporateAction {
+        symbol: "AAPL".to_owned(),
+        kind: CorporateActionKind::Dividend,
+        ex_date: today + Duration::days(1),
+        pay_date: Some(today + Duration::days(2)),
+        ratio: None,
+        amount: Some(Money::new(25, 2)),
+    };
+    database.add_corporate_actions(&[action]).await.unwrap();
+
+    let ex_date = today + Duration::days(1);
+    for action in database.get_due_actions(ex_date).await.unwrap() {
+        assert_eq!(database.declare_dividend(&action).await.unwrap(), Some(1));
+    }
+    let pay_date = today + Duration::days(2);
+    let dividends = database.get_payable_dividends(pay_date).await.unwrap();
+    assert_eq!(dividends.len(), 1);
+    let paid = database
+        .pay_dividend(&dividends[0], None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(paid.amount, Money::new(250, 2));
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn resets_forgo_unpaid_dividends(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "AAPL", OrderSide::Buy, 10, 150).await;
+    let today = OffsetDateTime::now_utc().date();
+    let action = NewCorporateAction {
+        symbol: "AAPL".to_owned(),
+        kind: CorporateActionKind::Dividend,
+        ex_date: today + Duration::days(1),
+        pay_date: Some(today + Duration::days(2)),
+        ratio: None,
+        amount: Some(Money::new(25, 2)),
+    };
+    database.add_corporate_actions(&[action]).await.unwrap();
+    for action in database
+        .get_due_actions(today + Duration::days(1))
+        .await
+        .unwrap()
+    {
+        assert_eq!(database.declare_dividend(&action).await.unwrap(), Some(1));
+    }
+
+    let account = database.reset_account(account.id).await.unwrap();
+
+    let pay_date = today + Duration::days(2);
+    assert!(database
+        .get_payable_dividends(pay_date)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(account.cash, Money::from(100_000));
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn splits_round_and_reconcile(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "AAPL", OrderSide::Buy, 10, 150).await;
+    let today = OffsetDateTime::now_utc().date();
+    let action = NewCorporateAction {
+        symbol: "AAPL".to_owned(),
+        kind: CorporateActionKind::Split,
+        ex_date: today + Duration::days(1),
+        pay_date: None,
+        ratio: Some(Money::round(Decimal::ONE / Decimal::from(3))),
+        amount: None,
+    };
+    database.add_corporate_actions(&[action]).await.unwrap();
+
+    let ex_date = today + Duration::days(1);
+    for action in database.get_due_actions(ex_date).await.unwrap() {
+        assert_eq!(database.apply_split(&action, None).await.unwrap(), Some(1));
+    }
+
+    let position = database
+        .get_position(account.id, "AAPL")
+        .await
+        .unwrap()
+        .unwrap();
+    let lots = database.get_open_lots(account.id, "AAPL").await.unwrap();
+    assert_eq!(position.quantity, Quantity::new(333_333_333, 8));
+    assert_eq!(
+        lots.iter().map(|lot| lot.quantity).sum::<Quantity>(),
+        position.quantity
+    );
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn reverse_splits_pay_cash_in_lieu_of_part_lots(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "GME", OrderSide::Buy, 10, 150).await;
+    let before = database.get_account_by_id(account.id).await.unwrap().cash;
+    let today = OffsetDateTime::now_utc().date();
+    let action = NewCorporateAction {
+        symbol: "GME".to_owned(),
+        kind: CorporateActionKind::Split,
+        ex_date: today + Duration::days(1),
+        pay_date: None,
+        ratio: Some(Money::round(Decimal::ONE / Decimal::from(3))),
+        amount: None,
+    };
+    database.add_corporate_actions(&[action]).await.unwrap();
+
+    let ex_date = today + Duration::days(1);
+    let price = Some(Decimal::from(480));
+    for action in database.get_due_actions(ex_date).await.unwrap() {
+        assert_eq!(database.apply_split(&action, price).await.unwrap(), Some(1));
+    }
+
+    let account = database.get_account_by_id(account.id).await.unwrap();
+    let position = database
+        .get_position(account.id, "GME")
+        .await
+        .unwrap()
+        .unwrap();
+    let lots = database.get_open_lots(account.id, "GME").await.unwrap();
+    assert_eq!(position.quantity, Quantity::from(3));
+    assert_eq!(
+        lots.iter().map(|lot| lot.quantity).sum::<Quantity>(),
+        position.quantity
+    );
+    let in_lieu = Money::round(Decimal::new(33_333_333, 8) * Decimal::from(480));
+    assert_eq!(account.cash, before + in_lieu);
+    assert!(position.realized_pnl > Money::ZERO);
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn interest_reconciles(pool: PgPool) {
+    let database = Database::new(pool).await.unwrap();
+    let account = open(&database).await;
+    let today = OffsetDateTime::now_utc().date();
+    let balance = CashBalance {
+        account_id: account.id,
+        currency: account.base_currency.clone(),
+        amount: account.cash,
+    };
+    let accrued = database
+        .accrue_interest(&balance, today)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(accrued > Decimal::ZERO);
+    assert_eq!(
+        database.accrue_interest(&balance, today).await.unwrap(),
+        None
+    );
+
+    let posted = database
+        .post_interest(
+            account.id,
+            &account.base_currency,
+            today + Duration::days(1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(posted, accrued.round_dp(2));
+    let account = database.get_account_by_id(account.id).await.unwrap();
+    assert_eq!(*account.cash, Decimal::from(100_000) + posted);
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn unbalanced_entries_are_rejected(pool: PgPool) {
+    let database = Database::new(pool.clone()).await.unwrap();
+    let account = open(&database).await;
+    let mut tx = pool.begin().await.unwrap();
+    let entry: i64 = sqlx::query_scalar(
+        "Insert Into journal_entries (account_id, kind, currency) Values ($1, 'deposit', 'USD') \
+         Returning id",
+    )
+    .bind(account.id)
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+    sqlx::query("Insert Into journal_lines (entry_id, ledger, amount) Values ($1, 'cash', 100)")
+        .bind(entry)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    let error = tx.commit().await.unwrap_err();
+
+    assert!(error.to_string().contains("does not balance"));
+    assert!(database.reconcile().await.unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn journal_is_append_only_but_goes_with_its_user(pool: PgPool) {
+    let database = Database::new(pool.clone()).await.unwrap();
+    let account = open(&database).await;
+    trade(&database, &account, "AAPL", OrderSide::Buy, 10, 150).await;
+
+    let edited = sqlx::query("Update journal_lines Set amount = 0")
+        .execute(&pool)
+        .await;
+    assert!(edited.is_err());
+    let fill = sqlx::query("Delete From fills").execute(&pool).await;
+    assert!(fill.is_err());
+
+    sqlx::query("Delete From users Where id = $1")
+        .bind(account.user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let entries: i64 = sqlx::query_scalar("Select Count(*) From journal_entries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(entries, 0);
+}
@@ -0,0 +1,29 @@
+//! Imports a corporate actions csv file into the corporate actions table
+//!
+//! Usage: `import-actions <FILE>`
+
+use std::{env, fs::File};
+
+use core_server::{
+    actions::import_actions,
+    anyhow::{bail, Context, Result},
+    sqlx::PgPool,
+    state::persist::Database,
+    tokio,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let Some(path) = env::args().nth(1) else {
+        bail!("usage: import-actions <FILE>");
+    };
+    let file = File::open(&path).with_context(|| format!("Failed to open {path}"))?;
+
+    let pool = PgPool::connect(env!("DATABASE_URL")).await?;
+    let database = Database::new(pool).await?;
+    let count = import_actions(&database, file)
+        .await
+        .with_context(|| format!("Failed to import {path}"))?;
+    println!("Imported {count} corporate actions");
+    Ok(())
+}
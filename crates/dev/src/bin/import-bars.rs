@@ -0,0 +1,31 @@
+//! Imports an OHLCV csv file into the bars table
+//!
+//! Usage: `import-bars <SYMBOL> <FILE>`
+
+use std::{env, fs::File};
+
+use core_server::{
+    anyhow::{bail, Context, Result},
+    market::import::import_bars,
+    sqlx::PgPool,
+    state::persist::Database,
+    tokio,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let (Some(symbol), Some(path)) = (args.next(), args.next()) else {
+        bail!("usage: import-bars <SYMBOL> <FILE>");
+    };
+    let symbol = symbol.to_ascii_uppercase();
+    let file = File::open(&path).with_context(|| format!("Failed to open {path}"))?;
+
+    let pool = PgPool::connect(env!("DATABASE_URL")).await?;
+    let database = Database::new(pool).await?;
+    let count = import_bars(&database, &symbol, file)
+        .await
+        .with_context(|| format!("Failed to import {path}"))?;
+    println!("Imported {count} {symbol} bars");
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Imports a symbol registry csv file into the instruments table, replacing
+//! the symbols already listed
+//!
+//! Usage: `import-instruments <FILE>`
+
+use std::{env, fs::File};
+
+use core_server::{
+    anyhow::{bail, Context, Result},
+    instruments::import_instruments,
+    sqlx::PgPool,
+    state::persist::Database,
+    tokio,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let Some(path) = env::args().nth(1) else {
+        bail!("usage: import-instruments <FILE>");
+    };
+    let file = File::open(&path).with_context(|| format!("Failed to open {path}"))?;
+
+    let pool = PgPool::connect(env!("DATABASE_URL")).await?;
+    let database = Database::new(pool).await?;
+    let count = import_instruments(&database, file)
+        .await
+        .with_context(|| format!("Failed to import {path}"))?;
+    println!("Imported {count} instruments");
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! Rebuilds every account's balances from the journal, reporting any drift
+//!
+//! Usage: `reconcile`
+//!
+//! Exits with an error if any balance has drifted.
+
+use core_server::{
+    anyhow::{bail, Result},
+    sqlx::PgPool,
+    state::persist::Database,
+    tokio,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let pool = PgPool::connect(env!("DATABASE_URL")).await?;
+    let database = Database::new(pool).await?;
+    let drift = database.reconcile().await?;
+    for drift in &drift {
+        println!(
+            "account {} {:?} {}: journal {}, recorded {}",
+            drift.account_id,
+            drift.balance,
+            drift.symbol.as_deref().unwrap_or("-"),
+            drift.journal,
+            drift.recorded
+        );
+    }
+    if !drift.is_empty() {
+        bail!("{} balances drifted from the journal", drift.len());
+    }
+    println!("Every balance matches the journal");
+    Ok(())
+}
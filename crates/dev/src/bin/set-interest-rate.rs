@@ -0,0 +1,53 @@
+//! Sets the interest cash in a currency earns or pays from a date, so rates
+//! can change over time
+//!
+//! Usage: `set-interest-rate <CURRENCY> <credit|debit> <EFFECTIVE_ON> <ANNUAL_RATE>`
+//!
+//! The effective date is an ISO 8601 date like `2026-01-01`, and the rate is
+//! yearly, in percent.
+
+use std::env;
+
+use core_server::{
+    anyhow::{bail, ensure, Context, Result},
+    models::{is_currency, InterestKind},
+    rust_decimal::Decimal,
+    sqlx::PgPool,
+    state::persist::Database,
+    time::{format_description::well_known::Iso8601, Date},
+    tokio,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let [currency, kind, effective_on, annual_rate] = args.as_slice() else {
+        bail!("usage: set-interest-rate <CURRENCY> <credit|debit> <EFFECTIVE_ON> <ANNUAL_RATE>");
+    };
+    ensure!(is_currency(currency), "invalid currency {currency}");
+    let kind = match kind.as_str() {
+        "credit" => InterestKind::Credit,
+        "debit" => InterestKind::Debit,
+        _ => bail!("invalid kind {kind}, expected credit or debit"),
+    };
+    let effective_on = Date::parse(effective_on, &Iso8601::DEFAULT)
+        .with_context(|| format!("invalid date {effective_on}"))?;
+    let annual_rate: Decimal = annual_rate
+        .parse()
+        .with_context(|| format!("invalid rate {annual_rate}"))?;
+    ensure!(
+        annual_rate >= Decimal::ZERO,
+        "rate {annual_rate} is negative"
+    );
+
+    let pool = PgPool::connect(env!("DATABASE_URL")).await?;
+    let database = Database::new(pool).await?;
+    let rate = database
+        .set_interest_rate(currency, kind, effective_on, annual_rate)
+        .await?;
+    println!(
+        "{} {:?} interest is {}% from {}",
+        rate.currency, rate.kind, rate.annual_rate, rate.effective_on
+    );
+    Ok(())
+}
@@ -1,12 +1,21 @@
 //! Main entrypoint
 #![allow(clippy::wildcard_imports)]
 
-use std::env;
+use std::{env, sync::Arc};
 
-use core_server::*;
+use core_server::{
+    calendar::Calendar,
+    market::{MarketData, OptionPricer, Replay, Synthetic},
+    models::SlippageKind,
+    slippage::{self, SlippageModel, Spread},
+    state::persist::Database,
+    *,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use rust_decimal::Decimal;
 use sqlx::PgPool;
+use time::{format_description::well_known::Iso8601, Date};
 use tokio::net::TcpListener;
 use tower_sessions::cookie::Key;
 use tracing::info;
@@ -32,7 +41,10 @@ pub async fn serve() -> Result<()> {
     let key = Key::from(&[8; 64]);
     signal::scroll();
     let pool = PgPool::connect(env!("DATABASE_URL")).await?;
-    let app = router(key, pool).await.context("Failed to create router")?;
+    let market = market(&pool).await?;
+    let app = router(key, pool, market, calendar(), slippage()?)
+        .await
+        .context("Failed to create router")?;
     let listener = TcpListener::bind(concat!("127.0.0.1:", env!("SERVER_PORT"))).await?;
     let address = listener.local_addr()?;
 
@@ -46,3 +58,69 @@ pub async fn serve() -> Result<()> {
     info!("dev process ending");
     Ok(())
 }
+
+/// The market to trade against
+///
+/// Replays the imported bars from the date in `REPLAY_FROM`, formatted
+/// `YYYY-MM-DD`, if set, and otherwise uses the synthetic feed, moving crypto
+/// pairs at the annual volatility in `CRYPTO_VOLATILITY`, 80% unless set.
+/// Option contracts are priced off either, at the annual volatility in
+/// `OPTION_VOLATILITY`, 30% unless set.
+async fn market(pool: &PgPool) -> Result<Arc<dyn MarketData>> {
+    let volatility = env::var("OPTION_VOLATILITY")
+        .ok()
+        .map(|volatility| volatility.parse::<f64>())
+        .transpose()
+        .context("Invalid OPTION_VOLATILITY")?
+        .unwrap_or(0.3);
+    let Ok(from) = env::var("REPLAY_FROM") else {
+        let crypto = env::var("CRYPTO_VOLATILITY")
+            .ok()
+            .map(|volatility| volatility.parse::<f64>())
+            .transpose()
+            .context("Invalid CRYPTO_VOLATILITY")?
+            .unwrap_or(0.8);
+        let market = Arc::new(Synthetic::default().with_crypto_volatility(crypto));
+        return Ok(Arc::new(OptionPricer::new(market, volatility)));
+    };
+    let from = Date::parse(&from, &Iso8601::DATE)
+        .context("Invalid REPLAY_FROM")?
+        .midnight()
+        .assume_utc();
+    info!("Replaying bars from {from}");
+    let market = Arc::new(Replay::new(Database::new(pool.clone()).await?, from));
+    Ok(Arc::new(OptionPricer::new(market, volatility)))
+}
+
+/// The calendar to trade during
+///
+/// Uses the bundled calendar, unless `ALWAYS_OPEN` is set, so orders fill at
+/// any hour.
+fn calendar() -> Arc<Calendar> {
+    if env::var_os("ALWAYS_OPEN").is_some() {
+        info!("Trading around the clock");
+        return Arc::new(Calendar::around_the_clock());
+    }
+    Arc::new(Calendar::bundled())
+}
+
+/// The slippage model for accounts that don't pick their own
+///
+/// Uses the model named in `SLIPPAGE`, one of `last`, `fixed`, `spread` or
+/// `volume`, slipping by `SLIPPAGE_BPS` if set. Defaults to the spread.
+fn slippage() -> Result<Arc<dyn SlippageModel>> {
+    let kind = match env::var("SLIPPAGE").as_deref() {
+        Err(_) | Ok("spread") => SlippageKind::Spread,
+        Ok("last") => SlippageKind::Last,
+        Ok("fixed") => SlippageKind::Fixed,
+        Ok("volume") => SlippageKind::Volume,
+        Ok(other) => bail!("Unknown SLIPPAGE {other}"),
+    };
+    let bps = env::var("SLIPPAGE_BPS")
+        .ok()
+        .map(|bps| bps.parse::<Decimal>())
+        .transpose()
+        .context("Invalid SLIPPAGE_BPS")?;
+    info!("Slipping fills by the {kind:?} model");
+    Ok(slippage::model(kind, bps).unwrap_or_else(|| Arc::new(Spread)))
+}
@@ -1,10 +1,15 @@
 //! Creates a prod binary
 
+use std::sync::Arc;
+
 use core_server::{
     axum::{
         http::{header, StatusCode, Uri},
         response::{Html, IntoResponse, Response},
     },
+    calendar::Calendar,
+    market::MarketData,
+    slippage::Spread,
     sqlx::PgPool,
     tower_sessions::cookie::Key,
     App, CreateRouterError,
@@ -19,16 +24,21 @@ use rust_embed::Embed;
 #[folder = "$CARGO_MANIFEST_DIR/../../client/dist"]
 pub struct Assets;
 
-pub use core_server::sqlx;
+pub use core_server::{market, sqlx};
 
-/// Creates a production ready router
+/// Creates a production ready router, trading against the given market
+/// during the bundled calendar's sessions, filling across the spread
+///
+/// Option contracts only trade if the market prices them, so wrap it in an
+/// [`OptionPricer`](market::OptionPricer).
 ///
 /// # Errors
 ///
 /// See [`core_server::router`]
-pub async fn router(pool: PgPool) -> Result<App, CreateRouterError> {
+pub async fn router(pool: PgPool, market: Arc<dyn MarketData>) -> Result<App, CreateRouterError> {
     let key = Key::generate();
-    let mut app = core_server::router(key, pool).await?;
+    let calendar = Arc::new(Calendar::bundled());
+    let mut app = core_server::router(key, pool, market, calendar, Arc::new(Spread)).await?;
     app.router = app.router.fallback(static_handler);
     Ok(app)
 }
@@ -1,6 +1,11 @@
 //! The shuttle runtime for the server
 
-use prod_server::sqlx::PgPool;
+use std::sync::Arc;
+
+use prod_server::{
+    market::{OptionPricer, Synthetic},
+    sqlx::PgPool,
+};
 
 #[allow(clippy::unused_async)]
 #[shuttle_runtime::main]
@@ -8,7 +13,11 @@ async fn main(
     #[shuttle_shared_db::Postgres(local_uri = "{secrets.LOCAL_DB_URL}")] url: String,
 ) -> shuttle_axum::ShuttleAxum {
     let pool = PgPool::connect(&url).await.map_err(map_err)?;
-    Ok(prod_server::router(pool)
+    // TODO: plug in a live feed
+    let feed = Arc::new(Synthetic::default());
+    // option contracts priced at 30% annual volatility
+    let market = Arc::new(OptionPricer::new(feed, 0.3));
+    Ok(prod_server::router(pool, market)
         .await
         .map_err(map_err)?
         .router